@@ -2,6 +2,16 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod bill;
+pub mod bill_share;
+pub mod chore;
+pub mod flatmate;
+pub mod guild_config;
 pub mod list;
 pub mod list_item;
+pub mod listing;
+pub mod maps_cache;
 pub mod payment;
+pub mod recurring_bill;
+pub mod reminder;
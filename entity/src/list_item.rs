@@ -24,6 +24,7 @@ pub struct Model {
     pub personal: bool,
     pub store: Option<String>,
     pub notes: Option<String>,
+    pub assigned_to: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -39,6 +40,7 @@ pub enum Column {
     Personal,
     Store,
     Notes,
+    AssignedTo,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -73,6 +75,7 @@ impl ColumnTrait for Column {
             Self::Personal => ColumnType::Boolean.def(),
             Self::Store => ColumnType::String(None).def().null(),
             Self::Notes => ColumnType::String(None).def().null(),
+            Self::AssignedTo => ColumnType::BigInteger.def().null(),
         }
     }
 }
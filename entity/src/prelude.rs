@@ -1,5 +1,15 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.6
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::bill::Entity as Bill;
+pub use super::bill_share::Entity as BillShare;
+pub use super::chore::Entity as Chore;
+pub use super::flatmate::Entity as Flatmate;
+pub use super::guild_config::Entity as GuildConfig;
 pub use super::list::Entity as List;
 pub use super::list_item::Entity as ListItem;
+pub use super::listing::Entity as Listing;
+pub use super::maps_cache::Entity as MapsCache;
 pub use super::payment::Entity as Payment;
+pub use super::recurring_bill::Entity as RecurringBill;
+pub use super::reminder::Entity as Reminder;
@@ -1,12 +1,66 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20230601_000001_create_bill_table;
+mod m20230601_000002_create_maps_cache_table;
+mod m20230601_000003_create_listing_table;
+mod m20230601_000004_add_list_item_assigned_to;
+mod m20230601_000005_create_flatmate_table;
+mod m20230601_000006_add_flatmate_removed_at;
+mod m20230601_000007_create_chore_table;
+mod m20230601_000008_create_reminder_table;
+mod m20230601_000009_create_recurring_bill_table;
+mod m20230601_000010_create_guild_config_table;
+mod m20230601_000011_add_flatmate_weekly_income;
+mod m20230601_000012_add_guild_config_thread_template;
+mod m20230601_000013_add_bill_share_note;
+mod m20230601_000014_add_guild_config_settings;
+mod m20230601_000015_add_guild_config_listings_channel;
+mod m20230601_000016_add_guild_config_commands_hash;
+mod m20230601_000017_add_guild_config_summary_posted;
+mod m20230601_000018_add_maps_cache_raw_address;
+mod m20230601_000019_add_guild_config_currency;
+mod m20230601_000020_create_audit_log_table;
+mod m20230601_000021_add_guild_config_branding;
+mod m20230601_000022_add_listing_distance_message;
+mod m20230601_000023_add_bill_category;
+mod m20230601_000024_add_guild_config_locale;
+mod m20230601_000025_add_guild_config_admin_role;
+mod m20230601_000026_add_guild_config_reminder_channel;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20230601_000001_create_bill_table::Migration),
+            Box::new(m20230601_000002_create_maps_cache_table::Migration),
+            Box::new(m20230601_000003_create_listing_table::Migration),
+            Box::new(m20230601_000004_add_list_item_assigned_to::Migration),
+            Box::new(m20230601_000005_create_flatmate_table::Migration),
+            Box::new(m20230601_000006_add_flatmate_removed_at::Migration),
+            Box::new(m20230601_000007_create_chore_table::Migration),
+            Box::new(m20230601_000008_create_reminder_table::Migration),
+            Box::new(m20230601_000009_create_recurring_bill_table::Migration),
+            Box::new(m20230601_000010_create_guild_config_table::Migration),
+            Box::new(m20230601_000011_add_flatmate_weekly_income::Migration),
+            Box::new(m20230601_000012_add_guild_config_thread_template::Migration),
+            Box::new(m20230601_000013_add_bill_share_note::Migration),
+            Box::new(m20230601_000014_add_guild_config_settings::Migration),
+            Box::new(m20230601_000015_add_guild_config_listings_channel::Migration),
+            Box::new(m20230601_000016_add_guild_config_commands_hash::Migration),
+            Box::new(m20230601_000017_add_guild_config_summary_posted::Migration),
+            Box::new(m20230601_000018_add_maps_cache_raw_address::Migration),
+            Box::new(m20230601_000019_add_guild_config_currency::Migration),
+            Box::new(m20230601_000020_create_audit_log_table::Migration),
+            Box::new(m20230601_000021_add_guild_config_branding::Migration),
+            Box::new(m20230601_000022_add_listing_distance_message::Migration),
+            Box::new(m20230601_000023_add_bill_category::Migration),
+            Box::new(m20230601_000024_add_guild_config_locale::Migration),
+            Box::new(m20230601_000025_add_guild_config_admin_role::Migration),
+            Box::new(m20230601_000026_add_guild_config_reminder_channel::Migration),
+        ]
     }
 }
@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Bill {
+    Table,
+    Id,
+    Purpose,
+    Total,
+    Account,
+    ReceiptUrl,
+    CreatedBy,
+    CreatedAt,
+
+    MessageId,
+    ChannelId,
+    GuildId,
+}
+
+#[derive(Iden)]
+enum BillShare {
+    Table,
+    Id,
+    BillId,
+    UserId,
+    Amount,
+    Paid,
+    PaidAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Bill::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Bill::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Bill::Purpose).string().not_null())
+                    .col(ColumnDef::new(Bill::Total).big_integer().not_null())
+                    .col(ColumnDef::new(Bill::Account).string().not_null())
+                    .col(ColumnDef::new(Bill::ReceiptUrl).string().not_null())
+                    .col(ColumnDef::new(Bill::CreatedBy).big_integer().not_null())
+                    .col(ColumnDef::new(Bill::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(Bill::MessageId).big_integer().not_null())
+                    .col(ColumnDef::new(Bill::ChannelId).big_integer().not_null())
+                    .col(ColumnDef::new(Bill::GuildId).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BillShare::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BillShare::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BillShare::BillId).integer().not_null())
+                    .col(ColumnDef::new(BillShare::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(BillShare::Amount).big_integer().not_null())
+                    .col(ColumnDef::new(BillShare::Paid).boolean().not_null())
+                    .col(ColumnDef::new(BillShare::PaidAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKeyCreateStatement::new()
+                    .from_tbl(BillShare::Table)
+                    .from_col(BillShare::BillId)
+                    .to_tbl(Bill::Table)
+                    .to_col(Bill::Id)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BillShare::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Bill::Table).to_owned())
+            .await
+    }
+}
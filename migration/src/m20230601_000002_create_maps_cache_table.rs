@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum MapsCache {
+    Table,
+    Id,
+    Address,
+    Mode,
+    Destinations,
+    Response,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MapsCache::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MapsCache::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MapsCache::Address).string().not_null())
+                    .col(ColumnDef::new(MapsCache::Mode).string().not_null())
+                    .col(ColumnDef::new(MapsCache::Destinations).string().not_null())
+                    .col(ColumnDef::new(MapsCache::Response).text().not_null())
+                    .col(ColumnDef::new(MapsCache::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-maps_cache-address")
+                    .table(MapsCache::Table)
+                    .col(MapsCache::Address)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MapsCache::Table).to_owned())
+            .await
+    }
+}
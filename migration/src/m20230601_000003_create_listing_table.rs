@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Listing {
+    Table,
+    Id,
+    ListingId,
+    Url,
+    Address,
+    ChannelId,
+    MessageId,
+    ThreadId,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Listing::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Listing::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Listing::ListingId).big_integer().not_null())
+                    .col(ColumnDef::new(Listing::Url).string().not_null())
+                    .col(ColumnDef::new(Listing::Address).string().not_null())
+                    .col(ColumnDef::new(Listing::ChannelId).big_integer().not_null())
+                    .col(ColumnDef::new(Listing::MessageId).big_integer().not_null())
+                    .col(ColumnDef::new(Listing::ThreadId).big_integer().not_null())
+                    .col(ColumnDef::new(Listing::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-listing-channel_id-listing_id")
+                    .table(Listing::Table)
+                    .col(Listing::ChannelId)
+                    .col(Listing::ListingId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Listing::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum ListItem {
+    Table,
+    AssignedTo,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ListItem::Table)
+                    .add_column(ColumnDef::new(ListItem::AssignedTo).big_integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ListItem::Table)
+                    .drop_column(ListItem::AssignedTo)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Flatmate {
+    Table,
+    Id,
+    DiscordId,
+    Name,
+    DisplayName,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Flatmate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Flatmate::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Flatmate::DiscordId)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Flatmate::Name).string().not_null())
+                    .col(ColumnDef::new(Flatmate::DisplayName).string().not_null())
+                    .col(ColumnDef::new(Flatmate::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Flatmate::Table).to_owned())
+            .await
+    }
+}
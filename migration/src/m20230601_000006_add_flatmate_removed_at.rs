@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Flatmate {
+    Table,
+    RemovedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Flatmate::Table)
+                    .add_column(ColumnDef::new(Flatmate::RemovedAt).date_time().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Flatmate::Table)
+                    .drop_column(Flatmate::RemovedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
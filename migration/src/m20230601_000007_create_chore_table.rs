@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Chore {
+    Table,
+    Id,
+    GuildId,
+    Name,
+    FrequencyWeeks,
+    Rotation,
+    AssignedTo,
+    LastAssigned,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Chore::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Chore::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Chore::GuildId).big_integer().not_null())
+                    .col(ColumnDef::new(Chore::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Chore::FrequencyWeeks)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(
+                        ColumnDef::new(Chore::Rotation)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Chore::AssignedTo).big_integer().null())
+                    .col(ColumnDef::new(Chore::LastAssigned).date().null())
+                    .col(ColumnDef::new(Chore::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Chore::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Reminder {
+    Table,
+    Id,
+    GuildId,
+    ChannelId,
+    AuthorId,
+    Message,
+    RemindAt,
+    PingEveryone,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reminder::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Reminder::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Reminder::GuildId).big_integer().not_null())
+                    .col(ColumnDef::new(Reminder::ChannelId).big_integer().not_null())
+                    .col(ColumnDef::new(Reminder::AuthorId).big_integer().not_null())
+                    .col(ColumnDef::new(Reminder::Message).string().not_null())
+                    .col(ColumnDef::new(Reminder::RemindAt).date_time().not_null())
+                    .col(
+                        ColumnDef::new(Reminder::PingEveryone)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Reminder::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Reminder::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum RecurringBill {
+    Table,
+    Id,
+    GuildId,
+    ChannelId,
+    CreatedBy,
+    Purpose,
+    Total,
+    Account,
+    DayOfMonth,
+    Paused,
+    LastPosted,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecurringBill::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecurringBill::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RecurringBill::GuildId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecurringBill::ChannelId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecurringBill::CreatedBy)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RecurringBill::Purpose).string().not_null())
+                    .col(
+                        ColumnDef::new(RecurringBill::Total)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RecurringBill::Account).string().not_null())
+                    .col(
+                        ColumnDef::new(RecurringBill::DayOfMonth)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecurringBill::Paused)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(RecurringBill::LastPosted).date().null())
+                    .col(
+                        ColumnDef::new(RecurringBill::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecurringBill::Table).to_owned())
+            .await
+    }
+}
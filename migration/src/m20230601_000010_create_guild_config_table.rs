@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum GuildConfig {
+    Table,
+    Id,
+    GuildId,
+    Units,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildConfig::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildConfig::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildConfig::GuildId)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildConfig::Units)
+                            .string()
+                            .not_null()
+                            .default("metric"),
+                    )
+                    .col(
+                        ColumnDef::new(GuildConfig::UpdatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildConfig::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum BillShare {
+    Table,
+    Note,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BillShare::Table)
+                    .add_column(ColumnDef::new(BillShare::Note).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BillShare::Table)
+                    .drop_column(BillShare::Note)
+                    .to_owned(),
+            )
+            .await
+    }
+}
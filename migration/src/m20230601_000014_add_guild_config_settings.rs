@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum GuildConfig {
+    Table,
+    ListingTimeout,
+    ChoreChannel,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .add_column(
+                        ColumnDef::new(GuildConfig::ListingTimeout)
+                            .big_integer()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(GuildConfig::ChoreChannel)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .drop_column(GuildConfig::ListingTimeout)
+                    .drop_column(GuildConfig::ChoreChannel)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum GuildConfig {
+    Table,
+    ListingsChannel,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .add_column(
+                        ColumnDef::new(GuildConfig::ListingsChannel)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .drop_column(GuildConfig::ListingsChannel)
+                    .to_owned(),
+            )
+            .await
+    }
+}
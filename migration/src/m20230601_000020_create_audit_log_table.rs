@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Id,
+    GuildId,
+    UserId,
+    Command,
+    CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::GuildId).big_integer().null())
+                    .col(ColumnDef::new(AuditLog::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(AuditLog::Command).string().not_null())
+                    .col(ColumnDef::new(AuditLog::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum GuildConfig {
+    Table,
+    BrandingAuthor,
+    BrandingThumbnail,
+    BrandingFooter,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .add_column(ColumnDef::new(GuildConfig::BrandingAuthor).string().null())
+                    .add_column(
+                        ColumnDef::new(GuildConfig::BrandingThumbnail)
+                            .string()
+                            .null(),
+                    )
+                    .add_column(ColumnDef::new(GuildConfig::BrandingFooter).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildConfig::Table)
+                    .drop_column(GuildConfig::BrandingAuthor)
+                    .drop_column(GuildConfig::BrandingThumbnail)
+                    .drop_column(GuildConfig::BrandingFooter)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Listing {
+    Table,
+    DistanceMessageId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Listing::Table)
+                    .add_column(
+                        ColumnDef::new(Listing::DistanceMessageId)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Listing::Table)
+                    .drop_column(Listing::DistanceMessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Bill {
+    Table,
+    Category,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Bill::Table)
+                    .add_column(
+                        ColumnDef::new(Bill::Category)
+                            .string()
+                            .not_null()
+                            .default("uncategorized"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Bill::Table)
+                    .drop_column(Bill::Category)
+                    .to_owned(),
+            )
+            .await
+    }
+}
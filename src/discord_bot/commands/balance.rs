@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serenity::{
+    all::CommandInteraction,
+    async_trait,
+    builder::{CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::bills::{BillModel, BillShareModel, SerenityBillDatabase},
+    },
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    util::{display_name_for, guild_branding, guild_currency, CommandResponse},
+};
+
+/// work out the net position of each flatmate in cents across every provided bill, positive when
+/// they are owed money and negative when they owe money. The creator of a bill fronted the money,
+/// so is owed every unpaid share on it.
+pub(super) fn net_balances(bills: &[(BillModel, Vec<BillShareModel>)]) -> HashMap<u64, i64> {
+    let mut balances: HashMap<u64, i64> = HashMap::new();
+    for (bill, shares) in bills.iter() {
+        for share in shares
+            .iter()
+            .filter(|s| !s.paid && s.user_id != bill.created_by)
+        {
+            *balances.entry(bill.created_by as u64).or_default() += share.amount;
+            *balances.entry(share.user_id as u64).or_default() -= share.amount;
+        }
+    }
+    balances
+}
+
+pub struct BalanceCommand;
+
+impl<'a> TryFrom<&'a CommandInteraction> for BalanceCommand {
+    type Error = String;
+    fn try_from(_: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for BalanceCommand {
+    fn name() -> &'static str {
+        "balance"
+    }
+
+    fn description() -> &'static str {
+        "Show how much each flatmate owes, or is owed, across every bill"
+    }
+
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
+        i
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let bills = match app_state
+            .get_bills_for_guild(interaction.guild_id.map(|g| g.0.into()))
+            .await
+        {
+            Ok(bills) => bills,
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        let mut balances = net_balances(&bills);
+
+        // everyone currently living in the flat is shown, even if they are square
+        for flatmate in app_state.flatmates().iter() {
+            balances.entry(flatmate.discord_id).or_default();
+        }
+
+        // whoever owes the most is shown first
+        let mut balances: Vec<(u64, i64)> = balances.into_iter().collect();
+        balances.sort_by_key(|(id, balance)| (*balance, *id));
+
+        let behind = balances.iter().any(|(_, balance)| *balance < 0);
+        let flatmates = app_state.all_flatmates();
+        let mut embed = branding.embed().title("Balances").color(if behind {
+            EmbedColor::Orange as u32
+        } else {
+            EmbedColor::Green as u32
+        });
+
+        if balances.is_empty() {
+            embed = embed.description("There are no flatmates, add one with /flatmate add");
+        }
+
+        for (id, balance) in balances {
+            let status = match balance {
+                b if b < 0 => format!("owes {}", format_money(-b, currency)),
+                b if b > 0 => format!("is owed {}", format_money(b, currency)),
+                _ => String::from("is square"),
+            };
+            embed = embed.field(display_name_for(&flatmates, id), status, false);
+        }
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(embed),
+            ),
+        ))
+    }
+}
@@ -0,0 +1,319 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use log::{error, info};
+use serenity::{
+    all::{
+        ChannelId, CommandInteraction, CommandOptionType, GuildId, ResolvedOption, ResolvedValue,
+    },
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::embed::EmbedColor,
+        database::{
+            chores::{ChoreModel, SerenityChoreDatabase},
+            guild_config::SerenityGuildConfigDatabase,
+        },
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::Command,
+    util::{display_name_for, guild_branding, paginate, Branding, CommandResponse},
+};
+
+/// the number of chores shown on each page of /chore list
+const CHORES_PER_PAGE: usize = 10;
+
+pub enum Chore<'a> {
+    Add { name: &'a str, frequency_weeks: i64 },
+    List,
+}
+
+/// describe how often a chore is done, in plain english
+fn frequency_label(frequency_weeks: i32) -> String {
+    match frequency_weeks {
+        1 => String::from("weekly"),
+        2 => String::from("fortnightly"),
+        4 => String::from("monthly"),
+        n => format!("every {} weeks", n),
+    }
+}
+
+/// the monday starting the week containing the provided date
+fn week_starting(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// check if a chore needs to move along the rota for the week starting `week`
+fn is_due(chore: &ChoreModel, week: NaiveDate) -> bool {
+    match chore.last_assigned {
+        Some(last) => (week - last).num_weeks() >= chore.frequency_weeks as i64,
+        None => true,
+    }
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for Chore<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        match options.into_iter().next() {
+            Some(ResolvedOption {
+                name: "add",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => {
+                let mut name = None;
+                let mut frequency_weeks = 1;
+
+                for option in options {
+                    match (option.name, option.value) {
+                        ("name", ResolvedValue::String(val)) => name = Some(val),
+                        ("frequency", ResolvedValue::Integer(val)) => frequency_weeks = val,
+                        _ => {}
+                    }
+                }
+
+                Ok(Chore::Add {
+                    name: name.ok_or("name is required")?,
+                    frequency_weeks,
+                })
+            }
+            Some(ResolvedOption { name: "list", .. }) => Ok(Chore::List),
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for Chore<'a> {
+    fn name() -> &'static str {
+        "chore"
+    }
+
+    fn description() -> &'static str {
+        "manage the chores shared around the flat"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        let add = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "add",
+            "add a chore to the rota",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "name", "The chore to be done")
+                .required(true)
+                .max_length(100),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "frequency",
+                "How often the chore needs doing, defaults to weekly",
+            )
+            .required(false)
+            .add_int_choice("Weekly", 1)
+            .add_int_choice("Fortnightly", 2)
+            .add_int_choice("Monthly", 4),
+        );
+
+        let list = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show every chore, and who is doing it",
+        );
+
+        cmd.add_option(add).add_option(list)
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let guild_id: u64 = match interaction.guild_id {
+            Some(guild_id) => guild_id.0.into(),
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "chores can only be managed in a server",
+                )))
+            }
+        };
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+
+        match self {
+            Chore::Add {
+                name,
+                frequency_weeks,
+            } => {
+                let chore = match app_state
+                    .add_chore(guild_id, name, frequency_weeks as i32)
+                    .await
+                {
+                    Ok(chore) => chore,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new().embed(
+                            branding
+                                .embed()
+                                .description(format!(
+                                    "Added {} to the rota, to be done {}",
+                                    chore.name,
+                                    frequency_label(chore.frequency_weeks)
+                                ))
+                                .color(EmbedColor::Green as u32),
+                        ),
+                    ),
+                ))
+            }
+            Chore::List => {
+                let chores = match app_state.get_chores(guild_id).await {
+                    Ok(chores) => chores,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if chores.is_empty() {
+                    return Err(CommandResponse::BasicFailure(String::from(
+                        "there are no chores, add one with /chore add",
+                    )));
+                }
+
+                let flatmates = app_state.all_flatmates();
+                let lines: Vec<String> = chores
+                    .iter()
+                    .map(|chore| {
+                        format!(
+                            "**{}** ({}) - {}",
+                            chore.name,
+                            frequency_label(chore.frequency_weeks),
+                            match chore.assigned_to {
+                                Some(id) => display_name_for(&flatmates, id as u64),
+                                None => String::from("not yet assigned"),
+                            }
+                        )
+                    })
+                    .collect();
+
+                let (embed, components) = paginate(
+                    interaction.id.into(),
+                    "Chores",
+                    EmbedColor::Green as u32,
+                    &branding,
+                    &lines,
+                    CHORES_PER_PAGE,
+                );
+
+                Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .embed(embed)
+                            .components(components),
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// move every chore which is due this week to the next flatmate in the rota, and post the
+/// assignments to the configured chore channel. Chores are only ever rotated once per week, so
+/// this is safe to call repeatedly
+pub async fn rotate_chores(guild: GuildId, app_state: &AppState, ctx: &Context) {
+    let week = week_starting(Local::now().date_naive());
+
+    let chores = match app_state.get_chores(guild.0.into()).await {
+        Ok(chores) => chores,
+        Err(e) => {
+            error!("unable to load chores for guild {}: {}", guild, e);
+            return;
+        }
+    };
+
+    let flatmates = app_state.flatmates();
+    if flatmates.is_empty() {
+        return;
+    }
+
+    let mut assignments = vec![];
+    for chore in chores.into_iter().filter(|chore| is_due(chore, week)) {
+        // a chore which has never been assigned starts at the front of the rota
+        let rotation = if chore.last_assigned.is_some() {
+            chore.rotation + 1
+        } else {
+            chore.rotation
+        };
+        let flatmate = &flatmates[rotation as usize % flatmates.len()];
+
+        match app_state
+            .assign_chore(chore, rotation, flatmate.discord_id, week)
+            .await
+        {
+            Ok(chore) => {
+                assignments.push(format!("**{}** - <@{}>", chore.name, flatmate.discord_id))
+            }
+            Err(e) => error!("unable to assign chore in guild {}: {}", guild, e),
+        }
+    }
+
+    if assignments.is_empty() {
+        return;
+    }
+
+    // the guild's own chore channel takes priority over the one in the config file
+    let (guild_channel, branding) = match app_state.get_guild_config(guild.0.into()).await {
+        Ok(config) => (
+            config.as_ref().and_then(|config| config.chore_channel),
+            Branding::from_config(config.as_ref()),
+        ),
+        Err(e) => {
+            error!("unable to load config for guild {}: {}", guild, e);
+            (None, Branding::default())
+        }
+    };
+
+    let channel = match guild_channel.map(|c| c as u64).or(CONFIG.chore_channel) {
+        Some(channel) => ChannelId::new(channel),
+        None => {
+            info!("no chore channel configured, not announcing chores");
+            return;
+        }
+    };
+
+    if let Err(e) = channel
+        .send_message(
+            ctx,
+            CreateMessage::new().embed(
+                branding
+                    .embed()
+                    .title(format!("Chores for the week of {}", week.format("%-d %B")))
+                    .description(assignments.join("\n"))
+                    .color(EmbedColor::Green as u32),
+            ),
+        )
+        .await
+    {
+        error!("unable to announce chores in guild {}: {}", guild, e);
+    }
+}
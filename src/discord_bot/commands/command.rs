@@ -1,26 +1,62 @@
+use std::time::{Duration, Instant};
+
 use serenity::{
-    all::{AutocompleteOption, CommandInteraction, ComponentInteraction},
+    all::{AutocompleteOption, CommandInteraction, ComponentInteraction, ModalInteraction},
     async_trait,
-    builder::{CreateAutocompleteResponse, CreateCommand},
+    builder::{
+        CreateAutocompleteResponse, CreateCommand, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
     model::{application::CommandType, Permissions},
     prelude::Context,
 };
 
 use crate::{
-    discord_bot::commands::{
-        hide::HideCommand,
-        pay::{PayAllCommand, PayCommand},
-        ping::PingCommand,
-        say::SayCommand,
-        shop::Shop,
+    discord_bot::{
+        commands::{
+            balance::BalanceCommand,
+            chore::Chore,
+            compare::CompareCommand,
+            config::ConfigCommand,
+            distance::DistanceCommand,
+            feedback::FeedbackCommand,
+            flatmate::FlatmateCommand,
+            help::HelpCommand,
+            hide::HideCommand,
+            history::HistoryCommand,
+            pay::{PayAllCommand, PayCommand},
+            ping::PingCommand,
+            recurring::Recurring,
+            refresh_distance::RefreshDistanceCommand,
+            remind::Remind,
+            say::SayCommand,
+            shop::Shop,
+            spending::SpendingCommand,
+            whoami::WhoamiCommand,
+        },
+        common::progress::Progress,
     },
     state::AppState,
 };
 
-use super::util::CommandResponse;
+use super::util::{handle_page_interaction, is_page_interaction, CommandResponse};
 
 const DEFAULT_PERMISSIONS: Permissions = Permissions::ADMINISTRATOR;
 
+/// how the response to a command is sent to discord
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ResponseMode {
+    /// the command responds within discord's three second window
+    #[default]
+    Immediate,
+    /// the interaction is acknowledged before the command runs, and its response is sent as a
+    /// followup once it completes, for commands that may take longer than three seconds
+    Deferred {
+        /// whether the response will only be visible to the user who used the command
+        ephemeral: bool,
+    },
+}
+
 /// A command that can be used in a guild, restricted to administrators
 #[async_trait]
 pub trait Command<'a>: TryFrom<&'a CommandInteraction> {
@@ -30,8 +66,28 @@ pub trait Command<'a>: TryFrom<&'a CommandInteraction> {
     /// Get the description of the command
     fn description() -> &'static str;
 
+    /// How long a user must wait between uses of this command, if they are limited at all
+    fn cooldown() -> Option<Duration> {
+        None
+    }
+
+    /// How the response to this command is sent, commands which call slow external apis should
+    /// be deferred
+    fn response_mode() -> ResponseMode {
+        ResponseMode::Immediate
+    }
+
+    /// Whether the response of a deferred command shows ⏳ while it runs, swapped for ✅ or ❌
+    /// once it completes. Ephemeral responses can't be reacted to, so this is ignored for them
+    fn progress_reactions() -> bool {
+        false
+    }
+
     /// Get the discord defined usage of this command, to be sent to discord
-    fn get_application_command_options(command: CreateCommand) -> CreateCommand;
+    fn get_application_command_options(
+        command: CreateCommand,
+        app_state: &AppState,
+    ) -> CreateCommand;
 
     /// handle the execution of this application command
     async fn handle_application_command<'b>(
@@ -40,18 +96,17 @@ pub trait Command<'a>: TryFrom<&'a CommandInteraction> {
         app_state: &'b AppState,
         context: &'b Context,
     ) -> Result<CommandResponse, CommandResponse>;
-}
 
-/// A command that has support for autocomplete responses
-#[async_trait]
-pub trait AutocompleteCommand<'a>: Command<'a> {
-    /// get the autocomplete options for this command, given the current input
+    /// suggest values for the focused option of this command, given the current input. Commands
+    /// with options set to autocomplete must override this, otherwise no suggestions are shown
     async fn autocomplete<'c>(
-        command: &'c CommandInteraction,
-        message: &'c AutocompleteOption,
-        app_state: &'c AppState,
-        context: &'c Context,
-    ) -> Result<CreateAutocompleteResponse, CommandResponse>;
+        _command: &'c CommandInteraction,
+        _focused: &'c AutocompleteOption,
+        _app_state: &'c AppState,
+        _context: &'c Context,
+    ) -> Result<CreateAutocompleteResponse, CommandResponse> {
+        Ok(CreateAutocompleteResponse::new())
+    }
 }
 
 /// A command with a followup interaction component which must be handled
@@ -72,6 +127,24 @@ pub trait InteractionCommand<'a>: Command<'a> {
     ) -> Result<CommandResponse, CommandResponse>;
 }
 
+/// A command which opens a modal, and must handle the modal being submitted
+#[async_trait]
+pub trait ModalCommand<'a>: Command<'a> {
+    /// validate if this submitted modal is related to a given command
+    async fn answerable_modal<'b>(
+        submission: &'b ModalInteraction,
+        app_state: &'b AppState,
+        context: &'b Context,
+    ) -> bool;
+
+    /// handle the submitted modal for this command
+    async fn modal<'b>(
+        submission: &'b ModalInteraction,
+        app_state: &'b AppState,
+        context: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse>;
+}
+
 // #[async_trait]
 // pub trait PaginatedResponse<'a>: Command<'a> {
 //     /// Get the number of pages this response has
@@ -83,13 +156,13 @@ pub trait InteractionCommand<'a>: Command<'a> {
 
 /// match against a list of provided command types, and generate an application command that can be registered with discord
 macro_rules! application_command {
-    ( $base:expr, $( $x:ty ),* $(,)? ) => {
+    ( $base:expr, $state:expr, $( $x:ty ),* $(,)? ) => {
         {
             /// ensures that the provided type has relevant traits
             fn assert_command<'a, T: Command<'a, Error=String>>() {}
             $(
                 assert_command::<$x>();
-                let mut v_base = <$x>::get_application_command_options(CreateCommand::new("unnamed command"));
+                let mut v_base = <$x>::get_application_command_options(CreateCommand::new("unnamed command"), $state);
                 v_base = v_base
                     .name(<$x>::name())
                     .description(<$x>::description())
@@ -102,6 +175,20 @@ macro_rules! application_command {
     };
 }
 
+/// list the name and description of each of the provided command types
+macro_rules! describe_commands {
+    ( $base:expr, $( $x:ty ),* $(,)? ) => {
+        {
+            /// ensures that the provided type has relevant traits
+            fn assert_command<'a, T: Command<'a, Error=String>>() {}
+            $(
+                assert_command::<$x>();
+                $base.push((<$x>::name(), <$x>::description()));
+            )*
+        }
+    };
+}
+
 /// match against a list of provided command types, and produce a response which can be sent to the user
 macro_rules! command {
     ( $cmd:expr, $state:expr, $context:expr, $( $x:ty ),* $(,)? ) => {
@@ -111,8 +198,57 @@ macro_rules! command {
             $(
                 assert_command::<$x>();
                 if ($cmd).data.name == <$x>::name() {
+                    if let Some(cooldown) = <$x>::cooldown() {
+                        if let Err(remaining) =
+                            $state.check_cooldown(($cmd).user.id.into(), <$x>::name(), cooldown)
+                        {
+                            return Err(CommandResponse::BasicFailure(format!(
+                                "/{} is on cooldown, try again in {}s",
+                                <$x>::name(),
+                                remaining.as_secs_f64().ceil()
+                            )));
+                        }
+                    }
+
                     if let Ok(value) = <$x>::try_from($cmd) {
-                        return value.handle_application_command($cmd, $state, $context).await
+                        let start = Instant::now();
+                        if let ResponseMode::Deferred { ephemeral } = <$x>::response_mode() {
+                            if let Err(e) = ($cmd)
+                                .create_response(
+                                    $context,
+                                    CreateInteractionResponse::Defer(
+                                        CreateInteractionResponseMessage::new().ephemeral(ephemeral),
+                                    ),
+                                )
+                                .await
+                            {
+                                $state.metrics.record_command(<$x>::name(), start.elapsed(), false);
+                                return Err(CommandResponse::InternalFailure(format!(
+                                    "error deferring response: {}",
+                                    e
+                                )));
+                            }
+
+                            let progress = if <$x>::progress_reactions() && !ephemeral {
+                                Progress::start_for_response($cmd, $context).await
+                            } else {
+                                None
+                            };
+
+                            let result = value.handle_application_command($cmd, $state, $context).await;
+                            $state.metrics.record_command(<$x>::name(), start.elapsed(), result.is_ok());
+                            if let Some(progress) = progress {
+                                progress.finish(result.is_ok(), $context).await;
+                            }
+                            return match result {
+                                Ok(response) => Ok(CommandResponse::Deferred(Box::new(response))),
+                                Err(response) => Err(CommandResponse::Deferred(Box::new(response))),
+                            };
+                        }
+
+                        let result = value.handle_application_command($cmd, $state, $context).await;
+                        $state.metrics.record_command(<$x>::name(), start.elapsed(), result.is_ok());
+                        return result
                     }
                 }
             )*
@@ -121,14 +257,14 @@ macro_rules! command {
     };
 }
 
-/// match against a list of provided autocomplete command types, and produce a response which can be sent to the user
+/// match against a list of provided command types, and produce the autocomplete suggestions for the focused option
 macro_rules! autocomplete {
     ( $cmd:expr, $state:expr, $context:expr, $( $x:ty ),* $(,)? ) => {
         {
             /// ensures that the provided type has relevant traits
-            fn assert_autocomplete<'a, T: AutocompleteCommand<'a, Error=String>>() {}
+            fn assert_command<'a, T: Command<'a, Error=String>>() {}
             $(
-                assert_autocomplete::<$x>();
+                assert_command::<$x>();
                 if ($cmd).data.name == <$x>::name() {
                     return match $cmd.data.autocomplete() {
                         Some(data) => <$x>::autocomplete($cmd, &data, $state, $context).await,
@@ -158,17 +294,78 @@ macro_rules! interaction {
     };
 }
 
-pub fn application_command() -> Vec<CreateCommand> {
+/// match against a list of provided modal command types, and produce a response which can be sent to the user
+macro_rules! modal {
+    ( $cmd:expr, $state:expr, $context:expr, $( $x:ty ),* $(,)? ) => {
+        {
+            /// ensures that the provided type has relevant traits
+            fn assert_modal<'a, T: ModalCommand<'a, Error=String>>() {}
+            $(
+                assert_modal::<$x>();
+                if <$x>::answerable_modal($cmd, $state, $context).await {
+                    return <$x>::modal($cmd, $state, $context).await
+                }
+            )*
+            Err(CommandResponse::InternalFailure(String::from("Unsupported Modal")))
+        }
+    };
+}
+
+pub fn application_command(app_state: &AppState) -> Vec<CreateCommand> {
     let mut base = vec![];
     application_command!(
         &mut base,
+        app_state,
+        HelpCommand,
+        HideCommand,
+        PingCommand,
+        WhoamiCommand,
+        SayCommand,
+        DistanceCommand,
+        RefreshDistanceCommand,
+        CompareCommand,
+        PayCommand,
+        PayAllCommand,
+        BalanceCommand,
+        HistoryCommand,
+        SpendingCommand,
+        Shop,
+        FlatmateCommand,
+        Chore,
+        Remind,
+        Recurring,
+        ConfigCommand,
+        FeedbackCommand,
+        // ShoppingComplete,
+    );
+    base
+}
+
+/// the name and description of every registered command, in the order they are registered
+pub fn command_descriptions() -> Vec<(&'static str, &'static str)> {
+    let mut base = vec![];
+    describe_commands!(
+        &mut base,
+        HelpCommand,
         HideCommand,
         PingCommand,
+        WhoamiCommand,
         SayCommand,
-        // DistanceCommand,
+        DistanceCommand,
+        RefreshDistanceCommand,
+        CompareCommand,
         PayCommand,
         PayAllCommand,
+        BalanceCommand,
+        HistoryCommand,
+        SpendingCommand,
         Shop,
+        FlatmateCommand,
+        Chore,
+        Remind,
+        Recurring,
+        ConfigCommand,
+        FeedbackCommand,
         // ShoppingComplete,
     );
     base
@@ -183,24 +380,61 @@ pub async fn command<'a>(
         command,
         app_state,
         context,
+        HelpCommand,
         HideCommand,
         PingCommand,
+        WhoamiCommand,
         SayCommand,
-        // DistanceCommand,
+        DistanceCommand,
+        RefreshDistanceCommand,
+        CompareCommand,
         PayCommand,
         PayAllCommand,
+        BalanceCommand,
+        HistoryCommand,
+        SpendingCommand,
         Shop,
+        FlatmateCommand,
+        Chore,
+        Remind,
+        Recurring,
+        ConfigCommand,
+        FeedbackCommand,
         // ShoppingComplete,
     )
 }
 
-#[allow(dead_code, unused_variables)]
 pub async fn autocomplete<'a>(
     command: &'a CommandInteraction,
     app_state: &'a AppState,
     context: &'a Context,
 ) -> Result<CreateAutocompleteResponse, CommandResponse> {
-    autocomplete!(command, app_state, context, PayCommand, PayAllCommand, Shop)
+    autocomplete!(
+        command,
+        app_state,
+        context,
+        HelpCommand,
+        HideCommand,
+        PingCommand,
+        WhoamiCommand,
+        SayCommand,
+        DistanceCommand,
+        RefreshDistanceCommand,
+        CompareCommand,
+        PayCommand,
+        PayAllCommand,
+        BalanceCommand,
+        HistoryCommand,
+        SpendingCommand,
+        Shop,
+        FlatmateCommand,
+        Chore,
+        Remind,
+        Recurring,
+        ConfigCommand,
+        FeedbackCommand,
+        // ShoppingComplete,
+    )
 }
 
 pub async fn interaction<'a>(
@@ -208,9 +442,21 @@ pub async fn interaction<'a>(
     app_state: &'a AppState,
     context: &'a Context,
 ) -> Result<CommandResponse, CommandResponse> {
+    if is_page_interaction(command) {
+        return handle_page_interaction(command);
+    }
+
     interaction!(
         command, app_state, context, PayCommand, // also handles PayAllCommand
         Shop,
         // ShoppingList
     )
 }
+
+pub async fn modal<'a>(
+    submission: &'a ModalInteraction,
+    app_state: &'a AppState,
+    context: &'a Context,
+) -> Result<CommandResponse, CommandResponse> {
+    modal!(submission, app_state, context, PayCommand)
+}
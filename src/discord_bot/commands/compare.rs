@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+use log::warn;
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedValue},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseFollowup},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{
+            currency::format_money,
+            embed::EmbedColor,
+            listing::{fetch_listing_cached, Listing, Rent},
+        },
+        database::{guild_config::SerenityGuildConfigDatabase, listings::SerenityListingDatabase},
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::{Command, ResponseMode},
+    util::{guild_branding, guild_currency, CommandResponse},
+};
+
+/// the most listings compared at once, only the most recently posted are included
+const MAX_COMPARED_LISTINGS: usize = 10;
+
+/// the longest address shown in the comparison table, in characters
+const MAX_ADDRESS_LENGTH: usize = 28;
+
+/// the column the comparison is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Posted,
+    Rent,
+    Bedrooms,
+    Commute,
+}
+
+impl SortBy {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Posted => "most recently posted",
+            Self::Rent => "rent",
+            Self::Bedrooms => "bedrooms",
+            Self::Commute => "commute",
+        }
+    }
+}
+
+/// a listing posted in the channel, with the details it is compared on
+struct ComparedListing {
+    listing: Listing,
+    /// the weekly rent in cents
+    rent: Option<i64>,
+    /// the commute to the first destination, in seconds and as described by google maps
+    commute: Option<(u32, String)>,
+}
+
+pub struct CompareCommand {
+    sort: SortBy,
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for CompareCommand {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let mut sort = SortBy::Posted;
+        for option in interaction.data.options() {
+            if let ("sort", ResolvedValue::String(value)) = (option.name, option.value) {
+                sort = match value {
+                    "rent" => SortBy::Rent,
+                    "bedrooms" => SortBy::Bedrooms,
+                    "commute" => SortBy::Commute,
+                    _ => SortBy::Posted,
+                };
+            }
+        }
+        Ok(Self { sort })
+    }
+}
+
+/// load the commute from an address to the first destination with the first travel mode. Every
+/// destination is requested, so the response cached when the listing was posted is reused
+async fn load_commute(address: &str, app_state: &AppState) -> Option<(u32, String)> {
+    let mode = *CONFIG.travel_modes.first()?;
+    if CONFIG.destinations.is_empty() {
+        return None;
+    }
+
+    let data = match app_state
+        .maps_api()
+        .get_distance(address.to_string(), &CONFIG.destinations, mode)
+        .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("unable to load commute for {}: {}", address, e);
+            return None;
+        }
+    };
+
+    let duration = data
+        .rows
+        .first()
+        .and_then(|row| row.elements.first())
+        .and_then(|element| element.duration.as_ref())?;
+    Some((duration.value, duration.text.clone()))
+}
+
+/// shorten an address to fit in the comparison table
+fn shorten(address: &str) -> String {
+    if address.chars().count() <= MAX_ADDRESS_LENGTH {
+        address.to_string()
+    } else {
+        let short: String = address.chars().take(MAX_ADDRESS_LENGTH - 1).collect();
+        format!("{}…", short)
+    }
+}
+
+/// sort the listings by a column, listings missing that detail are always last
+fn sort_listings(listings: &mut [ComparedListing], sort: SortBy) {
+    match sort {
+        // the listings are loaded most recent first, and the sort is stable
+        SortBy::Posted => {}
+        SortBy::Rent => listings.sort_by_key(|l| (l.rent.is_none(), l.rent)),
+        SortBy::Bedrooms => listings.sort_by_key(|l| {
+            (
+                l.listing.bedrooms.is_none(),
+                std::cmp::Reverse(l.listing.bedrooms),
+            )
+        }),
+        SortBy::Commute => listings.sort_by_key(|l| {
+            (
+                l.commute.is_none(),
+                l.commute.as_ref().map(|(seconds, _)| *seconds),
+            )
+        }),
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for CompareCommand {
+    fn name() -> &'static str {
+        "compare"
+    }
+
+    fn description() -> &'static str {
+        "Compare the rent, bedrooms, and commute of the listings posted in this channel"
+    }
+
+    fn response_mode() -> ResponseMode {
+        // each listing may need to be loaded again, which is rate limited
+        ResponseMode::Deferred { ephemeral: false }
+    }
+
+    fn progress_reactions() -> bool {
+        true
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "sort",
+                "The column to rank the listings by, defaults to the most recently posted",
+            )
+            .required(false)
+            .add_string_choice("Rent", "rent")
+            .add_string_choice("Bedrooms", "bedrooms")
+            .add_string_choice("Commute", "commute"),
+        )
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let posted = match app_state
+            .get_listings_for_channel(interaction.channel_id.into())
+            .await
+        {
+            Ok(posted) => posted,
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        if posted.is_empty() {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "no listings have been posted in this channel",
+            )));
+        }
+
+        let config = match interaction.guild_id {
+            Some(guild_id) => match app_state.get_guild_config(guild_id.0.into()).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+            },
+            None => None,
+        };
+        let timeout = Duration::from_secs(
+            config
+                .and_then(|config| config.listing_timeout)
+                .map(|timeout| timeout as u64)
+                .unwrap_or(CONFIG.listing_timeout),
+        );
+        let ttl = Duration::from_secs(CONFIG.listing_cache_ttl);
+
+        let mut listings = Vec::with_capacity(MAX_COMPARED_LISTINGS);
+        let mut unavailable = vec![];
+        for posted in posted.iter().take(MAX_COMPARED_LISTINGS) {
+            let listing = match fetch_listing_cached(
+                posted.listing_id as u64,
+                posted.url.clone(),
+                timeout,
+                ttl,
+            )
+            .await
+            {
+                Ok((listing, _)) => listing,
+                Err(e) => {
+                    // links are left out, as a field can only hold 1024 characters
+                    unavailable.push(format!("{} - {}", posted.address, e));
+                    continue;
+                }
+            };
+
+            let rent = listing.price.as_ref().and_then(Rent::weekly_cents);
+            let commute = load_commute(&listing.address, app_state).await;
+            listings.push(ComparedListing {
+                listing,
+                rent,
+                commute,
+            });
+        }
+
+        if listings.is_empty() {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "none of the listings posted in this channel are still available",
+            )));
+        }
+
+        sort_listings(&mut listings, self.sort);
+
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let mut table = format!(
+            "```\n{:<3}{:<11}{:<6}{:<10}{}\n",
+            "#", "Rent", "Beds", "Commute", "Address"
+        );
+        let mut links = String::new();
+        for (i, compared) in listings.iter().enumerate() {
+            let listing = &compared.listing;
+            table.push_str(&format!(
+                "{:<3}{:<11}{:<6}{:<10}{}\n",
+                i + 1,
+                compared
+                    .rent
+                    .map(|rent| format_money(rent, currency))
+                    .unwrap_or_else(|| String::from("?")),
+                listing
+                    .bedrooms
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| String::from("?")),
+                compared
+                    .commute
+                    .as_ref()
+                    .map(|(_, text)| text.clone())
+                    .unwrap_or_else(|| String::from("?")),
+                shorten(&listing.address)
+            ));
+            links.push_str(&format!(
+                "{}. [{}]({})\n",
+                i + 1,
+                listing.address,
+                listing.url
+            ));
+        }
+        table.push_str("```");
+
+        let mut embed = guild_branding(interaction.guild_id, app_state)
+            .await?
+            .embed()
+            .title(format!(
+                "Listings in this channel, by {}",
+                self.sort.label()
+            ))
+            .description(format!("{}\n{}", table, links))
+            .color(EmbedColor::Green as u32);
+
+        if let (Some(destination), Some(mode)) =
+            (CONFIG.destinations.first(), CONFIG.travel_modes.first())
+        {
+            embed = embed.field(
+                "Commute",
+                format!("to {} by {}", destination.label, mode.label()),
+                false,
+            );
+        }
+        if !unavailable.is_empty() {
+            embed = embed.field("Unavailable", unavailable.join("\n"), false);
+        }
+
+        Ok(CommandResponse::Followup(
+            CreateInteractionResponseFollowup::new().embed(embed),
+        ))
+    }
+}
@@ -0,0 +1,499 @@
+use serenity::{
+    all::{
+        AutocompleteOption, CommandInteraction, CommandOptionType, ResolvedOption, ResolvedValue,
+    },
+    async_trait,
+    builder::{
+        CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{
+            currency::{format_money, Currency},
+            distance::{format_distance, DistanceUnit},
+            embed::EmbedColor,
+            locale::Locale,
+        },
+        database::guild_config::{GuildConfigModel, GuildSetting, SerenityGuildConfigDatabase},
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::Command,
+    util::{is_admin, Branding, CommandResponse},
+};
+
+/// every setting which can be changed with /config, and what it controls
+const SETTINGS: [(&str, &str); 12] = [
+    (
+        "units",
+        "the units distances are shown in, metric or imperial",
+    ),
+    (
+        "currency",
+        "the currency amounts are shown in, e.g. NZD or AUD",
+    ),
+    (
+        "locale",
+        "the language responses are written in, e.g. en or mi",
+    ),
+    (
+        "thread-name",
+        "how listing threads are named, can use {price}, {address}, and {bedrooms}",
+    ),
+    (
+        "listing-timeout",
+        "how long to wait for a listing to load, in seconds",
+    ),
+    (
+        "chore-channel",
+        "the channel the weekly chore rota is announced in",
+    ),
+    (
+        "listings-channel",
+        "the only channel listings get threads in, leave empty for every channel",
+    ),
+    (
+        "reminder-channel",
+        "the channel reminders are sent in, leave empty to send each where it was set",
+    ),
+    (
+        "admin-role",
+        "the role allowed to use admin only commands, as well as the server owner",
+    ),
+    (
+        "brand-name",
+        "the name shown at the top of the bot's embeds",
+    ),
+    (
+        "brand-thumbnail",
+        "the url of an image shown in the corner of the bot's embeds",
+    ),
+    (
+        "brand-footer",
+        "the text shown at the bottom of the bot's embeds",
+    ),
+];
+
+/// the longest a listing can be waited on for, in seconds
+const MAX_LISTING_TIMEOUT: u64 = 600;
+
+pub enum ConfigCommand<'a> {
+    /// show a single setting, or every setting if no key is given
+    Get(Option<&'a str>),
+    /// change a setting, leaving out the value restores its default
+    Set {
+        key: &'a str,
+        value: Option<&'a str>,
+    },
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for ConfigCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        match options.into_iter().next() {
+            Some(ResolvedOption {
+                name: "get",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => match options.first().map(|o| &o.value) {
+                Some(ResolvedValue::String(key)) => Ok(ConfigCommand::Get(Some(*key))),
+                _ => Ok(ConfigCommand::Get(None)),
+            },
+            Some(ResolvedOption {
+                name: "set",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => {
+                let mut key = None;
+                let mut value = None;
+
+                for option in options {
+                    match (option.name, option.value) {
+                        ("key", ResolvedValue::String(val)) => key = Some(val),
+                        ("value", ResolvedValue::String(val)) => value = Some(val),
+                        _ => {}
+                    }
+                }
+
+                match key {
+                    Some(key) => Ok(ConfigCommand::Set { key, value }),
+                    None => Err(String::from("key is required")),
+                }
+            }
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
+/// parse a channel from either a mention, e.g. `<#1234>`, or a raw id
+fn parse_channel(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .trim_start_matches("<#")
+        .trim_end_matches('>')
+        .parse()
+        .ok()
+}
+
+/// parse a role from either a mention, e.g. `<@&1234>`, or a raw id
+fn parse_role(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse()
+        .ok()
+}
+
+/// validate the value of a setting, where no value restores the default
+fn parse_setting(key: &str, value: Option<&str>) -> Result<GuildSetting, String> {
+    let value = value.map(str::trim).filter(|v| !v.is_empty());
+
+    match key {
+        "units" => Ok(GuildSetting::Units(match value {
+            Some(value) => value.parse()?,
+            None => DistanceUnit::default(),
+        })),
+        "currency" => Ok(GuildSetting::Currency(match value {
+            Some(value) => value.parse()?,
+            None => Currency::default(),
+        })),
+        "locale" => Ok(GuildSetting::Locale(match value {
+            Some(value) => value.parse()?,
+            None => Locale::default(),
+        })),
+        "thread-name" => match value {
+            Some(value) if value.chars().count() > 100 => Err(String::from(
+                "thread names can be at most 100 characters long",
+            )),
+            value => Ok(GuildSetting::ThreadName(value.map(str::to_string))),
+        },
+        "listing-timeout" => match value {
+            Some(value) => match value.trim_end_matches('s').parse::<u64>() {
+                Ok(seconds) if (1..=MAX_LISTING_TIMEOUT).contains(&seconds) => {
+                    Ok(GuildSetting::ListingTimeout(Some(seconds)))
+                }
+                _ => Err(format!(
+                    "the listing timeout must be a number of seconds between 1 and {}",
+                    MAX_LISTING_TIMEOUT
+                )),
+            },
+            None => Ok(GuildSetting::ListingTimeout(None)),
+        },
+        "chore-channel" => match value {
+            Some(value) => match parse_channel(value) {
+                Some(channel) => Ok(GuildSetting::ChoreChannel(Some(channel))),
+                None => Err(format!("{} is not a channel", value)),
+            },
+            None => Ok(GuildSetting::ChoreChannel(None)),
+        },
+        "listings-channel" => match value {
+            Some(value) => match parse_channel(value) {
+                Some(channel) => Ok(GuildSetting::ListingsChannel(Some(channel))),
+                None => Err(format!("{} is not a channel", value)),
+            },
+            None => Ok(GuildSetting::ListingsChannel(None)),
+        },
+        "reminder-channel" => match value {
+            Some(value) => match parse_channel(value) {
+                Some(channel) => Ok(GuildSetting::ReminderChannel(Some(channel))),
+                None => Err(format!("{} is not a channel", value)),
+            },
+            None => Ok(GuildSetting::ReminderChannel(None)),
+        },
+        "admin-role" => match value {
+            Some(value) => match parse_role(value) {
+                Some(role) => Ok(GuildSetting::AdminRole(Some(role))),
+                None => Err(format!("{} is not a role", value)),
+            },
+            None => Ok(GuildSetting::AdminRole(None)),
+        },
+        "brand-name" => Ok(GuildSetting::BrandingAuthor(value.map(str::to_string))),
+        "brand-thumbnail" => match value {
+            Some(value) if !value.starts_with("https://") => Err(String::from(
+                "the thumbnail must be a link starting with https://",
+            )),
+            value => Ok(GuildSetting::BrandingThumbnail(value.map(str::to_string))),
+        },
+        "brand-footer" => Ok(GuildSetting::BrandingFooter(value.map(str::to_string))),
+        key => Err(format!("{} is not a setting", key)),
+    }
+}
+
+/// describe the current value of a setting, falling back to the bot's defaults for any the
+/// guild hasn't changed
+fn describe_setting(key: &str, config: Option<&GuildConfigModel>) -> String {
+    match key {
+        "units" => {
+            let unit: DistanceUnit = config
+                .and_then(|c| c.units.parse().ok())
+                .unwrap_or_default();
+            format!("{}, e.g. {}", unit.as_str(), format_distance(12_300, unit))
+        }
+        "currency" => {
+            let currency: Currency = config
+                .and_then(|c| c.currency.parse().ok())
+                .unwrap_or_default();
+            format!(
+                "{}, e.g. {}",
+                currency.as_str(),
+                format_money(123_450, currency)
+            )
+        }
+        "locale" => {
+            let locale: Locale = config
+                .and_then(|c| c.locale.parse().ok())
+                .unwrap_or_default();
+            format!("{} ({})", locale.as_str(), locale.name())
+        }
+        "thread-name" => match config.and_then(|c| c.thread_template.as_ref()) {
+            Some(template) => format!("`{}`", template),
+            None => String::from("the price and address (default)"),
+        },
+        "listing-timeout" => match config.and_then(|c| c.listing_timeout) {
+            Some(timeout) => format!("{}s", timeout),
+            None => format!("{}s (default)", CONFIG.listing_timeout),
+        },
+        "chore-channel" => match (config.and_then(|c| c.chore_channel), CONFIG.chore_channel) {
+            (Some(channel), _) => format!("<#{}>", channel),
+            (None, Some(channel)) => format!("<#{}> (default)", channel),
+            (None, None) => String::from("not set, chores aren't announced"),
+        },
+        "listings-channel" => match config.and_then(|c| c.listings_channel) {
+            Some(channel) => format!("<#{}>", channel),
+            None => String::from("every channel (default)"),
+        },
+        "reminder-channel" => match config.and_then(|c| c.reminder_channel) {
+            Some(channel) => format!("<#{}>", channel),
+            None => String::from("the channel each was set in (default)"),
+        },
+        "admin-role" => match (config.and_then(|c| c.admin_role), CONFIG.admin_role) {
+            (Some(role), _) => format!("<@&{}>", role),
+            (None, Some(role)) => format!("<@&{}> (default)", role),
+            (None, None) => String::from("not set, only the server owner"),
+        },
+        "brand-name" => match config.and_then(|c| c.branding_author.as_ref()) {
+            Some(author) => author.clone(),
+            None => String::from("not set"),
+        },
+        "brand-thumbnail" => match config.and_then(|c| c.branding_thumbnail.as_ref()) {
+            Some(url) => url.clone(),
+            None => String::from("not set"),
+        },
+        "brand-footer" => match config.and_then(|c| c.branding_footer.as_ref()) {
+            Some(footer) => footer.clone(),
+            None => String::from("a random phrase (default)"),
+        },
+        _ => String::from("unknown setting"),
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for ConfigCommand<'a> {
+    fn name() -> &'static str {
+        "config"
+    }
+
+    fn description() -> &'static str {
+        "view or change how the bot behaves in this server"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        let get = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "get",
+            "show the current settings",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "key",
+                "The setting to show, leave empty to show them all",
+            )
+            .required(false)
+            .set_autocomplete(true),
+        );
+
+        let set =
+            CreateCommandOption::new(CommandOptionType::SubCommand, "set", "change a setting")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "key",
+                        "The setting to change",
+                    )
+                    .required(true)
+                    .set_autocomplete(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "value",
+                        "The new value, leave empty to restore the default",
+                    )
+                    .required(false)
+                    .set_autocomplete(true)
+                    .max_length(100),
+                );
+
+        cmd.add_option(get).add_option(set)
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        if !is_admin(interaction, app_state, ctx).await {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "insufficient permissions",
+            )));
+        }
+
+        let guild_id: u64 = match interaction.guild_id {
+            Some(guild_id) => guild_id.0.into(),
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "the config can only be changed in a server",
+                )))
+            }
+        };
+
+        let embed = match self {
+            ConfigCommand::Get(key) => {
+                let keys: Vec<&str> = match key {
+                    Some(key) if SETTINGS.iter().any(|(k, _)| *k == key) => vec![key],
+                    Some(key) => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "{} is not a setting",
+                            key
+                        )))
+                    }
+                    None => SETTINGS.iter().map(|(k, _)| *k).collect(),
+                };
+
+                let config = match app_state.get_guild_config(guild_id).await {
+                    Ok(config) => config,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                let mut embed = Branding::from_config(config.as_ref())
+                    .embed()
+                    .title("Settings")
+                    .color(EmbedColor::Green as u32);
+                for key in keys {
+                    embed = embed.field(key, describe_setting(key, config.as_ref()), false);
+                }
+                embed
+            }
+            ConfigCommand::Set { key, value } => {
+                let setting = parse_setting(key, value).map_err(CommandResponse::BasicFailure)?;
+
+                let config = match app_state.set_guild_setting(guild_id, setting).await {
+                    Ok(config) => config,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                // the new branding is shown straight away, so a change can be checked
+                Branding::from_config(Some(&config))
+                    .embed()
+                    .description(format!(
+                        "`{}` is now {}",
+                        key,
+                        describe_setting(key, Some(&config))
+                    ))
+                    .color(EmbedColor::Green as u32)
+            }
+        };
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(embed),
+            ),
+        ))
+    }
+
+    async fn autocomplete<'c>(
+        interaction: &'c CommandInteraction,
+        focused: &'c AutocompleteOption,
+        _: &'c AppState,
+        _: &'c Context,
+    ) -> Result<CreateAutocompleteResponse, CommandResponse> {
+        let mut response = CreateAutocompleteResponse::new();
+        let search = focused.value.to_lowercase();
+
+        match focused.name {
+            "key" => {
+                for (key, description) in SETTINGS.iter().filter(|(k, _)| k.contains(&search)) {
+                    let name: String = format!("{} - {}", key, description)
+                        .chars()
+                        .take(100)
+                        .collect();
+                    response = response.add_string_choice(name, *key);
+                }
+            }
+            "value" => {
+                // only the units, currency, and locale have a fixed set of values to suggest
+                let key =
+                    interaction
+                        .data
+                        .options()
+                        .into_iter()
+                        .find_map(|option| match option.value {
+                            ResolvedValue::SubCommand(options) => {
+                                options.into_iter().find_map(|o| match (o.name, o.value) {
+                                    ("key", ResolvedValue::String(key)) => Some(key),
+                                    _ => None,
+                                })
+                            }
+                            _ => None,
+                        });
+
+                match key {
+                    Some("units") => {
+                        for unit in [DistanceUnit::Metric, DistanceUnit::Imperial] {
+                            response = response.add_string_choice(unit.as_str(), unit.as_str());
+                        }
+                    }
+                    Some("currency") => {
+                        for currency in Currency::ALL {
+                            response =
+                                response.add_string_choice(currency.as_str(), currency.as_str());
+                        }
+                    }
+                    Some("locale") => {
+                        for locale in Locale::ALL {
+                            response = response.add_string_choice(
+                                format!("{} - {}", locale.as_str(), locale.name()),
+                                locale.as_str(),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,154 @@
+//! The parts of the discord api used by commands, behind a trait so that a command's handling
+//! can be driven without a live gateway connection
+
+use serenity::{
+    all::{ChannelId, CommandInteraction, ComponentInteraction, Message, MessageId},
+    async_trait,
+    builder::{CreateAttachment, CreateInteractionResponse, EditMessage},
+    prelude::Context,
+};
+
+/// the requests a command makes to discord while handling an interaction. This is implemented
+/// for serenity's [`Context`], and can be implemented by a mock which records what was sent
+#[async_trait]
+pub trait DiscordApi: Send + Sync {
+    /// download a file, such as a receipt, so that it can be attached to a response
+    async fn download_attachment(&self, url: &str) -> serenity::Result<CreateAttachment>;
+
+    /// send the initial response to a command
+    async fn create_response(
+        &self,
+        interaction: &CommandInteraction,
+        response: CreateInteractionResponse,
+    ) -> serenity::Result<()>;
+
+    /// fetch the message which was sent as the response to a command
+    async fn get_response(&self, interaction: &CommandInteraction) -> serenity::Result<Message>;
+
+    /// send the response to a button press
+    async fn respond_to_component(
+        &self,
+        interaction: &ComponentInteraction,
+        response: CreateInteractionResponse,
+    ) -> serenity::Result<()>;
+
+    /// fetch a message the bot sent earlier, such as a posted bill
+    async fn get_message(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+    ) -> serenity::Result<Message>;
+
+    /// edit a message the bot sent earlier
+    async fn edit_message(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        edit: EditMessage,
+    ) -> serenity::Result<()>;
+}
+
+#[async_trait]
+impl DiscordApi for Context {
+    async fn download_attachment(&self, url: &str) -> serenity::Result<CreateAttachment> {
+        CreateAttachment::url(self, url).await
+    }
+
+    async fn create_response(
+        &self,
+        interaction: &CommandInteraction,
+        response: CreateInteractionResponse,
+    ) -> serenity::Result<()> {
+        interaction.create_response(self, response).await
+    }
+
+    async fn get_response(&self, interaction: &CommandInteraction) -> serenity::Result<Message> {
+        interaction.get_response(self).await
+    }
+
+    async fn respond_to_component(
+        &self,
+        interaction: &ComponentInteraction,
+        response: CreateInteractionResponse,
+    ) -> serenity::Result<()> {
+        interaction.create_response(self, response).await
+    }
+
+    async fn get_message(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+    ) -> serenity::Result<Message> {
+        channel.message(self, message).await
+    }
+
+    async fn edit_message(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        edit: EditMessage,
+    ) -> serenity::Result<()> {
+        channel.edit_message(self, message, edit).await.map(|_| ())
+    }
+}
+
+/// a [`DiscordApi`] for tests, which records every request instead of sending it
+#[cfg(test)]
+pub mod mock {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct MockDiscord {
+        /// the url of every attachment downloaded, in order
+        pub downloads: Mutex<Vec<String>>,
+        /// every response sent to a command or button press, in order
+        pub responses: Mutex<Vec<CreateInteractionResponse>>,
+    }
+
+    #[async_trait]
+    impl DiscordApi for MockDiscord {
+        async fn download_attachment(&self, url: &str) -> serenity::Result<CreateAttachment> {
+            self.downloads.lock().unwrap().push(url.to_string());
+            Ok(CreateAttachment::bytes(Vec::new(), "receipt.png"))
+        }
+
+        async fn create_response(
+            &self,
+            _: &CommandInteraction,
+            response: CreateInteractionResponse,
+        ) -> serenity::Result<()> {
+            self.responses.lock().unwrap().push(response);
+            Ok(())
+        }
+
+        async fn get_response(&self, _: &CommandInteraction) -> serenity::Result<Message> {
+            // nothing is really sent, so there is never a message to fetch
+            Err(serenity::Error::Other("the mock doesn't send messages"))
+        }
+
+        async fn respond_to_component(
+            &self,
+            _: &ComponentInteraction,
+            response: CreateInteractionResponse,
+        ) -> serenity::Result<()> {
+            self.responses.lock().unwrap().push(response);
+            Ok(())
+        }
+
+        async fn get_message(&self, _: ChannelId, _: MessageId) -> serenity::Result<Message> {
+            // as nothing is really sent, earlier messages can never be found
+            Err(serenity::Error::Other("the mock doesn't send messages"))
+        }
+
+        async fn edit_message(
+            &self,
+            _: ChannelId,
+            _: MessageId,
+            _: EditMessage,
+        ) -> serenity::Result<()> {
+            Err(serenity::Error::Other("the mock doesn't send messages"))
+        }
+    }
+}
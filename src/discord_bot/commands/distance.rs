@@ -0,0 +1,121 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedValue},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseFollowup},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::distance::{load_maps_data_to_embed, DistanceUnit},
+        database::guild_config::SerenityGuildConfigDatabase,
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::{Command, ResponseMode},
+    util::{guild_branding, CommandResponse, FailureMessageKind},
+};
+
+pub struct DistanceCommand<'a> {
+    address: &'a str,
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for DistanceCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        for option in interaction.data.options() {
+            if let ("address", ResolvedValue::String(address)) = (option.name, option.value) {
+                return Ok(Self { address });
+            }
+        }
+        Err(String::from("address is required"))
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for DistanceCommand<'a> {
+    fn name() -> &'static str {
+        "distance"
+    }
+
+    fn description() -> &'static str {
+        "Show the commute from an address to each of the flat's destinations"
+    }
+
+    fn response_mode() -> ResponseMode {
+        // every travel mode is looked up with google maps, which can take a few seconds
+        ResponseMode::Deferred { ephemeral: false }
+    }
+
+    fn progress_reactions() -> bool {
+        true
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "address",
+                "The address to check, e.g. 12 Smith Street, Auckland",
+            )
+            .required(true)
+            .max_length(200),
+        )
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let address = self.address.trim();
+        if address.is_empty() {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "an address is required",
+            )));
+        }
+
+        let unit: DistanceUnit = match interaction.guild_id {
+            Some(guild_id) => match app_state.get_guild_config(guild_id.0.into()).await {
+                Ok(config) => config
+                    .and_then(|config| config.units.parse().ok())
+                    .unwrap_or_default(),
+                Err(e) => {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+            },
+            None => DistanceUnit::default(),
+        };
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+
+        let embed = match load_maps_data_to_embed(
+            address.to_string(),
+            &CONFIG.destinations,
+            &CONFIG.travel_modes,
+            unit,
+            &branding,
+            app_state,
+        )
+        .await
+        {
+            Ok(embed) => embed,
+            Err(e) => {
+                return Err(CommandResponse::ComplexFailure {
+                    response: format!("Couldn't find distances for {}", address),
+                    kind: FailureMessageKind::Error,
+                    log_message: e.to_string(),
+                });
+            }
+        };
+
+        Ok(CommandResponse::Followup(
+            CreateInteractionResponseFollowup::new().embed(embed),
+        ))
+    }
+}
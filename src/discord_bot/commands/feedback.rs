@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedValue},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseFollowup},
+    prelude::Context,
+};
+
+use crate::{
+    state::{AppState, CONFIG},
+    timeout::with_timeout,
+};
+
+use super::{
+    command::{Command, ResponseMode},
+    util::{CommandResponse, FailureMessageKind},
+};
+
+/// how long to wait for the webhook to accept the feedback
+const FEEDBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the longest message that can be sent, so it fits in the description of an embed
+const MAX_FEEDBACK_LENGTH: u16 = 2000;
+
+pub struct FeedbackCommand<'a> {
+    message: &'a str,
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for FeedbackCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let mut message = None;
+        for option in interaction.data.options() {
+            if let ("message", ResolvedValue::String(val)) = (option.name, option.value) {
+                message = Some(val);
+            }
+        }
+
+        Ok(Self {
+            message: message.ok_or("No message provided")?,
+        })
+    }
+}
+
+/// whether a url is a discord webhook, which expects a message rather than arbitrary json
+fn is_discord_webhook(url: &Url) -> bool {
+    matches!(
+        url.host_str(),
+        Some("discord.com" | "discordapp.com" | "canary.discord.com" | "ptb.discord.com")
+    ) && url.path().starts_with("/api/webhooks/")
+}
+
+/// the body posted to the webhook. Discord webhooks are sent an embed with mentions disabled, so
+/// feedback can't ping anyone, while any other endpoint is sent the fields as plain json
+fn feedback_body(
+    url: &Url,
+    message: &str,
+    interaction: &CommandInteraction,
+    guild_name: Option<String>,
+) -> Value {
+    let timestamp = Utc::now().to_rfc3339();
+    let guild_id = interaction.guild_id.map(|g| g.to_string());
+
+    if !is_discord_webhook(url) {
+        return json!({
+            "message": message,
+            "user_id": interaction.user.id.to_string(),
+            "user_name": interaction.user.name,
+            "guild_id": guild_id,
+            "guild_name": guild_name,
+            "timestamp": timestamp,
+        });
+    }
+
+    let guild = match (guild_name, guild_id) {
+        (Some(name), Some(id)) => format!("{} ({})", name, id),
+        (None, Some(id)) => id,
+        _ => String::from("direct message"),
+    };
+    json!({
+        "allowed_mentions": { "parse": [] },
+        "embeds": [{
+            "title": "Feedback",
+            "description": message,
+            "fields": [
+                {
+                    "name": "From",
+                    "value": format!("{} (<@{}>)", interaction.user.name, interaction.user.id),
+                    "inline": true,
+                },
+                { "name": "Guild", "value": guild, "inline": true },
+            ],
+            "timestamp": timestamp,
+        }],
+    })
+}
+
+#[async_trait]
+impl<'a> Command<'a> for FeedbackCommand<'a> {
+    fn name() -> &'static str {
+        "feedback"
+    }
+
+    fn description() -> &'static str {
+        "Report a bug or suggest an improvement to the bot's maintainer"
+    }
+
+    fn cooldown() -> Option<Duration> {
+        Some(Duration::from_secs(5 * 60))
+    }
+
+    fn response_mode() -> ResponseMode {
+        // the webhook is an external service, which may be slow to respond
+        ResponseMode::Deferred { ephemeral: true }
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "message",
+                "What went wrong, or what you'd like changed",
+            )
+            .required(true)
+            .max_length(MAX_FEEDBACK_LENGTH),
+        )
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        _: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let url = match CONFIG.feedback_webhook.as_deref().map(Url::parse) {
+            Some(Ok(url)) => url,
+            Some(Err(e)) => {
+                return Err(CommandResponse::ComplexFailure {
+                    response: String::from("Feedback couldn't be sent, try again later"),
+                    kind: FailureMessageKind::Error,
+                    log_message: format!("the feedback webhook is not a valid url: {}", e),
+                });
+            }
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "feedback isn't set up for this bot",
+                )));
+            }
+        };
+
+        let guild_name = interaction
+            .guild_id
+            .and_then(|guild_id| ctx.cache.guild(guild_id).map(|g| g.name.clone()));
+        let body = feedback_body(&url, self.message, interaction, guild_name);
+
+        let sent = with_timeout(
+            "the feedback webhook",
+            FEEDBACK_TIMEOUT,
+            Client::new().post(url).json(&body).send(),
+        )
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|response| response.map_err(|e| e.to_string()))
+        .and_then(|response| {
+            response
+                .error_for_status()
+                .map_err(|e| e.to_string())
+                .map(|_| ())
+        });
+
+        if let Err(e) = sent {
+            return Err(CommandResponse::ComplexFailure {
+                response: String::from("Feedback couldn't be sent, try again later"),
+                kind: FailureMessageKind::Error,
+                log_message: format!("failed to send feedback: {}", e),
+            });
+        }
+
+        Ok(CommandResponse::Followup(
+            CreateInteractionResponseFollowup::new()
+                .content("Thanks, your feedback has been sent!")
+                .ephemeral(true),
+        ))
+    }
+}
@@ -0,0 +1,392 @@
+use log::error;
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedOption, ResolvedValue, UserId},
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::flatmates::{NewFlatmate, SerenityFlatmateDatabase},
+        guilds::sync_commands,
+    },
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    util::{guild_branding, guild_currency, is_admin, CommandResponse},
+};
+
+/// option names already used by /pay, which a flatmate's name must not collide with
+const RESERVED_NAMES: &[&str] = &[
+    "purpose",
+    "account",
+    "total",
+    "split",
+    "participants",
+    "rest",
+    "preview",
+    "category",
+];
+
+pub enum FlatmateCommand<'a> {
+    Add {
+        user: UserId,
+        display_name: &'a str,
+    },
+    Remove(UserId),
+    /// set a flatmate's weekly income, None clears it
+    Income {
+        user: UserId,
+        amount: Option<f64>,
+    },
+    List,
+}
+
+/// derive the name used for a flatmate's /pay option from their display name, discord only
+/// allows lowercase letters, numbers, dashes, and underscores
+fn option_name_for(display_name: &str) -> String {
+    display_name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .take(32)
+        .collect()
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for FlatmateCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        match options.into_iter().next() {
+            Some(ResolvedOption {
+                name: "add",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => {
+                let mut user = None;
+                let mut display_name = None;
+
+                for option in options {
+                    match (option.name, option.value) {
+                        ("user", ResolvedValue::User(val, _)) => user = Some(val.id),
+                        ("name", ResolvedValue::String(val)) => display_name = Some(val),
+                        _ => {}
+                    }
+                }
+
+                Ok(FlatmateCommand::Add {
+                    user: user.ok_or("user is required")?,
+                    display_name: display_name.ok_or("name is required")?,
+                })
+            }
+            Some(ResolvedOption {
+                name: "remove",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => match options.first().map(|o| &o.value) {
+                Some(ResolvedValue::User(user, _)) => Ok(FlatmateCommand::Remove(user.id)),
+                _ => Err(String::from("user is required")),
+            },
+            Some(ResolvedOption {
+                name: "income",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => {
+                let mut user = None;
+                let mut amount = None;
+
+                for option in options {
+                    match (option.name, option.value) {
+                        ("user", ResolvedValue::User(val, _)) => user = Some(val.id),
+                        ("amount", ResolvedValue::Number(val)) => amount = Some(val),
+                        _ => {}
+                    }
+                }
+
+                Ok(FlatmateCommand::Income {
+                    user: user.ok_or("user is required")?,
+                    amount,
+                })
+            }
+            Some(ResolvedOption { name: "list", .. }) => Ok(FlatmateCommand::List),
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
+/// reload the roster, and update the commands of every guild so that the /pay options match it
+async fn refresh_roster(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<(), CommandResponse> {
+    if let Err(e) = app_state.reload_flatmates().await {
+        return Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        )));
+    }
+
+    // the roster is shared by every guild, so each needs its /pay options updated, starting
+    // with the guild the change was made in
+    let mut guilds = ctx.cache.guilds();
+    if let Some(guild) = interaction.guild_id {
+        guilds.retain(|g| *g != guild);
+        guilds.insert(0, guild);
+    }
+    for guild in guilds {
+        if let Err(e) = sync_commands(guild, app_state, ctx).await {
+            error!("failed to update commands for guild {}: {}", guild, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl<'a> Command<'a> for FlatmateCommand<'a> {
+    fn name() -> &'static str {
+        "flatmate"
+    }
+
+    fn description() -> &'static str {
+        "manage the flatmates living in the flat"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        let add =
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "add a new flatmate")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "The new flatmate")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "The name to show for the flatmate",
+                    )
+                    .required(true)
+                    .max_length(32),
+                );
+
+        let remove = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "remove",
+            "remove a flatmate who has moved out",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The flatmate to remove")
+                .required(true),
+        );
+
+        let income = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "income",
+            "set a flatmate's weekly income, used to check whether listings are affordable",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The flatmate")
+                .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "amount",
+                "Their weekly income after tax, leave empty to clear it",
+            )
+            .required(false)
+            .min_number_value(0.0),
+        );
+
+        let list = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show everyone currently living in the flat",
+        );
+
+        cmd.add_option(add)
+            .add_option(remove)
+            .add_option(income)
+            .add_option(list)
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        if !is_admin(interaction, app_state, ctx).await {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "insufficient permissions",
+            )));
+        }
+
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        let embed = match self {
+            FlatmateCommand::Add { user, display_name } => {
+                let discord_id: u64 = user.into();
+                let name = option_name_for(display_name);
+
+                if name.is_empty() {
+                    return Err(CommandResponse::BasicFailure(String::from(
+                        "the name must contain at least one letter or number",
+                    )));
+                }
+
+                if RESERVED_NAMES.contains(&name.as_str())
+                    || name.starts_with("receipt")
+                    || app_state
+                        .flatmates()
+                        .iter()
+                        .any(|f| f.discord_id != discord_id && f.name.to_ascii_lowercase() == name)
+                {
+                    return Err(CommandResponse::BasicFailure(format!(
+                        "the name {} is already in use, try another",
+                        display_name
+                    )));
+                }
+
+                if let Err(e) = app_state
+                    .add_flatmate(NewFlatmate {
+                        discord_id,
+                        name: &name,
+                        display_name,
+                    })
+                    .await
+                {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+
+                refresh_roster(interaction, app_state, ctx).await?;
+
+                branding
+                    .embed()
+                    .description(format!(
+                        "Welcome {} (<@{}>) to the flat!",
+                        display_name, discord_id
+                    ))
+                    .color(EmbedColor::Green as u32)
+            }
+            FlatmateCommand::Remove(user) => {
+                let discord_id: u64 = user.into();
+
+                let flatmate = match app_state.remove_flatmate(discord_id).await {
+                    Ok(Some(flatmate)) => flatmate,
+                    Ok(None) => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "<@{}> is not a current flatmate",
+                            discord_id
+                        )));
+                    }
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                refresh_roster(interaction, app_state, ctx).await?;
+
+                branding
+                    .embed()
+                    .description(format!(
+                        "Removed {} (<@{}>) from the flat",
+                        flatmate.display_name, discord_id
+                    ))
+                    .color(EmbedColor::Orange as u32)
+            }
+            FlatmateCommand::Income { user, amount } => {
+                let discord_id: u64 = user.into();
+                let income = amount.map(|amount| (amount * 100.0).round() as i64);
+
+                let flatmate = match app_state.set_flatmate_income(discord_id, income).await {
+                    Ok(Some(flatmate)) => flatmate,
+                    Ok(None) => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "<@{}> is not a current flatmate",
+                            discord_id
+                        )));
+                    }
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if let Err(e) = app_state.reload_flatmates().await {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+
+                let currency = guild_currency(interaction.guild_id, app_state).await?;
+                let description = match income {
+                    Some(income) => format!(
+                        "Set the weekly income of {} to {}",
+                        flatmate.display_name,
+                        format_money(income, currency)
+                    ),
+                    None => format!("Cleared the weekly income of {}", flatmate.display_name),
+                };
+
+                // incomes are private, so only the admin who set it sees the confirmation
+                return Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .embed(
+                                branding
+                                    .embed()
+                                    .description(description)
+                                    .color(EmbedColor::Green as u32),
+                            )
+                            .ephemeral(true),
+                    ),
+                ));
+            }
+            FlatmateCommand::List => {
+                let flatmates = app_state.flatmates();
+
+                let description = if flatmates.is_empty() {
+                    String::from("There are no flatmates, add one with /flatmate add")
+                } else {
+                    flatmates
+                        .iter()
+                        .map(|f| format!("{} - <@{}>", f.display_name, f.discord_id))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+
+                branding
+                    .embed()
+                    .title("Flatmates")
+                    .description(description)
+                    .color(EmbedColor::Green as u32)
+            }
+        };
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(embed),
+            ),
+        ))
+    }
+}
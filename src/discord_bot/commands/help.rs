@@ -0,0 +1,77 @@
+use serenity::{
+    all::CommandInteraction,
+    async_trait,
+    builder::{CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{common::embed::EmbedColor, messages::reactor_descriptions},
+    state::AppState,
+};
+
+use super::{
+    command::{command_descriptions, Command},
+    util::{guild_branding, CommandResponse},
+};
+
+pub struct HelpCommand;
+
+impl<'a> TryFrom<&'a CommandInteraction> for HelpCommand {
+    type Error = String;
+    fn try_from(_: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for HelpCommand {
+    fn name() -> &'static str {
+        "help"
+    }
+
+    fn description() -> &'static str {
+        "List everything the bot can do"
+    }
+
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
+        i
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        let commands = command_descriptions()
+            .into_iter()
+            .map(|(name, description)| format!("`/{}` - {}", name, description))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        // reactors respond to ordinary messages, so they are listed separately from the commands
+        let reactors = reactor_descriptions()
+            .into_iter()
+            .map(|(name, description)| format!("**{}** - {}", name, description))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(
+                        branding
+                            .embed()
+                            .title("Help")
+                            .description(format!("**Commands**\n{}", commands))
+                            .field("Message reactors", reactors, false)
+                            .footer(branding.flavour_footer(&mut rand::thread_rng()))
+                            .color(EmbedColor::Green as u32),
+                    )
+                    .ephemeral(true),
+            ),
+        ))
+    }
+}
@@ -28,7 +28,7 @@ impl<'a> Command<'a> for HideCommand {
         "Creates a large message to hide previous messages in the chat"
     }
 
-    fn get_application_command_options(i: CreateCommand) -> CreateCommand {
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
         i
     }
 
@@ -0,0 +1,286 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedValue, UserId},
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::bills::SerenityBillDatabase,
+    },
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    util::{display_name_for, guild_branding, guild_currency, paginate, CommandResponse},
+};
+
+/// the number of bills shown on each page of /history
+const BILLS_PER_PAGE: usize = 10;
+
+/// how far back /history looks when no start date is given
+const DEFAULT_DAYS: i64 = 30;
+
+pub struct HistoryCommand<'a> {
+    from: Option<&'a str>,
+    to: Option<&'a str>,
+    flatmate: Option<UserId>,
+}
+
+/// parse a day in the past such as "today", "yesterday", "monday", "3 days ago", "2 weeks ago",
+/// "2023-06-20", "20/6/23", "20 june 2023", or "20/6"
+fn parse_past_day(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Some(today),
+        "yesterday" => return Some(today - Duration::days(1)),
+        "last week" => return Some(today - Duration::weeks(1)),
+        "last month" => return Some(today - Duration::days(DEFAULT_DAYS)),
+        _ => {}
+    }
+
+    // a weekday is the most recent one, including today
+    let weekday = input.strip_prefix("last ").unwrap_or(&input);
+    if let Ok(weekday) = weekday.parse::<Weekday>() {
+        let days = (today.weekday().num_days_from_monday() as i64
+            - weekday.num_days_from_monday() as i64)
+            .rem_euclid(7);
+        return Some(today - Duration::days(days));
+    }
+
+    if let Some(ago) = input.strip_suffix(" ago") {
+        let (count, unit) = ago.split_once(' ')?;
+        let count: i64 = count.parse().ok()?;
+        let days_per_unit = match unit.trim_end_matches('s') {
+            "day" => 1,
+            "week" => 7,
+            "month" => DEFAULT_DAYS,
+            _ => return None,
+        };
+        // the count is typed by the user, so a day too far back to represent is refused rather
+        // than overflowing
+        let millis = count
+            .checked_mul(days_per_unit)?
+            .checked_mul(24 * 60 * 60 * 1000)?;
+        return today.checked_sub_signed(Duration::milliseconds(millis));
+    }
+
+    // two digit years are tried first, as "%Y" would read "23" as the year 23
+    for format in [
+        "%Y-%m-%d", "%d/%m/%y", "%d/%m/%Y", "%d-%m-%Y", "%d %B %Y", "%d %b %Y",
+    ] {
+        if let Ok(date) = NaiveDate::parse_from_str(&input, format) {
+            return Some(date);
+        }
+    }
+
+    // a day without a year is the last time that date came around
+    let (day, month) = input.split_once('/')?;
+    let (day, month) = (day.parse().ok()?, month.parse().ok()?);
+    match NaiveDate::from_ymd_opt(today.year(), month, day) {
+        Some(date) if date <= today => Some(date),
+        _ => NaiveDate::from_ymd_opt(today.year() - 1, month, day),
+    }
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for HistoryCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let mut command = HistoryCommand {
+            from: None,
+            to: None,
+            flatmate: None,
+        };
+
+        for option in interaction.data.options() {
+            match (option.name, option.value) {
+                ("from", ResolvedValue::String(val)) => command.from = Some(val),
+                ("to", ResolvedValue::String(val)) => command.to = Some(val),
+                ("flatmate", ResolvedValue::User(user, _)) => command.flatmate = Some(user.id),
+                _ => {}
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for HistoryCommand<'a> {
+    fn name() -> &'static str {
+        "history"
+    }
+
+    fn description() -> &'static str {
+        "List the bills created between two dates"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "from",
+                "The first day to include, e.g. 1/6/23 or 2 weeks ago, defaults to 30 days ago",
+            )
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "to",
+                "The last day to include, e.g. yesterday, defaults to today",
+            )
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                "flatmate",
+                "Only show bills this flatmate created or has a share of",
+            )
+            .required(false),
+        )
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let today = Local::now().date_naive();
+
+        let from = match self.from {
+            Some(from) => parse_past_day(from, today).ok_or_else(|| {
+                CommandResponse::BasicFailure(format!("I don't understand the date {}", from))
+            })?,
+            None => today - Duration::days(DEFAULT_DAYS),
+        };
+        let to = match self.to {
+            Some(to) => parse_past_day(to, today).ok_or_else(|| {
+                CommandResponse::BasicFailure(format!("I don't understand the date {}", to))
+            })?,
+            None => today,
+        };
+
+        if from > to {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "the start date must be before the end date",
+            )));
+        }
+
+        let bills = match app_state
+            .get_bills_for_guild(interaction.guild_id.map(|g| g.0.into()))
+            .await
+        {
+            Ok(bills) => bills,
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        let flatmate = self.flatmate.map(|id| u64::from(id) as i64);
+        let flatmates = app_state.all_flatmates();
+        let lines: Vec<String> = bills
+            .iter()
+            .filter(|(bill, _)| (from..=to).contains(&bill.created_at.date()))
+            .filter_map(|(bill, shares)| {
+                let share = flatmate.and_then(|id| shares.iter().find(|s| s.user_id == id));
+                let line = format!(
+                    "`{}` **{}** - {}, by {}",
+                    bill.created_at.format("%d/%m/%y"),
+                    bill.purpose,
+                    format_money(bill.total, currency),
+                    display_name_for(&flatmates, bill.created_by as u64)
+                );
+
+                match (flatmate, share) {
+                    (None, _) => Some(line),
+                    (Some(_), Some(share)) => Some(format!(
+                        "{} ({}'s share {}{})",
+                        line,
+                        display_name_for(&flatmates, share.user_id as u64),
+                        format_money(share.amount, currency),
+                        if share.paid { ", paid" } else { "" }
+                    )),
+                    (Some(id), None) if bill.created_by == id => Some(line),
+                    (Some(_), None) => None,
+                }
+            })
+            .collect();
+
+        if lines.is_empty() {
+            return Err(CommandResponse::BasicFailure(format!(
+                "there are no bills between {} and {}",
+                from.format("%-d %B %Y"),
+                to.format("%-d %B %Y")
+            )));
+        }
+
+        let (embed, components) = paginate(
+            interaction.id.into(),
+            &format!(
+                "Bills from {} to {}",
+                from.format("%-d %B"),
+                to.format("%-d %B %Y")
+            ),
+            EmbedColor::Green as u32,
+            &branding,
+            &lines,
+            BILLS_PER_PAGE,
+        );
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(components),
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 6, 21).unwrap()
+    }
+
+    #[test]
+    fn parses_days_ago() {
+        assert_eq!(
+            parse_past_day("3 days ago", today()),
+            NaiveDate::from_ymd_opt(2023, 6, 18)
+        );
+        assert_eq!(
+            parse_past_day("2 weeks ago", today()),
+            NaiveDate::from_ymd_opt(2023, 6, 7)
+        );
+    }
+
+    #[test]
+    fn refuses_out_of_range_counts() {
+        // too large for a duration
+        assert_eq!(parse_past_day("99999999999 weeks ago", today()), None);
+        assert_eq!(
+            parse_past_day("9223372036854775807 months ago", today()),
+            None
+        );
+        // a valid duration, but before the first representable date
+        assert_eq!(parse_past_day("99999999 days ago", today()), None);
+    }
+}
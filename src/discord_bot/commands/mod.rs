@@ -1,10 +1,31 @@
 mod command;
-mod util;
+pub mod util;
 
+mod balance;
+mod chore;
+mod compare;
+mod config;
+mod discord;
+mod distance;
+mod feedback;
+mod flatmate;
+mod help;
 mod hide;
+mod history;
 mod pay;
 mod ping;
+mod recurring;
+mod refresh_distance;
+mod remind;
 mod say;
+mod settle;
 mod shop;
+mod spending;
+mod summary;
+mod whoami;
 
-pub use command::{application_command, autocomplete, command, interaction};
+pub use chore::rotate_chores;
+pub use command::{application_command, autocomplete, command, interaction, modal};
+pub use recurring::post_recurring_bills;
+pub use remind::send_due_reminders;
+pub use summary::post_weekly_summary;
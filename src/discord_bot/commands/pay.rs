@@ -1,15 +1,24 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::error;
 
 use serenity::{
     all::{
-        AutocompleteOption, ButtonStyle, CommandInteraction, CommandOptionType,
-        ComponentInteraction, ResolvedValue,
+        ActionRowComponent, AutocompleteOption, ButtonStyle, ChannelId, CommandInteraction,
+        CommandOptionType, ComponentInteraction, GuildId, InputTextStyle, MessageId,
+        ModalInteraction, ResolvedOption, ResolvedValue, UserId,
     },
     async_trait,
     builder::{
-        AutocompleteChoice, CreateActionRow, CreateAttachment, CreateAutocompleteResponse,
-        CreateButton, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
-        CreateInteractionResponse, CreateInteractionResponseMessage, EditMessage,
+        AutocompleteChoice, CreateActionRow, CreateAutocompleteResponse, CreateButton,
+        CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter, CreateInputText,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateModal,
+        EditInteractionResponse, EditMessage,
     },
     json::Value,
     model::prelude::Attachment,
@@ -17,18 +26,118 @@ use serenity::{
 };
 
 use crate::{
-    discord_bot::common::embed::EmbedColor,
+    discord_bot::{
+        common::{
+            currency::{format_money, Currency},
+            embed::EmbedColor,
+            locale::{Locale, Text},
+        },
+        database::bills::{BillModel, BillShareModel, NewBill, SerenityBillDatabase},
+    },
     state::{AppState, Flatmate, CONFIG},
 };
 
 use super::{
-    command::{AutocompleteCommand, Command, InteractionCommand},
-    util::CommandResponse,
+    command::{Command, InteractionCommand, ModalCommand},
+    discord::DiscordApi,
+    settle::settle_up,
+    util::{
+        display_name_for, find_flatmate, guild_branding, guild_currency, guild_locale, paginate,
+        Branding, CommandResponse,
+    },
 };
 
+/// the maximum number of receipts which can be attached to a single bill
+const MAX_RECEIPTS: usize = 4;
+
+/// the most flatmates the amounts form can ask about, as discord limits a modal to five inputs
+const MAX_FORM_FLATMATES: usize = 5;
+
+/// how long a bill waits for its amounts form to be submitted, after which the interaction it
+/// would be posted with has expired anyway
+const PENDING_BILL_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// how recently the same flatmate must have created a bill with the same purpose and a similar
+/// total for a new bill to be treated as a duplicate of it
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// the category of a bill which wasn't given one, or was given one no longer configured
+pub(super) const UNCATEGORIZED: &str = "uncategorized";
+
+/// a bill from /pay create which is waiting on its amounts to be entered in a form
+struct PendingBill {
+    purpose: String,
+    receipts: Vec<String>,
+    inline_receipt: Option<String>,
+    total: Option<f64>,
+    account: String,
+    category: String,
+    participants: Vec<Flatmate>,
+    preview: bool,
+    currency: Currency,
+    branding: Branding,
+    created_at: Instant,
+}
+
+/// a bill held back as a likely duplicate, which is posted if its creator confirms it
+struct DuplicateBill {
+    purpose: String,
+    receipts: Vec<String>,
+    inline_receipt: Option<String>,
+    total: f64,
+    amounts: Vec<(Flatmate, f64)>,
+    account: String,
+    category: String,
+    split_even: bool,
+    currency: Currency,
+    branding: Branding,
+    created_at: Instant,
+}
+
+lazy_static! {
+    /// every bill with an open amounts form, keyed by the interaction that opened it
+    static ref PENDING_BILLS: Mutex<HashMap<u64, PendingBill>> = Mutex::new(HashMap::new());
+
+    /// every bill waiting on its creator to confirm it isn't a duplicate, keyed by the
+    /// interaction that created it
+    static ref PENDING_DUPLICATES: Mutex<HashMap<u64, DuplicateBill>> = Mutex::new(HashMap::new());
+}
+
+/// add a choice to an option for each configured bill category. Discord allows at most 25
+/// choices, and one is left free for /spending to offer uncategorized bills
+pub(super) fn add_category_choices(mut option: CreateCommandOption) -> CreateCommandOption {
+    for category in CONFIG.bill_categories.iter().take(24) {
+        let mut name = category.clone();
+        if let Some(first) = name.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        option = option.add_string_choice(name, category);
+    }
+    option
+}
+
+/// the category a bill was filed under, falling back to uncategorized if it is no longer
+/// configured, as the choices discord offers may be out of date
+fn bill_category(category: Option<&str>) -> &str {
+    match category {
+        Some(category) if CONFIG.bill_categories.iter().any(|c| c == category) => category,
+        _ => UNCATEGORIZED,
+    }
+}
+
+/// the options describing a bill, which for /pay are nested under the create subcommand
+fn bill_options(interaction: &CommandInteraction) -> Vec<ResolvedOption<'_>> {
+    let mut options = interaction.data.options();
+    if let Some(ResolvedValue::SubCommand(nested)) = options.first_mut().map(|o| &mut o.value) {
+        return std::mem::take(nested);
+    }
+    options
+}
+
 async fn handle_autocomplete_for_pay<'c>(
     interaction: &'c CommandInteraction,
     autocomplete: &'c AutocompleteOption<'_>,
+    app_state: &'c AppState,
 ) -> Result<CreateAutocompleteResponse, CommandResponse> {
     let mut response = CreateAutocompleteResponse::new();
 
@@ -54,23 +163,20 @@ async fn handle_autocomplete_for_pay<'c>(
                 },
             ]);
         }
-        i if CONFIG
-            .flatmates
-            .iter()
-            .any(|f| f.name.to_ascii_lowercase() == *i) =>
-        {
+        i if app_state.flatmate_names().iter().any(|name| name == i) => {
             // load all previous values that have been entered as options, and use those as the options for payment
             // this will allow for easy re-use of values
 
             let mut existing_options: HashSet<String> = HashSet::default();
+            let currency = guild_currency(interaction.guild_id, app_state).await?;
 
-            for option in interaction.data.options().iter_mut() {
+            for option in bill_options(interaction).iter() {
                 if matches!(option.value, ResolvedValue::Unresolved(_)) {
                     continue;
                 }
 
                 match option.name {
-                    "purpose" | "receipt" => {
+                    "purpose" | "receipt" | "total" => {
                         continue;
                     }
                     i => {
@@ -78,8 +184,14 @@ async fn handle_autocomplete_for_pay<'c>(
                             let num_str = format!("{:.2}", num);
                             // check if existing num is in the list of options
                             if !existing_options.contains(&num_str) {
-                                response = response
-                                    .add_number_choice(format!("Same as {} ({:.2})", i, num), num);
+                                response = response.add_number_choice(
+                                    format!(
+                                        "Same as {} ({})",
+                                        i,
+                                        format_money(to_cents(num), currency)
+                                    ),
+                                    num,
+                                );
                                 existing_options.insert(num_str);
                             }
                         }
@@ -97,74 +209,1244 @@ async fn handle_autocomplete_for_pay<'c>(
     Ok(response)
 }
 
+/// compare the sum of all shares against the stated total of a bill, working in cents to avoid
+/// floating point noise. Returns a description of the discrepancy if they do not match.
+fn check_shares_match_total(shares: f64, total: f64, currency: Currency) -> Option<String> {
+    let shares_cents = to_cents(shares);
+    let total_cents = to_cents(total);
+
+    match shares_cents.cmp(&total_cents) {
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Less => Some(format!(
+            "shares sum to {} but total is {}, {} unaccounted for",
+            format_money(shares_cents, currency),
+            format_money(total_cents, currency),
+            format_money(total_cents - shares_cents, currency)
+        )),
+        std::cmp::Ordering::Greater => Some(format!(
+            "shares sum to {} but total is {}, {} over the total",
+            format_money(shares_cents, currency),
+            format_money(total_cents, currency),
+            format_money(shares_cents - total_cents, currency)
+        )),
+    }
+}
+
+/// divide a total evenly between the provided flatmates, any remaining cents which cannot be
+/// divided evenly are assigned to the first flatmate so the shares reconcile exactly.
+pub(super) fn split_evenly(total: f64, flatmates: &[Flatmate]) -> Vec<(&Flatmate, f64)> {
+    if flatmates.is_empty() {
+        return Vec::new();
+    }
+
+    let total_cents = to_cents(total);
+    let individual = total_cents / flatmates.len() as i64;
+    let remainder = total_cents - individual * flatmates.len() as i64;
+
+    flatmates
+        .iter()
+        .enumerate()
+        .map(|(i, flatmate)| {
+            let share = if i == 0 {
+                individual + remainder
+            } else {
+                individual
+            };
+            (flatmate, share as f64 / 100.0)
+        })
+        .collect()
+}
+
+/// find the flatmates named in a list of participants, separated by commas or spaces, where each
+/// is either a mention or the name of a current flatmate
+fn parse_participants(value: &str, flatmates: &[Flatmate]) -> Result<Vec<Flatmate>, String> {
+    let mut participants: Vec<Flatmate> = Vec::new();
+
+    for token in value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+    {
+        let mention = token
+            .strip_prefix("<@")
+            .and_then(|t| t.strip_suffix('>'))
+            .map(|t| t.trim_start_matches('!'))
+            .and_then(|t| t.parse::<u64>().ok());
+
+        let flatmate = match mention {
+            Some(id) => flatmates
+                .iter()
+                .find(|f| f.discord_id == id)
+                .ok_or_else(|| format!("{} is not a current flatmate", token))?,
+            None => find_flatmate(flatmates, token)?,
+        };
+
+        if !participants
+            .iter()
+            .any(|p| p.discord_id == flatmate.discord_id)
+        {
+            participants.push(flatmate.clone());
+        }
+    }
+
+    if participants.is_empty() {
+        return Err(String::from("no participants were given"));
+    }
+
+    Ok(participants)
+}
+
+/// build the embed describing a bill, shared by every command which creates one
+#[allow(clippy::too_many_arguments)]
+pub(super) fn bill_embed(
+    purpose: &str,
+    user: &str,
+    total: f64,
+    amounts: Vec<(&Flatmate, f64)>,
+    account: &str,
+    split_even: bool,
+    currency: Currency,
+    branding: &Branding,
+) -> CreateEmbed {
+    branding
+        .embed()
+        .title("Bill created")
+        .description(format!(
+            "Bill for {} totalling {} created by {} on {} to be paid into `{}`{}",
+            purpose,
+            format_money(to_cents(total), currency),
+            user,
+            chrono::offset::Local::now().format("%d/%m/%y at %I:%M%P"),
+            account,
+            if split_even {
+                "\nThis bill has been split evenly."
+            } else {
+                ""
+            }
+        ))
+        .color(EmbedColor::Red as u32)
+        .fields({
+            let mut fields: Vec<(String, String, bool)> = Vec::with_capacity(amounts.len());
+            for (flatmate, amount) in amounts {
+                if amount == 0.0 {
+                    continue;
+                }
+
+                fields.push((
+                    format!("Amount for {} to pay:", flatmate.display_name),
+                    format_money(to_cents(amount), currency),
+                    false,
+                ));
+            }
+
+            fields
+        })
+        .footer(branding.flavour_footer(&mut rand::thread_rng()))
+}
+
+/// build the buttons attached to a bill, with a link to each receipt if there are any. The paid
+/// and cancel buttons refer to the bill by its id, so are only added once the bill is stored
+pub(super) fn bill_components(bill_id: Option<i32>, receipts: &[&str]) -> Vec<CreateActionRow> {
+    let mut components = Vec::with_capacity(2);
+    if let Some(bill_id) = bill_id {
+        components.push(CreateActionRow::Buttons({
+            vec![
+                CreateButton::new(format!("{}{}", PAID_BUTTON_PREFIX, bill_id))
+                    .style(ButtonStyle::Success)
+                    .label("Paid!"),
+                CreateButton::new(format!("{}{}", SETTLE_BUTTON_PREFIX, bill_id))
+                    .style(ButtonStyle::Secondary)
+                    .label("Mark all paid"),
+                CreateButton::new(format!("{}{}", CANCEL_BUTTON_PREFIX, bill_id))
+                    .style(ButtonStyle::Danger)
+                    .label("Cancel"),
+            ]
+        }));
+    }
+
+    // discord allows up to 5 buttons per row, so the receipts get a row of their own
+    match receipts {
+        [] => {}
+        [receipt] => components.push(CreateActionRow::Buttons(vec![CreateButton::new_link(
+            *receipt,
+        )
+        .label("Receipt")])),
+        receipts => components.push(CreateActionRow::Buttons(
+            receipts
+                .iter()
+                .enumerate()
+                .map(|(i, receipt)| {
+                    CreateButton::new_link(*receipt).label(format!("Receipt {}", i + 1))
+                })
+                .collect(),
+        )),
+    }
+
+    components
+}
+
+/// how a receipt is shown on a bill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReceiptKind {
+    /// an image discord can show inline, attached to the bill message
+    Image,
+    /// a file discord can't preview, such as a pdf or heic photo, only linked from the bill
+    Document,
+}
+
+/// work out how a receipt should be shown from its mime type, falling back to the extension of
+/// the file if discord did not provide one. Receipts in unsupported formats are rejected with a
+/// message for the user
+fn receipt_kind(attachment: &Attachment) -> Result<ReceiptKind, String> {
+    // mime types can carry parameters, e.g. "image/jpeg; charset=utf-8"
+    let content_type = attachment
+        .content_type
+        .as_deref()
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_ascii_lowercase());
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+
+    match (content_type.as_deref(), extension.as_deref()) {
+        (Some("image/jpeg" | "image/jpg" | "image/png"), _) => Ok(ReceiptKind::Image),
+        (Some("image/heic" | "image/heif" | "application/pdf"), _) => Ok(ReceiptKind::Document),
+        (None, Some("jpg" | "jpeg" | "png")) => Ok(ReceiptKind::Image),
+        (None, Some("heic" | "heif" | "pdf")) => Ok(ReceiptKind::Document),
+        _ => Err(format!(
+            "{} isn't a supported receipt, attach a jpg, png, heic, or pdf instead",
+            attachment.filename
+        )),
+    }
+}
+
+/// pick the receipt to show inline on a bill, the first which is an image. Every receipt is
+/// still linked from the bill's buttons
+fn inline_receipt<'a>(receipts: &[&'a Attachment]) -> Result<Option<&'a str>, CommandResponse> {
+    let mut inline = None;
+    for receipt in receipts {
+        match receipt_kind(receipt) {
+            Ok(ReceiptKind::Image) if inline.is_none() => inline = Some(receipt.url.as_str()),
+            Ok(_) => {}
+            Err(e) => return Err(CommandResponse::BasicFailure(e)),
+        }
+    }
+    Ok(inline)
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn create_response<'a>(
+    bill_id: Option<i32>,
     purpose: &str,
     user: &str,
-    receipt: &str,
+    receipts: &[&str],
+    inline_receipt: Option<&str>,
     total: f64,
     amounts: Vec<(&Flatmate, f64)>,
     account: &str,
-    ctx: &Context,
-) -> CreateInteractionResponse {
-    CreateInteractionResponse::Message(
-        CreateInteractionResponseMessage::new()
-            .embed(
-                CreateEmbed::new()
-                    .title("Bill created")
-                    .description(format!(
-                        "Bill for {} totalling ${:.2} created by {} on {} to be paid into `{}`",
+    split_even: bool,
+    currency: Currency,
+    branding: &Branding,
+    discord: &impl DiscordApi,
+) -> Result<CreateInteractionResponse, CommandResponse> {
+    let mut message = CreateInteractionResponseMessage::new()
+        .embed(bill_embed(
+            purpose, user, total, amounts, account, split_even, currency, branding,
+        ))
+        .components(bill_components(bill_id, receipts));
+
+    if let Some(receipt) = inline_receipt {
+        match discord.download_attachment(receipt).await {
+            Ok(attachment) => message = message.add_file(attachment),
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "Failed to download receipt: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    Ok(CreateInteractionResponse::Message(message))
+}
+
+/// store a bill before it is posted, so a bill which couldn't be stored is never shown. The
+/// message it is posted in is recorded afterwards with `record_bill_message`
+async fn store_bill(
+    app_state: &AppState,
+    bill: NewBill<'_>,
+    locale: Locale,
+) -> Result<BillModel, CommandResponse> {
+    let purpose = bill.purpose;
+    match app_state.create_bill(bill).await {
+        Ok(bill) => Ok(bill),
+        Err(e) => {
+            error!("unable to store bill {}: {}", purpose, e);
+            Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayUnsaved).to_string(),
+            ))
+        }
+    }
+}
+
+/// record the message a stored bill was posted in, so it can be found again as it is paid. The
+/// bill's buttons carry its id, so it can still be paid if this fails
+async fn record_bill_message(app_state: &AppState, bill: &BillModel, message_id: MessageId) {
+    if let Err(e) = app_state.set_bill_message(bill.id, message_id.into()).await {
+        error!("unable to record the message of bill {}: {}", bill.id, e);
+    }
+}
+
+/// remove a stored bill which couldn't be posted, so nobody is left owing on a bill they can't see
+async fn discard_bill(app_state: &AppState, bill: &BillModel) {
+    if let Err(e) = app_state.delete_bill(bill.id).await {
+        error!("unable to remove unposted bill {}: {}", bill.id, e);
+    }
+}
+
+/// create a bill from the options given to /pay create, store it, and post it. Discord is only
+/// reached through `discord`, so this can be driven by a mock
+async fn create_bill(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+    discord: &impl DiscordApi,
+) -> Result<CommandResponse, CommandResponse> {
+    // extract the options
+    let options = bill_options(interaction);
+    let flatmates = app_state.flatmates();
+    let currency = guild_currency(interaction.guild_id, app_state).await?;
+    let branding = guild_branding(interaction.guild_id, app_state).await?;
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+
+    let mut purpose: Option<&str> = None;
+    let mut receipts: Vec<&Attachment> = Vec::with_capacity(MAX_RECEIPTS);
+    let mut total: Option<f64> = None;
+    let mut split_even = false;
+    let mut split_form = false;
+    let mut participants: Option<&str> = None;
+    let mut rest: Option<&str> = None;
+    let mut preview = false;
+    let mut category: Option<&str> = None;
+    let mut amount = 0.0;
+    let mut amounts: Vec<(&Flatmate, f64)> = Vec::with_capacity(flatmates.len());
+    let mut account = CONFIG.head_tennant_acc_number.as_ref();
+
+    for option in options.iter() {
+        match option.name {
+            "purpose" => {
+                if let ResolvedValue::String(s) = option.value {
+                    purpose = Some(s);
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse purpose as a string".to_string(),
+                    ));
+                }
+            }
+            name if name.starts_with("receipt") => {
+                if let ResolvedValue::Attachment(attachment) = option.value {
+                    // keep the required receipt first, so it is the one shown inline
+                    if name == "receipt" {
+                        receipts.insert(0, attachment);
+                    } else {
+                        receipts.push(attachment);
+                    }
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse receipt as an attachment".to_string(),
+                    ));
+                }
+            }
+            "account" => {
+                if let ResolvedValue::String(s) = option.value {
+                    account = s;
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse account as a string".to_string(),
+                    ));
+                }
+            }
+            "total" => {
+                if let ResolvedValue::Number(n) = option.value {
+                    total = Some(n);
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse total as a number".to_string(),
+                    ));
+                }
+            }
+            "split" => {
+                if let ResolvedValue::String(s) = option.value {
+                    split_even = s == "even";
+                    split_form = s == "form";
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse split as a string".to_string(),
+                    ));
+                }
+            }
+            "participants" => {
+                if let ResolvedValue::String(s) = option.value {
+                    participants = Some(s);
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse participants as a string".to_string(),
+                    ));
+                }
+            }
+            "rest" => {
+                if let ResolvedValue::String(s) = option.value {
+                    rest = Some(s);
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse rest as a string".to_string(),
+                    ));
+                }
+            }
+            "preview" => {
+                if let ResolvedValue::Boolean(b) = option.value {
+                    preview = b;
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse preview as a boolean".to_string(),
+                    ));
+                }
+            }
+            "category" => {
+                if let ResolvedValue::String(s) = option.value {
+                    category = Some(s);
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse category as a string".to_string(),
+                    ));
+                }
+            }
+            _ => {
+                let name = option.name;
+
+                let flatmate = match flatmates
+                    .iter()
+                    .find(|f| f.name.to_ascii_lowercase() == name)
+                {
+                    Some(flatmate) => flatmate,
+                    None => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "{} is not a current flatmate",
+                            name
+                        )));
+                    }
+                };
+
+                if let ResolvedValue::Number(value) = option.value {
+                    amount += value;
+                    amounts.push((flatmate, value));
+                } else {
+                    return Err(CommandResponse::InternalFailure(
+                        "Failed to parse amount as a number".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    // check if initialisation was successful
+    if purpose.is_none() || receipts.is_empty() {
+        return Err(CommandResponse::InternalFailure(
+            "Failed to initialize command".to_string(),
+        ));
+    }
+
+    let purpose = purpose.unwrap();
+    let category = bill_category(category);
+    let receipt_urls: Vec<&str> = receipts.iter().map(|r| r.url.as_str()).collect();
+    let inline = inline_receipt(&receipts)?;
+
+    // only the participants share an even split or are asked about in the form, everyone else
+    // is left off the bill entirely
+    let participants = match participants {
+        Some(_) if !split_even && !split_form => {
+            return Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayParticipantsNeedSplit).to_string(),
+            ))
+        }
+        Some(participants) => {
+            parse_participants(participants, &flatmates).map_err(CommandResponse::BasicFailure)?
+        }
+        None => flatmates.clone(),
+    };
+
+    // whoever covers the rest splits whatever the other amounts leave of the total
+    let rest = match rest {
+        Some(_) if split_even || split_form => {
+            return Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayRestNeedsManualSplit).to_string(),
+            ))
+        }
+        Some(rest) => {
+            parse_participants(rest, &flatmates).map_err(CommandResponse::BasicFailure)?
+        }
+        None => Vec::new(),
+    };
+
+    if split_form {
+        if !amounts.is_empty() {
+            return Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayFormAmountsGiven).to_string(),
+            ));
+        }
+
+        let pending = PendingBill {
+            purpose: purpose.to_string(),
+            receipts: receipt_urls.iter().map(|url| url.to_string()).collect(),
+            inline_receipt: inline.map(String::from),
+            total,
+            account: account.to_string(),
+            category: category.to_string(),
+            participants,
+            preview,
+            currency,
+            branding,
+            created_at: Instant::now(),
+        };
+        return open_amounts_form(interaction, discord, pending).await;
+    }
+
+    if split_even {
+        // an even split ignores any manually entered amounts, and divides the total instead
+        let total = match total {
+            Some(total) => total,
+            None => {
+                return Err(CommandResponse::BasicFailure(
+                    locale.text(Text::PayEvenSplitNeedsTotal).to_string(),
+                ))
+            }
+        };
+
+        amount = total;
+        amounts = split_evenly(total, &participants);
+    } else {
+        if !rest.is_empty() {
+            let total = match total {
+                Some(total) => total,
+                None => {
+                    return Err(CommandResponse::BasicFailure(
+                        locale.text(Text::PayRestNeedsTotal).to_string(),
+                    ))
+                }
+            };
+
+            if let Some(flatmate) = rest
+                .iter()
+                .find(|r| amounts.iter().any(|(f, _)| f.discord_id == r.discord_id))
+            {
+                return Err(CommandResponse::BasicFailure(format!(
+                    "{} was given an amount, so can't also cover the rest",
+                    flatmate.display_name
+                )));
+            }
+
+            let remaining = to_cents(total) - to_cents(amount);
+            if remaining < 0 {
+                return Err(CommandResponse::BasicFailure(format!(
+                    "the amounts are {} over the total, so there is nothing left for the rest",
+                    format_money(-remaining, currency)
+                )));
+            }
+
+            amounts.extend(split_evenly(remaining as f64 / 100.0, &rest));
+            amount = total;
+        }
+
+        if amounts.is_empty() {
+            return Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayNoAmounts).to_string(),
+            ));
+        }
+
+        // if a total was provided, make sure the shares actually add up to it
+        if let Some(total) = total {
+            if let Some(discrepancy) = check_shares_match_total(amount, total, currency) {
+                return Err(CommandResponse::BasicFailure(discrepancy));
+            }
+        }
+    }
+
+    // a preview is only shown to the user who asked for it, and nothing is stored
+    if preview {
+        return Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(locale.text(Text::PayPreview))
+                    .embed(bill_embed(
                         purpose,
-                        total,
-                        user,
-                        chrono::offset::Local::now().format("%d/%m/%y at %I:%M%P"),
-                        account
+                        &interaction.user.name,
+                        amount,
+                        amounts,
+                        account,
+                        split_even,
+                        currency,
+                        &branding,
                     ))
-                    .color(EmbedColor::Red as u32)
-                    .fields({
-                        let mut fields: Vec<(String, String, bool)> =
-                            Vec::with_capacity(amounts.len());
-                        for (flatmate, amount) in amounts {
-                            if amount == 0.0 {
-                                continue;
-                            }
+                    .components(bill_components(None, &receipt_urls))
+                    .ephemeral(true),
+            ),
+        ));
+    }
 
-                            fields.push((
-                                format!("Amount for {} to pay:", flatmate.display_name),
-                                format!("${:.2}", amount),
-                                false,
-                            ));
-                        }
+    let created_by: u64 = interaction.user.id.into();
+    if let Some(duplicate) = find_duplicate(
+        app_state,
+        interaction.guild_id,
+        created_by,
+        purpose,
+        to_cents(amount),
+    )
+    .await?
+    {
+        let held = DuplicateBill {
+            purpose: purpose.to_string(),
+            receipts: receipt_urls.iter().map(|url| url.to_string()).collect(),
+            inline_receipt: inline.map(String::from),
+            total: amount,
+            amounts: amounts.iter().map(|(f, a)| ((*f).clone(), *a)).collect(),
+            account: account.to_string(),
+            category: category.to_string(),
+            split_even,
+            currency,
+            branding,
+            created_at: Instant::now(),
+        };
+        return Ok(hold_duplicate(
+            interaction.id.into(),
+            &duplicate,
+            held,
+            locale,
+        ));
+    }
 
-                        fields
-                    })
-                    .footer(CreateEmbedFooter::new(format!(
-                        "\n{}",
-                        CONFIG.phrases[rand::random::<usize>() % CONFIG.phrases.len()]
-                    ))),
+    let bill = store_bill(
+        app_state,
+        NewBill {
+            purpose,
+            total: to_cents(amount),
+            account,
+            receipt_url: receipt_urls[0],
+            created_by,
+            message_id: None,
+            channel_id: interaction.channel_id.into(),
+            guild_id: interaction.guild_id.map(|g| g.0.into()),
+            category,
+            shares: shares_for(&amounts),
+        },
+        locale,
+    )
+    .await?;
+
+    let posted = match create_response(
+        Some(bill.id),
+        purpose,
+        &interaction.user.name,
+        &receipt_urls,
+        inline,
+        amount,
+        amounts,
+        account,
+        split_even,
+        currency,
+        &branding,
+        discord,
+    )
+    .await
+    {
+        Ok(response) => discord
+            .create_response(interaction, response)
+            .await
+            .map_err(|e| {
+                CommandResponse::InternalFailure(format!(
+                    "Failed to create interaction response: {}",
+                    e
+                ))
+            }),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = posted {
+        discard_bill(app_state, &bill).await;
+        return Err(e);
+    }
+
+    match discord.get_response(interaction).await {
+        Ok(message) => record_bill_message(app_state, &bill, message.id).await,
+        Err(e) => error!("unable to load the message of bill {}: {}", bill.id, e),
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// open a form asking for each participant's share of a bill, which is posted once submitted
+async fn open_amounts_form(
+    interaction: &CommandInteraction,
+    discord: &impl DiscordApi,
+    pending: PendingBill,
+) -> Result<CommandResponse, CommandResponse> {
+    if pending.participants.len() > MAX_FORM_FLATMATES {
+        return Err(CommandResponse::BasicFailure(format!(
+            "the form only fits {} flatmates, choose who is involved with participants",
+            MAX_FORM_FLATMATES
+        )));
+    }
+
+    let inputs = pending
+        .participants
+        .iter()
+        .map(|flatmate| {
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    &flatmate.display_name,
+                    flatmate.discord_id.to_string(),
+                )
+                .placeholder("0.00")
+                .max_length(12)
+                .required(false),
             )
-            .add_file(CreateAttachment::url(ctx, receipt).await.unwrap()) //XXX: handle error
-            .components({
-                let mut components = Vec::with_capacity(2);
-                components.push(CreateActionRow::Buttons({
-                    vec![
-                        CreateButton::new("paid")
-                            .style(ButtonStyle::Success)
-                            .label("Paid!"),
-                        CreateButton::new_link(receipt).label("Receipt"),
-                    ]
-                }));
-                components
+        })
+        .collect();
+    let title: String = format!("Amounts for {}", pending.purpose)
+        .chars()
+        .take(45)
+        .collect();
+    let modal = CreateModal::new(format!("{}{}", AMOUNTS_MODAL_PREFIX, interaction.id), title)
+        .components(inputs);
+
+    let key: u64 = interaction.id.into();
+    {
+        let mut pending_bills = PENDING_BILLS.lock().unwrap();
+        pending_bills.retain(|_, bill| bill.created_at.elapsed() < PENDING_BILL_TTL);
+        pending_bills.insert(key, pending);
+    }
+
+    if let Err(e) = discord
+        .create_response(interaction, CreateInteractionResponse::Modal(modal))
+        .await
+    {
+        PENDING_BILLS.lock().unwrap().remove(&key);
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to open amounts form: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// store and post a bill once its amounts have been entered in the form
+async fn handle_amounts_form(
+    submission: &ModalInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<CommandResponse, CommandResponse> {
+    let key: u64 = match submission.data.custom_id[AMOUNTS_MODAL_PREFIX.len()..].parse() {
+        Ok(key) => key,
+        Err(_) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "Invalid amounts form id {}",
+                submission.data.custom_id
+            )))
+        }
+    };
+    let locale = guild_locale(submission.guild_id, app_state).await?;
+    let pending = match PENDING_BILLS.lock().unwrap().remove(&key) {
+        Some(pending) if pending.created_at.elapsed() < PENDING_BILL_TTL => pending,
+        _ => {
+            return Err(CommandResponse::BasicFailure(
+                locale.text(Text::PayFormExpired).to_string(),
+            ))
+        }
+    };
+    let currency = pending.currency;
+    let branding = pending.branding.clone();
+
+    let mut amount = 0.0;
+    let mut amounts: Vec<(&Flatmate, f64)> = Vec::with_capacity(pending.participants.len());
+    for input in submission
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(input) => Some(input),
+            _ => None,
+        })
+    {
+        let value = input.value.trim().trim_start_matches('$').replace(',', "");
+        if value.is_empty() {
+            continue;
+        }
+
+        let flatmate = match pending
+            .participants
+            .iter()
+            .find(|f| f.discord_id.to_string() == input.custom_id)
+        {
+            Some(flatmate) => flatmate,
+            None => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "Unknown flatmate {} in amounts form",
+                    input.custom_id
+                )))
+            }
+        };
+
+        match value.parse::<f64>() {
+            Ok(value) if value.is_finite() && value >= 0.0 => {
+                amount += value;
+                amounts.push((flatmate, value));
+            }
+            _ => {
+                return Err(CommandResponse::BasicFailure(format!(
+                    "{} is not a valid amount for {}",
+                    input.value.trim(),
+                    flatmate.display_name
+                )))
+            }
+        }
+    }
+
+    if amounts.is_empty() {
+        return Err(CommandResponse::BasicFailure(
+            locale.text(Text::PayFormNoAmounts).to_string(),
+        ));
+    }
+    if let Some(total) = pending.total {
+        if let Some(discrepancy) = check_shares_match_total(amount, total, currency) {
+            return Err(CommandResponse::BasicFailure(discrepancy));
+        }
+    }
+
+    let receipt_urls: Vec<&str> = pending.receipts.iter().map(String::as_str).collect();
+
+    if pending.preview {
+        return Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(locale.text(Text::PayPreview))
+                    .embed(bill_embed(
+                        &pending.purpose,
+                        &submission.user.name,
+                        amount,
+                        amounts,
+                        &pending.account,
+                        false,
+                        currency,
+                        &branding,
+                    ))
+                    .components(bill_components(None, &receipt_urls))
+                    .ephemeral(true),
+            ),
+        ));
+    }
+
+    let created_by: u64 = submission.user.id.into();
+    if let Some(duplicate) = find_duplicate(
+        app_state,
+        submission.guild_id,
+        created_by,
+        &pending.purpose,
+        to_cents(amount),
+    )
+    .await?
+    {
+        let held = DuplicateBill {
+            purpose: pending.purpose.clone(),
+            receipts: pending.receipts.clone(),
+            inline_receipt: pending.inline_receipt.clone(),
+            total: amount,
+            amounts: amounts.iter().map(|(f, a)| ((*f).clone(), *a)).collect(),
+            account: pending.account.clone(),
+            category: pending.category.clone(),
+            split_even: false,
+            currency,
+            branding,
+            created_at: Instant::now(),
+        };
+        return Ok(hold_duplicate(
+            submission.id.into(),
+            &duplicate,
+            held,
+            locale,
+        ));
+    }
+
+    let bill = store_bill(
+        app_state,
+        NewBill {
+            purpose: &pending.purpose,
+            total: to_cents(amount),
+            account: &pending.account,
+            receipt_url: receipt_urls[0],
+            created_by,
+            message_id: None,
+            channel_id: submission.channel_id.into(),
+            guild_id: submission.guild_id.map(|g| g.0.into()),
+            category: &pending.category,
+            shares: shares_for(&amounts),
+        },
+        locale,
+    )
+    .await?;
+
+    let posted = match create_response(
+        Some(bill.id),
+        &pending.purpose,
+        &submission.user.name,
+        &receipt_urls,
+        pending.inline_receipt.as_deref(),
+        amount,
+        amounts,
+        &pending.account,
+        false,
+        currency,
+        &branding,
+        ctx,
+    )
+    .await
+    {
+        Ok(response) => submission
+            .create_response(&ctx, response)
+            .await
+            .map_err(|e| {
+                CommandResponse::InternalFailure(format!(
+                    "Failed to create interaction response: {}",
+                    e
+                ))
             }),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = posted {
+        discard_bill(app_state, &bill).await;
+        return Err(e);
+    }
+
+    match submission.get_response(&ctx).await {
+        Ok(message) => record_bill_message(app_state, &bill, message.id).await,
+        Err(e) => error!("unable to load the message of bill {}: {}", bill.id, e),
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// find a bill the same flatmate created within `DUPLICATE_WINDOW` for the same purpose and a
+/// total within 1% of this one, which is most likely the same bill being submitted twice
+async fn find_duplicate(
+    app_state: &AppState,
+    guild_id: Option<GuildId>,
+    created_by: u64,
+    purpose: &str,
+    total: i64,
+) -> Result<Option<BillModel>, CommandResponse> {
+    let since = chrono::offset::Local::now().naive_local()
+        - chrono::Duration::seconds(DUPLICATE_WINDOW.as_secs() as i64);
+
+    let bills = match app_state
+        .get_recent_bills_by_creator(guild_id.map(|g| g.0.into()), created_by, since)
+        .await
+    {
+        Ok(bills) => bills,
+        Err(e) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "error communicating with database: {}",
+                e
+            )));
+        }
+    };
+
+    Ok(bills.into_iter().find(|bill| {
+        bill.purpose.trim().eq_ignore_ascii_case(purpose.trim())
+            && (bill.total - total).abs() <= total.abs() / 100
+    }))
+}
+
+/// hold back a bill which looks like a duplicate, asking its creator whether to post it anyway.
+/// Only the creator sees the question, and nothing is posted until they answer
+fn hold_duplicate(
+    key: u64,
+    duplicate: &BillModel,
+    bill: DuplicateBill,
+    locale: Locale,
+) -> CommandResponse {
+    let content = locale.format(
+        Text::PayDuplicate,
+        &[
+            &duplicate.id.to_string(),
+            &duplicate.purpose,
+            &format_money(duplicate.total, bill.currency),
+        ],
+    );
+
+    {
+        let mut pending = PENDING_DUPLICATES.lock().unwrap();
+        pending.retain(|_, bill| bill.created_at.elapsed() < PENDING_BILL_TTL);
+        pending.insert(key, bill);
+    }
+
+    CommandResponse::ComplexSuccess(CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("{}{}", DUPLICATE_CONFIRM_PREFIX, key))
+                    .style(ButtonStyle::Primary)
+                    .label("Create anyway"),
+                CreateButton::new(format!("{}{}", DUPLICATE_ABORT_PREFIX, key))
+                    .style(ButtonStyle::Secondary)
+                    .label("Don't create"),
+            ])])
+            .ephemeral(true),
+    ))
+}
+
+/// store and post a bill its creator confirmed isn't a duplicate. The confirmation is only shown
+/// to the creator, so the bill is sent to the channel as a message of its own
+async fn post_confirmed_bill(
+    interaction: &ComponentInteraction,
+    bill: DuplicateBill,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<(), CommandResponse> {
+    let receipt_urls: Vec<&str> = bill.receipts.iter().map(String::as_str).collect();
+    let amounts: Vec<(&Flatmate, f64)> = bill.amounts.iter().map(|(f, a)| (f, *a)).collect();
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+
+    let stored = store_bill(
+        app_state,
+        NewBill {
+            purpose: &bill.purpose,
+            total: to_cents(bill.total),
+            account: &bill.account,
+            receipt_url: receipt_urls[0],
+            created_by: interaction.user.id.into(),
+            message_id: None,
+            channel_id: interaction.channel_id.into(),
+            guild_id: interaction.guild_id.map(|g| g.0.into()),
+            category: &bill.category,
+            shares: shares_for(&amounts),
+        },
+        locale,
     )
+    .await?;
+
+    let mut message = CreateMessage::new()
+        .embed(bill_embed(
+            &bill.purpose,
+            &interaction.user.name,
+            bill.total,
+            amounts,
+            &bill.account,
+            bill.split_even,
+            bill.currency,
+            &bill.branding,
+        ))
+        .components(bill_components(Some(stored.id), &receipt_urls));
+    if let Some(receipt) = bill.inline_receipt.as_deref() {
+        match ctx.download_attachment(receipt).await {
+            Ok(attachment) => message = message.add_file(attachment),
+            Err(e) => {
+                discard_bill(app_state, &stored).await;
+                return Err(CommandResponse::InternalFailure(format!(
+                    "Failed to download receipt: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    match interaction.channel_id.send_message(&ctx, message).await {
+        Ok(posted) => record_bill_message(app_state, &stored, posted.id).await,
+        Err(e) => {
+            discard_bill(app_state, &stored).await;
+            return Err(CommandResponse::InternalFailure(format!(
+                "Failed to post bill: {}",
+                e
+            )));
+        }
+    }
+
+    Ok(())
 }
 
-pub struct PayCommand {}
+/// post a bill held back as a duplicate once its creator confirms it, replacing the question
+/// with the outcome
+async fn handle_confirm_duplicate(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<CommandResponse, CommandResponse> {
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+    let key = interaction.data.custom_id[DUPLICATE_CONFIRM_PREFIX.len()..]
+        .parse::<u64>()
+        .ok();
+    let bill = match key.and_then(|key| PENDING_DUPLICATES.lock().unwrap().remove(&key)) {
+        Some(bill) if bill.created_at.elapsed() < PENDING_BILL_TTL => bill,
+        _ => {
+            return resolve_confirmation(interaction, ctx, locale.text(Text::PayDuplicateExpired))
+                .await
+        }
+    };
+
+    // posting the bill can take longer than discord waits for a response, so the button is
+    // acknowledged first and the question is replaced once the bill is posted
+    if let Err(e) = interaction
+        .create_response(&ctx, CreateInteractionResponse::Acknowledge)
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to create interaction response: {}",
+            e
+        )));
+    }
+
+    let posted = post_confirmed_bill(interaction, bill, app_state, ctx).await;
+    let outcome = match posted {
+        Ok(()) => locale.text(Text::PayDuplicateCreated),
+        Err(_) => locale.text(Text::PayUnsaved),
+    };
+    if let Err(e) = interaction
+        .edit_response(
+            &ctx,
+            EditInteractionResponse::new()
+                .content(outcome)
+                .components(Vec::with_capacity(0)),
+        )
+        .await
+    {
+        error!("unable to replace duplicate bill confirmation: {}", e);
+    }
+
+    posted.map(|_| CommandResponse::NoResponse)
+}
+
+/// convert a dollar amount into whole cents
+pub(super) fn to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+/// convert the amounts for each flatmate into a list of shares to be stored against a bill
+pub(super) fn shares_for(amounts: &[(&Flatmate, f64)]) -> Vec<(u64, i64)> {
+    amounts
+        .iter()
+        .filter(|(_, amount)| *amount != 0.0)
+        .map(|(flatmate, amount)| (flatmate.discord_id, to_cents(*amount)))
+        .collect()
+}
+
+/// the number of outstanding bills shown on each page of /pay list
+const BILLS_PER_PAGE: usize = 10;
+
+/// describe a bill which hasn't been fully paid on a single line, linking back to its message
+fn describe_outstanding(
+    bill: &BillModel,
+    shares: &[BillShareModel],
+    flatmates: &[Flatmate],
+    currency: Currency,
+) -> String {
+    let waiting_on = shares
+        .iter()
+        .filter(|share| !share.paid)
+        .map(|share| {
+            format!(
+                "{} ({})",
+                display_name_for(flatmates, share.user_id as u64),
+                format_money(share.amount, currency)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let guild = bill
+        .guild_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| String::from("@me"));
+
+    format!(
+        "[**{}**](https://discord.com/channels/{}/{}/{}) - {}, waiting on {}",
+        bill.purpose,
+        guild,
+        bill.channel_id,
+        bill.message_id,
+        format_money(bill.total, currency),
+        waiting_on
+    )
+}
+
+/// list every bill in the guild which still has an unpaid share
+async fn list_outstanding(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+) -> Result<CommandResponse, CommandResponse> {
+    let bills = match app_state
+        .get_bills_for_guild(interaction.guild_id.map(|g| g.0.into()))
+        .await
+    {
+        Ok(bills) => bills,
+        Err(e) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "error communicating with database: {}",
+                e
+            )));
+        }
+    };
+
+    let currency = guild_currency(interaction.guild_id, app_state).await?;
+    let branding = guild_branding(interaction.guild_id, app_state).await?;
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+    let flatmates = app_state.all_flatmates();
+    let lines: Vec<String> = bills
+        .iter()
+        .filter(|(_, shares)| shares.iter().any(|share| !share.paid))
+        .map(|(bill, shares)| describe_outstanding(bill, shares, &flatmates, currency))
+        .collect();
+
+    if lines.is_empty() {
+        return Ok(CommandResponse::BasicSuccess(
+            locale.text(Text::PayAllPaid).to_string(),
+        ));
+    }
+
+    let (embed, components) = paginate(
+        interaction.id.into(),
+        locale.text(Text::PayOutstanding),
+        EmbedColor::Orange as u32,
+        &branding,
+        &lines,
+        BILLS_PER_PAGE,
+    );
+
+    Ok(CommandResponse::ComplexSuccess(
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+                .components(components),
+        ),
+    ))
+}
+
+pub enum PayCommand {
+    /// create a new bill, and ask each flatmate to pay their share
+    Create,
+    /// list every bill which hasn't been fully paid
+    List,
+    /// work out the fewest payments needed to square up every outstanding bill
+    Settle,
+}
 
 impl<'a> TryFrom<&'a CommandInteraction> for PayCommand {
     type Error = String;
 
-    fn try_from(_: &'a CommandInteraction) -> Result<Self, Self::Error> {
-        Ok(Self {})
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        match interaction.data.options().first().map(|o| o.name) {
+            Some("create") => Ok(Self::Create),
+            Some("list") => Ok(Self::List),
+            Some("settle") => Ok(Self::Settle),
+            _ => Err(String::from("unknown subcommand")),
+        }
     }
 }
 
@@ -175,146 +1457,150 @@ impl<'a> Command<'a> for PayCommand {
     }
 
     fn description() -> &'static str {
-        "Create a shared bill for the flat"
+        "Create, list, and settle shared bills for the flat"
     }
 
-    fn get_application_command_options(mut cmd: CreateCommand) -> CreateCommand {
-        cmd = cmd
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::String,
-                    "purpose",
-                    "What is this bill for?",
-                )
-                .required(true)
-                .set_autocomplete(true),
+    fn get_application_command_options(cmd: CreateCommand, app_state: &AppState) -> CreateCommand {
+        let mut create = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "create",
+            "Create a shared bill for the flat",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "purpose",
+                "What is this bill for?",
+            )
+            .required(true)
+            .set_autocomplete(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Attachment,
+                "receipt",
+                "Attach a photograph or pdf of the receipt",
             )
-            .add_option(
+            .required(true),
+        );
+
+        for i in 2..=MAX_RECEIPTS {
+            create = create.add_sub_option(
                 CreateCommandOption::new(
                     CommandOptionType::Attachment,
-                    "receipt",
-                    "Attach a photograph of the receipt",
+                    format!("receipt-{}", i),
+                    "Attach a photograph of another receipt",
                 )
-                .required(true),
+                .required(false),
             );
+        }
 
-        for flatmate in CONFIG.flatmates.iter() {
-            cmd = cmd.add_option(
+        for flatmate in app_state.flatmates().iter() {
+            create = create.add_sub_option(
                 CreateCommandOption::new(
                     CommandOptionType::Number,
                     flatmate.name.to_ascii_lowercase(),
                     format!("The amount for {} to pay.", flatmate.name),
                 )
-                .required(true)
+                .required(false)
                 .set_autocomplete(true),
             );
         }
 
-        cmd.add_option(
-            CreateCommandOption::new(
-                CommandOptionType::String,
-                "account",
-                "The account number to pay into, defaults to head tenant account.",
+        create = create
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "account",
+                    "The account number to pay into, defaults to head tenant account.",
+                )
+                .required(false),
             )
-            .required(false),
-        )
-    }
-
-    async fn handle_application_command<'b>(
-        self,
-        interaction: &'b CommandInteraction,
-        _: &'b AppState,
-        ctx: &'b Context,
-    ) -> Result<CommandResponse, CommandResponse> {
-        // extract the options
-        let options = interaction.data.options();
-
-        let mut purpose: Option<&str> = None;
-        let mut receipt: Option<&Attachment> = None;
-        let mut amount = 0.0;
-        let mut amounts: Vec<(&Flatmate, f64)> = Vec::with_capacity(CONFIG.flatmates.len());
-        let mut account = CONFIG.head_tennant_acc_number.as_ref();
-
-        for option in options.iter() {
-            match option.name {
-                "purpose" => {
-                    if let ResolvedValue::String(s) = option.value {
-                        purpose = Some(s);
-                    } else {
-                        return Err(CommandResponse::InternalFailure(
-                            "Failed to parse purpose as a string".to_string(),
-                        ));
-                    }
-                }
-                "receipt" => {
-                    if let ResolvedValue::Attachment(attachment) = option.value {
-                        receipt = Some(attachment);
-                    } else {
-                        return Err(CommandResponse::InternalFailure(
-                            "Failed to parse receipt as an attachment".to_string(),
-                        ));
-                    }
-                }
-                "account" => {
-                    if let ResolvedValue::String(s) = option.value {
-                        account = s;
-                    } else {
-                        return Err(CommandResponse::InternalFailure(
-                            "Failed to parse account as a string".to_string(),
-                        ));
-                    }
-                }
-                _ => {
-                    let name = option.name;
-
-                    if let ResolvedValue::Number(value) = option.value {
-                        amount += value;
-                        amounts.push((
-                            CONFIG.flatmates.iter().find(|f| f.name == name).unwrap(),
-                            value,
-                        ));
-                    } else {
-                        return Err(CommandResponse::InternalFailure(
-                            "Failed to parse amount as a number".to_string(),
-                        ));
-                    }
-                }
-            }
-        }
-
-        // check if initialisation was successful
-        if purpose.is_none() || receipt.is_none() || amounts.is_empty() {
-            return Err(CommandResponse::InternalFailure(
-                "Failed to initialize command".to_string(),
-            ));
-        }
-
-        let purpose = purpose.unwrap();
-        let receipt = receipt.unwrap();
-
-        if let Err(e) = interaction
-            .create_response(
-                &ctx,
-                create_response(
-                    purpose,
-                    &interaction.user.name,
-                    &receipt.url,
-                    amount,
-                    amounts,
-                    account,
-                    ctx,
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Number,
+                    "total",
+                    "The total on the receipt, used to check the shares add up.",
                 )
-                .await,
+                .required(false),
             )
-            .await
-        {
-            return Err(CommandResponse::InternalFailure(format!(
-                "Failed to create interaction response: {}",
-                e
-            )));
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "split",
+                    "How to split the bill, even divides the total, form asks for each amount.",
+                )
+                .required(false)
+                .add_string_choice("Manual", "manual")
+                .add_string_choice("Even", "even")
+                .add_string_choice("Form", "form"),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "participants",
+                    "Who an even split or the form covers, e.g. @john @peter, defaults to everyone.",
+                )
+                .required(false),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "rest",
+                    "Who covers whatever the amounts leave of the total, e.g. @john, needs a total.",
+                )
+                .required(false),
+            )
+            .add_sub_option(
+                add_category_choices(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "category",
+                    "What the bill was for, used to total spending with /spending.",
+                ))
+                .required(false),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "preview",
+                    "Only show yourself the bill, without posting or saving it.",
+                )
+                .required(false),
+            );
+
+        cmd.add_option(create)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List every bill which hasn't been fully paid",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "settle",
+                "Work out the fewest payments needed to square up all outstanding bills",
+            ))
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        match self {
+            PayCommand::Create => create_bill(interaction, app_state, ctx).await,
+            PayCommand::List => list_outstanding(interaction, app_state).await,
+            PayCommand::Settle => settle_up(interaction, app_state).await,
         }
+    }
 
-        Ok(CommandResponse::NoResponse)
+    async fn autocomplete<'c>(
+        interaction: &'c CommandInteraction,
+        autocomplete: &'c AutocompleteOption,
+        app_state: &'c AppState,
+        _: &'c Context,
+    ) -> Result<CreateAutocompleteResponse, CommandResponse> {
+        handle_autocomplete_for_pay(interaction, autocomplete, app_state).await
     }
 }
 
@@ -325,10 +1611,28 @@ impl<'a> InteractionCommand<'a> for PayCommand {
         _: &'b AppState,
         _: &'b Context,
     ) -> bool {
-        //XXX: eventually it'll be good to store message id's in the database, and react to those *specifically*
-        if let Some(embed) = interaction.message.embeds.get(0) {
-            if let Some(description) = embed.description.as_ref() {
-                return description.starts_with("Bill for ");
+        let custom_id = interaction.data.custom_id.as_str();
+        if [
+            PAID_BUTTON_PREFIX,
+            SETTLE_BUTTON_PREFIX,
+            CANCEL_BUTTON_PREFIX,
+            CONFIRM_CANCEL_PREFIX,
+            ABORT_CANCEL_PREFIX,
+            DUPLICATE_CONFIRM_PREFIX,
+            DUPLICATE_ABORT_PREFIX,
+        ]
+        .iter()
+        .any(|prefix| custom_id.starts_with(prefix))
+        {
+            return true;
+        }
+
+        // bills posted before their buttons carried an id can only be recognised by their embed
+        if matches!(custom_id, "paid" | "cancel") {
+            if let Some(embed) = interaction.message.embeds.get(0) {
+                if let Some(description) = embed.description.as_ref() {
+                    return description.starts_with("Bill for ");
+                }
             }
         }
         false
@@ -336,130 +1640,656 @@ impl<'a> InteractionCommand<'a> for PayCommand {
 
     async fn interaction<'b>(
         interaction: &'b ComponentInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        match interaction.data.custom_id.as_ref() {
+            id if id.starts_with(PAID_BUTTON_PREFIX) || id == "paid" => {
+                handle_paid(interaction, app_state, ctx).await
+            }
+            id if id.starts_with(SETTLE_BUTTON_PREFIX) => {
+                handle_settle(interaction, app_state, ctx).await
+            }
+            id if id.starts_with(CANCEL_BUTTON_PREFIX) || id == "cancel" => {
+                handle_cancel(interaction, app_state, ctx).await
+            }
+            id if id.starts_with(CONFIRM_CANCEL_PREFIX) => {
+                handle_confirm_cancel(interaction, app_state, ctx).await
+            }
+            id if id.starts_with(ABORT_CANCEL_PREFIX) => {
+                let locale = guild_locale(interaction.guild_id, app_state).await?;
+                resolve_confirmation(interaction, ctx, locale.text(Text::PayKept)).await
+            }
+            id if id.starts_with(DUPLICATE_CONFIRM_PREFIX) => {
+                handle_confirm_duplicate(interaction, app_state, ctx).await
+            }
+            id if id.starts_with(DUPLICATE_ABORT_PREFIX) => {
+                if let Ok(key) = id[DUPLICATE_ABORT_PREFIX.len()..].parse::<u64>() {
+                    PENDING_DUPLICATES.lock().unwrap().remove(&key);
+                }
+                let locale = guild_locale(interaction.guild_id, app_state).await?;
+                resolve_confirmation(interaction, ctx, locale.text(Text::PayDuplicateDropped)).await
+            }
+            _ => Err(CommandResponse::InternalFailure(
+                "Invalid interaction".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> ModalCommand<'a> for PayCommand {
+    async fn answerable_modal<'b>(
+        submission: &'b ModalInteraction,
         _: &'b AppState,
+        _: &'b Context,
+    ) -> bool {
+        let id = &submission.data.custom_id;
+        id.starts_with(PAID_NOTE_MODAL_PREFIX) || id.starts_with(AMOUNTS_MODAL_PREFIX)
+    }
+
+    async fn modal<'b>(
+        submission: &'b ModalInteraction,
+        app_state: &'b AppState,
         ctx: &'b Context,
     ) -> Result<CommandResponse, CommandResponse> {
-        if interaction.member.is_none() {
-            return Err(CommandResponse::InternalFailure(
-                "Failed to get member".to_string(),
-            ));
+        if submission.data.custom_id.starts_with(AMOUNTS_MODAL_PREFIX) {
+            handle_amounts_form(submission, app_state, ctx).await
+        } else {
+            handle_paid_note(submission, app_state, ctx).await
         }
+    }
+}
 
-        let user: u64 = interaction.user.id.into();
-        let user: Option<&Flatmate> = CONFIG
-            .flatmates
-            .iter()
-            .find(|flatmate| flatmate.discord_id == user);
+/// the prefix of the custom id of a bill's paid button, followed by the id of the bill
+const PAID_BUTTON_PREFIX: &str = "bill-paid-";
+
+/// the prefix of the custom id of the button the creator of a bill uses to mark every share as
+/// paid, followed by the id of the bill
+const SETTLE_BUTTON_PREFIX: &str = "bill-settle-";
+
+/// the prefix of the custom id of a bill's cancel button, followed by the id of the bill
+const CANCEL_BUTTON_PREFIX: &str = "bill-cancel-";
+
+/// the prefix of the custom id of the button confirming a bill should be cancelled, followed by
+/// the id of the bill
+const CONFIRM_CANCEL_PREFIX: &str = "bill-confirm-cancel-";
+
+/// the prefix of the custom id of the button keeping a bill rather than cancelling it, followed
+/// by the id of the bill
+const ABORT_CANCEL_PREFIX: &str = "bill-abort-cancel-";
+
+/// the prefix of the custom id of the modal used to add a note when marking a share as paid,
+/// followed by the id of the bill
+const PAID_NOTE_MODAL_PREFIX: &str = "bill-note-";
 
-        if user.is_none() {
+/// the prefix of the custom id of the form used to enter the amounts of a bill, followed by the
+/// id of the interaction which opened it
+const AMOUNTS_MODAL_PREFIX: &str = "bill-amounts-";
+
+/// the prefix of the custom id of the button posting a bill which looks like a duplicate anyway,
+/// followed by the id of the interaction which created it
+const DUPLICATE_CONFIRM_PREFIX: &str = "bill-duplicate-confirm-";
+
+/// the prefix of the custom id of the button dropping a bill which looks like a duplicate,
+/// followed by the id of the interaction which created it
+const DUPLICATE_ABORT_PREFIX: &str = "bill-duplicate-abort-";
+
+/// the note left on every share marked as paid by the creator of a bill
+const SETTLED_NOTE: &str = "marked as paid by the creator";
+
+/// load a bill and its shares by id, so that its buttons keep working however old the message is
+async fn load_bill(
+    id: &str,
+    app_state: &AppState,
+) -> Result<(BillModel, Vec<BillShareModel>), CommandResponse> {
+    let id: i32 = match id.parse() {
+        Ok(id) => id,
+        Err(_) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "Invalid bill id {}",
+                id
+            )))
+        }
+    };
+
+    match app_state.get_bill(id).await {
+        Ok(Some(bill)) => Ok(bill),
+        Ok(None) => Err(CommandResponse::BasicFailure(String::from(
+            "this bill no longer exists",
+        ))),
+        Err(e) => Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        ))),
+    }
+}
+
+/// load the bill a button belongs to, bills posted before their buttons carried an id are found by
+/// their message instead
+async fn bill_for_button(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+) -> Result<(BillModel, Vec<BillShareModel>), CommandResponse> {
+    let custom_id = interaction.data.custom_id.as_str();
+    if let Some(id) = custom_id
+        .strip_prefix(PAID_BUTTON_PREFIX)
+        .or_else(|| custom_id.strip_prefix(SETTLE_BUTTON_PREFIX))
+        .or_else(|| custom_id.strip_prefix(CANCEL_BUTTON_PREFIX))
+    {
+        return load_bill(id, app_state).await;
+    }
+
+    match app_state
+        .get_bill_by_message_id(interaction.message.id.into())
+        .await
+    {
+        Ok(Some(bill)) => Ok(bill),
+        Ok(None) => Err(CommandResponse::InternalFailure(
+            "Failed to find bill for message".to_string(),
+        )),
+        Err(e) => Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        ))),
+    }
+}
+
+/// find the flatmate who interacted with a bill, along with their share of it and every share
+/// of the bill. The inner error is the message to show the user when they have nothing to pay
+fn find_unpaid_share(
+    user: UserId,
+    shares: Vec<BillShareModel>,
+    app_state: &AppState,
+) -> Result<Result<(Flatmate, BillShareModel, Vec<BillShareModel>), Text>, CommandResponse> {
+    let user: u64 = user.into();
+    let user = match app_state
+        .all_flatmates()
+        .iter()
+        .find(|flatmate| flatmate.discord_id == user)
+    {
+        Some(user) => user.clone(),
+        None => {
             return Err(CommandResponse::InternalFailure(
                 "Failed to get user".to_string(),
-            ));
+            ))
         }
-        let user = user.unwrap();
-        let mut message = interaction.message.clone();
-        let current_time = chrono::offset::Local::now().format("%d/%m/%y at %I:%M%P");
-        let mut all_set = 0;
+    };
 
-        if message.embeds.len() != 1 {
+    // check the state of this users share, so that it can't be paid twice
+    let share = match shares
+        .iter()
+        .find(|share| share.user_id == user.discord_id as i64)
+    {
+        Some(share) => share.clone(),
+        None => return Ok(Err(Text::PayNotOwed)),
+    };
+
+    if share.paid {
+        return Ok(Err(Text::PayAlreadyMarked));
+    }
+
+    Ok(Ok((user, share, shares)))
+}
+
+/// ask the user who clicked the paid button for an optional note, their share is marked as paid
+/// once the note is submitted
+async fn handle_paid(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+    discord: &impl DiscordApi,
+) -> Result<CommandResponse, CommandResponse> {
+    if interaction.member.is_none() {
+        return Err(CommandResponse::InternalFailure(
+            "Failed to get member".to_string(),
+        ));
+    }
+
+    let (bill, shares) = bill_for_button(interaction, app_state).await?;
+    if let Err(message) = find_unpaid_share(interaction.user.id, shares, app_state)? {
+        let locale = guild_locale(interaction.guild_id, app_state).await?;
+        return respond_ephemeral(interaction, discord, locale.text(message)).await;
+    }
+
+    let modal_id = format!("{}{}", PAID_NOTE_MODAL_PREFIX, bill.id);
+    let modal =
+        CreateModal::new(modal_id, "Mark as paid").components(vec![CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Note (optional)", "note")
+                .placeholder("e.g. paid $5 extra, cover me next time")
+                .max_length(200)
+                .required(false),
+        )]);
+
+    if let Err(e) = discord
+        .respond_to_component(interaction, CreateInteractionResponse::Modal(modal))
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to open modal: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// mark the share of the user who submitted the note as paid, adding the note to the bill
+async fn handle_paid_note(
+    submission: &ModalInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<CommandResponse, CommandResponse> {
+    let mut message = match submission.message.as_ref() {
+        Some(message) => message.as_ref().clone(),
+        None => {
             return Err(CommandResponse::InternalFailure(
-                "Invalid embeds in message".to_string(),
-            ));
+                "Modal was not opened from a bill".to_string(),
+            ))
         }
+    };
 
-        let mut edit_message = EditMessage::new().embed(
-            CreateEmbed::new()
-                .description(
-                    interaction.message.embeds[0]
-                        .description
-                        .as_ref()
-                        .unwrap_or(&String::from("")),
-                )
-                .footer(CreateEmbedFooter::new({
-                    interaction.message.embeds[0]
-                        .footer
-                        .as_ref()
-                        .expect("footer to be present")
-                        .text
-                        .clone()
-                }))
-                .fields({
-                    let mut fields: Vec<(String, String, bool)> =
-                        Vec::with_capacity(message.embeds[0].fields.len());
-                    for field in message.embeds[0].fields.iter() {
-                        if field.name.contains("paid") {
-                            all_set += 1;
-                        }
-                        if field.name.to_lowercase().contains(&user.name)
-                            && field.name.contains("pay")
-                        {
-                            fields.push((
-                                format!(
-                                    "{}{} paid {} on:",
-                                    user.name[0..1].to_uppercase(),
-                                    &user.name[1..],
-                                    field.value
-                                ),
-                                current_time.to_string(),
-                                field.inline,
-                            ));
-                            all_set += 1;
-                        } else {
-                            fields.push((field.name.clone(), field.value.clone(), field.inline));
-                        }
-                    }
-                    fields
-                })
-                .color({
-                    if all_set == message.embeds[0].fields.len() {
-                        EmbedColor::Green as u32
-                    } else {
-                        EmbedColor::Red as u32
-                    }
-                }),
-        );
-
-        if all_set == message.embeds[0].fields.len() {
-            edit_message = edit_message.components(Vec::with_capacity(0));
+    // the share is checked again, as it may have been paid while the modal was open
+    let bill_id = &submission.data.custom_id[PAID_NOTE_MODAL_PREFIX.len()..];
+    let (_, shares) = load_bill(bill_id, app_state).await?;
+    let locale = guild_locale(submission.guild_id, app_state).await?;
+    let (user, share, _) = match find_unpaid_share(submission.user.id, shares, app_state)? {
+        Ok(found) => found,
+        Err(message) => {
+            return Ok(CommandResponse::BasicSuccess(
+                locale.text(message).to_string(),
+            ))
         }
+    };
+
+    let note = submission
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == "note" => {
+                Some(input.value.trim().to_string())
+            }
+            _ => None,
+        })
+        .filter(|note| !note.is_empty());
 
-        if let Err(e) = message.edit(&ctx, edit_message).await {
+    match app_state
+        .set_bill_share_paid(share.id, true, note.as_deref())
+        .await
+    {
+        Ok(0) => {
+            // another submission marked the share first
+            return Ok(CommandResponse::BasicSuccess(
+                locale.text(Text::PayAlreadyMarked).to_string(),
+            ));
+        }
+        Ok(_) => {}
+        Err(e) => {
             return Err(CommandResponse::InternalFailure(format!(
-                "Failed to edit message: {}",
+                "error communicating with database: {}",
                 e
             )));
         }
+    }
 
-        interaction
-            .create_response(
-                &ctx,
-                CreateInteractionResponse::Message(
-                    CreateInteractionResponseMessage::new()
-                        .content(format!(
-                            "{}{} paid!",
-                            user.name[0..1].to_uppercase(),
-                            &user.name[1..]
-                        ))
-                        .ephemeral(true),
-                ),
+    // the shares are loaded again, as others may have been paid since they were checked
+    let (_, shares) = load_bill(bill_id, app_state).await?;
+    let paid_count = shares.iter().filter(|s| s.paid).count();
+    let all_paid = paid_count == shares.len();
+
+    let current_time = chrono::offset::Local::now().format("%d/%m/%y at %I:%M%P");
+    let paid_value = match note.as_ref() {
+        Some(note) => format!("{}\n> {}", current_time, note),
+        None => current_time.to_string(),
+    };
+
+    if message.embeds.len() != 1 {
+        return Err(CommandResponse::InternalFailure(
+            "Invalid embeds in message".to_string(),
+        ));
+    }
+
+    let branding = guild_branding(submission.guild_id, app_state).await?;
+    let mut edit_message = EditMessage::new().embed(
+        branding
+            .embed()
+            .description(
+                message.embeds[0]
+                    .description
+                    .as_ref()
+                    .unwrap_or(&String::from("")),
             )
-            .await
-            .unwrap();
+            .footer(CreateEmbedFooter::new({
+                message.embeds[0]
+                    .footer
+                    .as_ref()
+                    .expect("footer to be present")
+                    .text
+                    .clone()
+            }))
+            .fields({
+                // only this user's field is replaced, matched exactly as `bill_embed` names it
+                let owing = format!("Amount for {} to pay:", user.display_name);
+                let mut fields: Vec<(String, String, bool)> =
+                    Vec::with_capacity(message.embeds[0].fields.len());
+                for field in message.embeds[0].fields.iter() {
+                    if field.name == owing {
+                        fields.push((
+                            format!("{} paid {} on:", user.display_name, field.value),
+                            paid_value.clone(),
+                            field.inline,
+                        ));
+                    } else {
+                        fields.push((field.name.clone(), field.value.clone(), field.inline));
+                    }
+                }
+                fields
+            })
+            .color(progress_color(paid_count, shares.len()) as u32),
+    );
 
-        Ok(CommandResponse::NoResponse)
+    if all_paid {
+        edit_message = edit_message.components(Vec::with_capacity(0));
     }
+
+    if let Err(e) = message.edit(&ctx, edit_message).await {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to edit message: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::BasicSuccess(
+        locale.format(Text::PayMarkedPaid, &[&user.display_name]),
+    ))
 }
 
-#[async_trait]
-impl<'a> AutocompleteCommand<'a> for PayCommand {
-    async fn autocomplete<'c>(
-        interaction: &'c CommandInteraction,
-        autocomplete: &'c AutocompleteOption,
-        _: &'c AppState,
-        _: &'c Context,
-    ) -> Result<CreateAutocompleteResponse, CommandResponse> {
-        handle_autocomplete_for_pay(interaction, autocomplete).await
+/// mark every unpaid share of a bill as paid, for when the creator has been paid outside of the
+/// bot, e.g. in cash. Only the creator of the bill can settle it
+async fn handle_settle(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<CommandResponse, CommandResponse> {
+    let (bill, shares) = bill_for_button(interaction, app_state).await?;
+
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+
+    let user: u64 = interaction.user.id.into();
+    if bill.created_by != user as i64 {
+        return respond_ephemeral(interaction, ctx, locale.text(Text::PayOnlyCreatorSettles)).await;
+    }
+
+    if shares.iter().all(|share| share.paid) {
+        return respond_ephemeral(interaction, ctx, locale.text(Text::PayAlreadySettled)).await;
+    }
+
+    if let Err(e) = app_state.settle_bill(bill.id, SETTLED_NOTE).await {
+        return Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        )));
+    }
+
+    let embed = match interaction.message.embeds.first() {
+        Some(embed) => embed,
+        None => {
+            return Err(CommandResponse::InternalFailure(
+                "Invalid embeds in message".to_string(),
+            ))
+        }
+    };
+
+    // every share which was still owing is shown as paid by the creator
+    let paid_value = format!(
+        "{}\n> {}",
+        chrono::offset::Local::now().format("%d/%m/%y at %I:%M%P"),
+        SETTLED_NOTE
+    );
+    let fields: Vec<(String, String, bool)> = embed
+        .fields
+        .iter()
+        .map(|field| {
+            match field
+                .name
+                .strip_prefix("Amount for ")
+                .and_then(|name| name.strip_suffix(" to pay:"))
+            {
+                Some(name) => (
+                    format!("{} paid {} on:", name, field.value),
+                    paid_value.clone(),
+                    field.inline,
+                ),
+                None => (field.name.clone(), field.value.clone(), field.inline),
+            }
+        })
+        .collect();
+
+    let mut settled = guild_branding(interaction.guild_id, app_state)
+        .await?
+        .embed()
+        .description(embed.description.clone().unwrap_or_default())
+        .fields(fields)
+        .color(EmbedColor::Green as u32);
+    if let Some(title) = embed.title.as_ref() {
+        settled = settled.title(title);
+    }
+    if let Some(footer) = embed.footer.as_ref() {
+        settled = settled.footer(CreateEmbedFooter::new(&footer.text));
+    }
+
+    if let Err(e) = interaction
+        .create_response(
+            &ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(settled)
+                    .components(Vec::with_capacity(0)),
+            ),
+        )
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to edit message: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// pick a colour for a bill based on how many of its shares have been paid, red when nobody has
+/// paid, amber when some have, and green once everybody has
+fn progress_color(paid: usize, total: usize) -> EmbedColor {
+    if paid >= total {
+        EmbedColor::Green
+    } else if paid > 0 {
+        EmbedColor::Orange
+    } else {
+        EmbedColor::Red
+    }
+}
+
+/// reply to a button press with a message only the user who pressed it can see
+async fn respond_ephemeral(
+    interaction: &ComponentInteraction,
+    discord: &impl DiscordApi,
+    content: &str,
+) -> Result<CommandResponse, CommandResponse> {
+    if let Err(e) = discord
+        .respond_to_component(
+            interaction,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to create interaction response: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// ask the flatmate who created a bill to confirm they want to cancel it, only they can see the
+/// confirmation and the bill is left alone until they confirm
+async fn handle_cancel(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> Result<CommandResponse, CommandResponse> {
+    let (bill, shares) = bill_for_button(interaction, app_state).await?;
+
+    let user: u64 = interaction.user.id.into();
+    if bill.created_by != user as i64 {
+        let locale = guild_locale(interaction.guild_id, app_state).await?;
+        return respond_ephemeral(interaction, ctx, locale.text(Text::PayOnlyCreatorCancels)).await;
+    }
+
+    let paid = shares.iter().filter(|share| share.paid).count();
+    if let Err(e) = interaction
+        .create_response(
+            &ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "Are you sure you want to cancel the bill for **{}**? {} of {} shares have \
+                         already been paid.",
+                        bill.purpose,
+                        paid,
+                        shares.len()
+                    ))
+                    .components(vec![CreateActionRow::Buttons(vec![
+                        CreateButton::new(format!("{}{}", CONFIRM_CANCEL_PREFIX, bill.id))
+                            .style(ButtonStyle::Danger)
+                            .label("Cancel bill"),
+                        CreateButton::new(format!("{}{}", ABORT_CANCEL_PREFIX, bill.id))
+                            .style(ButtonStyle::Secondary)
+                            .label("Keep bill"),
+                    ])])
+                    .ephemeral(true),
+            ),
+        )
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to create interaction response: {}",
+            e
+        )));
+    }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// replace the confirmation message of a cancellation with the outcome, removing its buttons
+async fn resolve_confirmation(
+    interaction: &ComponentInteraction,
+    discord: &impl DiscordApi,
+    content: &str,
+) -> Result<CommandResponse, CommandResponse> {
+    if let Err(e) = discord
+        .respond_to_component(
+            interaction,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::with_capacity(0)),
+            ),
+        )
+        .await
+    {
+        return Err(CommandResponse::InternalFailure(format!(
+            "Failed to create interaction response: {}",
+            e
+        )));
     }
+
+    Ok(CommandResponse::NoResponse)
+}
+
+/// strike out the message of a cancelled bill, removing its buttons
+async fn strike_out_bill(
+    bill: &BillModel,
+    branding: &Branding,
+    discord: &impl DiscordApi,
+) -> serenity::Result<()> {
+    let channel = ChannelId::new(bill.channel_id as u64);
+    let message_id = MessageId::new(bill.message_id as u64);
+
+    // the confirmation is a separate message, so the bill itself is loaded to be struck out
+    let message = discord.get_message(channel, message_id).await?;
+    let description = message
+        .embeds
+        .first()
+        .and_then(|embed| embed.description.clone())
+        .unwrap_or_default();
+
+    discord
+        .edit_message(
+            channel,
+            message_id,
+            EditMessage::new()
+                .embed(
+                    branding
+                        .embed()
+                        .title("Cancelled")
+                        .description(format!("~~{}~~", description))
+                        .color(EmbedColor::Orange as u32),
+                )
+                .components(Vec::with_capacity(0)),
+        )
+        .await
+}
+
+/// cancel a bill once its creator has confirmed it, deleting it and striking out its message
+async fn handle_confirm_cancel(
+    interaction: &ComponentInteraction,
+    app_state: &AppState,
+    discord: &impl DiscordApi,
+) -> Result<CommandResponse, CommandResponse> {
+    let id = &interaction.data.custom_id[CONFIRM_CANCEL_PREFIX.len()..];
+    let (bill, _) = match load_bill(id, app_state).await {
+        Ok(bill) => bill,
+        Err(CommandResponse::BasicFailure(message)) => {
+            return resolve_confirmation(interaction, discord, &message).await;
+        }
+        Err(e) => return Err(e),
+    };
+
+    let locale = guild_locale(interaction.guild_id, app_state).await?;
+    let user: u64 = interaction.user.id.into();
+    if bill.created_by != user as i64 {
+        return resolve_confirmation(
+            interaction,
+            discord,
+            locale.text(Text::PayOnlyCreatorCancels),
+        )
+        .await;
+    }
+
+    let branding = guild_branding(interaction.guild_id, app_state).await?;
+    if let Err(e) = app_state.delete_bill(bill.id).await {
+        return Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        )));
+    }
+
+    // the bill is already gone, so failing to strike out its message doesn't undo the cancel.
+    // A bill whose message was never recorded has nothing to strike out
+    if bill.message_id == 0 {
+        error!("cancelled bill {} has no recorded message", bill.id);
+    } else if let Err(e) = strike_out_bill(&bill, &branding, discord).await {
+        error!("unable to strike out cancelled bill {}: {}", bill.id, e);
+    }
+
+    resolve_confirmation(interaction, discord, locale.text(Text::PayCancelled)).await
 }
 
 /// A variation of the PayCommand which takes a single argument, and pays that amount to all flatmates
@@ -483,7 +2313,7 @@ impl<'a> Command<'a> for PayAllCommand {
         "Evenly split a bill between all flatmates"
     }
 
-    fn get_application_command_options(cmd: CreateCommand) -> CreateCommand {
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
         cmd.add_option(
             CreateCommandOption::new(
                 CommandOptionType::String,
@@ -518,7 +2348,7 @@ impl<'a> Command<'a> for PayAllCommand {
     async fn handle_application_command<'b>(
         self,
         interaction: &'b CommandInteraction,
-        _: &'b AppState,
+        app_state: &'b AppState,
         ctx: &'b Context,
     ) -> Result<CommandResponse, CommandResponse> {
         // extract the options
@@ -584,48 +2414,409 @@ impl<'a> Command<'a> for PayAllCommand {
         let purpose = purpose.unwrap();
         let amount = amount.unwrap();
         let receipt = receipt.unwrap();
+        let inline = inline_receipt(&[receipt])?;
 
         // parse response and create message
-        let mut amounts: Vec<(&Flatmate, f64)> = Vec::with_capacity(CONFIG.flatmates.len());
-        let individual = amount / CONFIG.flatmates.len() as f64;
-        for flatmate in CONFIG.flatmates.iter() {
-            amounts.push((flatmate, individual));
-        }
+        let flatmates = app_state.flatmates();
+        let amounts = split_evenly(amount, &flatmates);
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        let locale = guild_locale(interaction.guild_id, app_state).await?;
 
-        if let Err(e) = interaction
-            .create_response(
-                &ctx,
-                create_response(
-                    purpose,
-                    &interaction.user.name,
-                    &receipt.url,
-                    amount,
-                    amounts,
-                    account,
-                    ctx,
-                )
-                .await,
-            )
-            .await
+        let bill = store_bill(
+            app_state,
+            NewBill {
+                purpose,
+                total: to_cents(amount),
+                account,
+                receipt_url: &receipt.url,
+                created_by: interaction.user.id.into(),
+                message_id: None,
+                channel_id: interaction.channel_id.into(),
+                guild_id: interaction.guild_id.map(|g| g.0.into()),
+                category: UNCATEGORIZED,
+                shares: shares_for(&amounts),
+            },
+            locale,
+        )
+        .await?;
+
+        let posted = match create_response(
+            Some(bill.id),
+            purpose,
+            &interaction.user.name,
+            &[receipt.url.as_str()],
+            inline,
+            amount,
+            amounts,
+            account,
+            true,
+            currency,
+            &branding,
+            ctx,
+        )
+        .await
         {
-            return Err(CommandResponse::InternalFailure(format!(
-                "Failed to create interaction response: {}",
-                e
-            )));
+            Ok(response) => ctx
+                .create_response(interaction, response)
+                .await
+                .map_err(|e| {
+                    CommandResponse::InternalFailure(format!(
+                        "Failed to create interaction response: {}",
+                        e
+                    ))
+                }),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = posted {
+            discard_bill(app_state, &bill).await;
+            return Err(e);
+        }
+
+        match ctx.get_response(interaction).await {
+            Ok(message) => record_bill_message(app_state, &bill, message.id).await,
+            Err(e) => error!("unable to load the message of bill {}: {}", bill.id, e),
         }
 
         Ok(CommandResponse::NoResponse)
     }
-}
 
-#[async_trait]
-impl<'a> AutocompleteCommand<'a> for PayAllCommand {
     async fn autocomplete<'c>(
         interaction: &'c CommandInteraction,
         autocomplete: &'c AutocompleteOption,
-        _: &'c AppState,
+        app_state: &'c AppState,
         _: &'c Context,
     ) -> Result<CreateAutocompleteResponse, CommandResponse> {
-        handle_autocomplete_for_pay(interaction, autocomplete).await
+        handle_autocomplete_for_pay(interaction, autocomplete, app_state).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::EntityTrait;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::discord_bot::commands::discord::mock::MockDiscord;
+
+    /// a flatmate from the example config, who sends every interaction
+    fn user() -> Value {
+        json!({
+            "id": "12038931",
+            "username": "john",
+            "discriminator": "0001",
+            "avatar": null,
+            "bot": false,
+            "public_flags": 0,
+        })
+    }
+
+    /// a /pay create interaction sent in a direct message, with a receipt and the given options
+    fn pay_create(options: Value) -> CommandInteraction {
+        let mut options = options.as_array().cloned().unwrap();
+        options.push(json!({ "name": "receipt", "type": 11, "value": "500" }));
+
+        serde_json::from_value(json!({
+            "id": "100",
+            "application_id": "200",
+            "type": 2,
+            "data": {
+                "id": "300",
+                "name": "pay",
+                "type": 1,
+                "options": [{ "name": "create", "type": 1, "options": options }],
+                "resolved": {
+                    "attachments": {
+                        "500": {
+                            "id": "500",
+                            "filename": "receipt.png",
+                            "size": 1024,
+                            "url": "https://cdn.discordapp.com/attachments/400/500/receipt.png",
+                            "proxy_url": "https://media.discordapp.net/attachments/400/500/receipt.png",
+                            "content_type": "image/png",
+                        },
+                    },
+                },
+            },
+            "channel_id": "400",
+            "user": user(),
+            "token": "token",
+            "version": 1,
+            "locale": "en-US",
+        }))
+        .unwrap()
+    }
+
+    async fn stored_bills(app_state: &AppState) -> Vec<BillModel> {
+        entity::bill::Entity::find()
+            .all(&*app_state.database.connection())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preview_is_only_shown_to_the_creator() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let interaction = pay_create(json!([
+            { "name": "purpose", "type": 3, "value": "Groceries" },
+            { "name": "split", "type": 3, "value": "even" },
+            { "name": "total", "type": 10, "value": 30.0 },
+            { "name": "preview", "type": 5, "value": true },
+        ]));
+
+        let response = create_bill(&interaction, &app_state, &discord)
+            .await
+            .unwrap();
+        let response = match response {
+            CommandResponse::ComplexSuccess(response) => serde_json::to_value(response).unwrap(),
+            other => panic!("expected a preview, got {:?}", other),
+        };
+        assert_eq!(
+            response["data"]["content"],
+            Locale::default().text(Text::PayPreview)
+        );
+
+        // a preview is neither posted nor stored
+        assert!(discord.responses.lock().unwrap().is_empty());
+        assert!(stored_bills(&app_state).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_bills_are_refused() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let purpose = json!({ "name": "purpose", "type": 3, "value": "Groceries" });
+
+        let cases = [
+            (
+                json!([purpose]),
+                Locale::default().text(Text::PayNoAmounts).to_string(),
+            ),
+            (
+                json!([
+                    purpose,
+                    { "name": "john", "type": 10, "value": 10.0 },
+                    { "name": "participants", "type": 3, "value": "john" },
+                ]),
+                Locale::default()
+                    .text(Text::PayParticipantsNeedSplit)
+                    .to_string(),
+            ),
+            (
+                json!([purpose, { "name": "steve", "type": 10, "value": 10.0 }]),
+                String::from("steve is not a current flatmate"),
+            ),
+        ];
+        for (options, expected) in cases {
+            match create_bill(&pay_create(options), &app_state, &discord).await {
+                Err(CommandResponse::BasicFailure(message)) => assert_eq!(message, expected),
+                other => panic!("expected {:?}, got {:?}", expected, other),
+            }
+        }
+
+        // the shares have to add up to the total when one is given
+        let interaction = pay_create(json!([
+            purpose,
+            { "name": "john", "type": 10, "value": 10.0 },
+            { "name": "total", "type": 10, "value": 30.0 },
+        ]));
+        assert!(matches!(
+            create_bill(&interaction, &app_state, &discord).await,
+            Err(CommandResponse::BasicFailure(_))
+        ));
+
+        assert!(discord.responses.lock().unwrap().is_empty());
+        assert!(stored_bills(&app_state).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn bills_are_stored_before_being_posted() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let interaction = pay_create(json!([
+            { "name": "purpose", "type": 3, "value": "Groceries" },
+            { "name": "john", "type": 10, "value": 10.0 },
+            { "name": "peter", "type": 10, "value": 5.0 },
+        ]));
+
+        assert!(matches!(
+            create_bill(&interaction, &app_state, &discord).await,
+            Ok(CommandResponse::NoResponse)
+        ));
+
+        let bills = stored_bills(&app_state).await;
+        assert_eq!(bills.len(), 1);
+        assert_eq!(bills[0].total, 1500);
+        // the mock never sends a message, so there is none to record
+        assert_eq!(bills[0].message_id, 0);
+
+        // the posted buttons carry the id of the stored bill, and the receipt is shown inline
+        let responses = discord.responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        let posted = serde_json::to_string(&responses[0]).unwrap();
+        assert!(posted.contains(&format!("{}{}", PAID_BUTTON_PREFIX, bills[0].id)));
+        assert_eq!(
+            *discord.downloads.lock().unwrap(),
+            vec!["https://cdn.discordapp.com/attachments/400/500/receipt.png"]
+        );
+    }
+
+    /// a press of the paid button of a bill, on a message the bot has no record of, as happens
+    /// after a restart or for a message which couldn't be recorded
+    fn paid_button(bill_id: i32) -> ComponentInteraction {
+        button(&format!("{}{}", PAID_BUTTON_PREFIX, bill_id))
+    }
+
+    /// a press of a button by john, on a message the bot has no record of
+    fn button(custom_id: &str) -> ComponentInteraction {
+        serde_json::from_value(json!({
+            "id": "101",
+            "application_id": "200",
+            "type": 3,
+            "data": {
+                "custom_id": custom_id,
+                "component_type": 2,
+                "values": [],
+            },
+            "guild_id": "600",
+            "channel_id": "400",
+            "member": {
+                "user": user(),
+                "roles": [],
+                "joined_at": "2023-01-01T00:00:00.000000+00:00",
+                "deaf": false,
+                "mute": false,
+                "flags": 0,
+                "pending": false,
+                "permissions": "0",
+                "guild_id": "600",
+            },
+            "user": user(),
+            "token": "token",
+            "version": 1,
+            "message": {
+                "id": "999",
+                "channel_id": "400",
+                "author": user(),
+                "content": "",
+                "timestamp": "2023-01-01T00:00:00.000000+00:00",
+                "edited_timestamp": null,
+                "tts": false,
+                "mention_everyone": false,
+                "mentions": [],
+                "mention_roles": [],
+                "attachments": [],
+                "embeds": [],
+                "pinned": false,
+                "type": 0,
+                "components": [],
+            },
+            "locale": "en-US",
+        }))
+        .unwrap()
+    }
+
+    /// store a bill from john, owed by john and peter, as if it were posted before a restart
+    async fn seed_bill(app_state: &AppState) -> BillModel {
+        app_state
+            .create_bill(NewBill {
+                purpose: "Power",
+                total: 3000,
+                account: "0000-0000-0000000-00",
+                receipt_url: "https://cdn.discordapp.com/attachments/400/500/receipt.png",
+                created_by: 12038931,
+                message_id: Some(998),
+                channel_id: 400,
+                guild_id: Some(600),
+                category: UNCATEGORIZED,
+                shares: vec![(12038931, 1500), (120398091, 1500)],
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn paid_button_works_without_a_cached_message() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let bill = seed_bill(&app_state).await;
+
+        assert!(matches!(
+            handle_paid(&paid_button(bill.id), &app_state, &discord).await,
+            Ok(CommandResponse::NoResponse)
+        ));
+
+        // the bill is found from the button alone, and the note form is opened for it
+        let responses = discord.responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        let response = serde_json::to_value(&responses[0]).unwrap();
+        assert_eq!(
+            response["data"]["custom_id"],
+            format!("{}{}", PAID_NOTE_MODAL_PREFIX, bill.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn paid_button_refuses_a_share_already_paid() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let bill = seed_bill(&app_state).await;
+        let (_, shares) = app_state.get_bill(bill.id).await.unwrap().unwrap();
+        let share = shares.iter().find(|s| s.user_id == 12038931).unwrap();
+        app_state
+            .set_bill_share_paid(share.id, true, None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            handle_paid(&paid_button(bill.id), &app_state, &discord).await,
+            Ok(CommandResponse::NoResponse)
+        ));
+
+        let responses = discord.responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        let response = serde_json::to_value(&responses[0]).unwrap();
+        assert_eq!(
+            response["data"]["content"],
+            Locale::default().text(Text::PayAlreadyMarked)
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_succeeds_when_the_bill_message_cant_be_loaded() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let bill = seed_bill(&app_state).await;
+
+        // the mock can't load the bill's message, as if it was deleted
+        let confirm = button(&format!("{}{}", CONFIRM_CANCEL_PREFIX, bill.id));
+        assert!(matches!(
+            handle_confirm_cancel(&confirm, &app_state, &discord).await,
+            Ok(CommandResponse::NoResponse)
+        ));
+
+        assert!(app_state.get_bill(bill.id).await.unwrap().is_none());
+        let responses = discord.responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        let response = serde_json::to_value(&responses[0]).unwrap();
+        assert_eq!(
+            response["data"]["content"],
+            Locale::default().text(Text::PayCancelled)
+        );
+    }
+
+    #[tokio::test]
+    async fn paid_button_of_a_deleted_bill_is_refused() {
+        let app_state = AppState::for_tests().await;
+        let discord = MockDiscord::default();
+        let bill = seed_bill(&app_state).await;
+        app_state.delete_bill(bill.id).await.unwrap();
+
+        assert!(matches!(
+            handle_paid(&paid_button(bill.id), &app_state, &discord).await,
+            Err(CommandResponse::BasicFailure(_))
+        ));
+        assert!(discord.responses.lock().unwrap().is_empty());
     }
 }
@@ -1,13 +1,34 @@
+use std::time::{Duration, Instant};
+
 use serenity::{
-    all::CommandInteraction,
+    all::{CommandInteraction, ShardId},
     async_trait,
-    builder::{CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage},
+    builder::{CreateCommand, CreateInteractionResponseFollowup},
     prelude::Context,
 };
 
-use crate::state::AppState;
+use crate::{
+    discord_bot::{
+        common::{
+            embed::EmbedColor,
+            listing,
+            locale::{Locale, Text},
+        },
+        manager::ShardManagerContainer,
+    },
+    state::AppState,
+};
+
+use super::{
+    command::{Command, ResponseMode},
+    util::{guild_branding, guild_locale, CommandResponse},
+};
+
+/// how long an integration can go without a successful request before it is reported as degraded
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60 * 24);
 
-use super::{command::Command, util::CommandResponse};
+/// the first second of 2015, which discord snowflakes are measured from, in milliseconds
+const DISCORD_EPOCH: u64 = 1_420_070_400_000;
 
 pub struct PingCommand;
 
@@ -18,6 +39,29 @@ impl<'a> TryFrom<&'a CommandInteraction> for PingCommand {
     }
 }
 
+/// time a trivial query against the database, returning the round trip in milliseconds
+async fn database_latency(app_state: &AppState) -> Result<u128, sea_orm::DbErr> {
+    let start = Instant::now();
+    app_state.database.ping().await?;
+    Ok(start.elapsed().as_millis())
+}
+
+/// the latency of the gateway heartbeat of the shard the command arrived on, or why it isn't known
+async fn shard_latency(ctx: &Context) -> Result<Duration, &'static str> {
+    let manager = match ctx.data.read().await.get::<ShardManagerContainer>() {
+        Some(manager) => manager.clone(),
+        None => return Err("the shard manager isn't available"),
+    };
+    let manager = manager.lock().await;
+    let runners = manager.runners.lock().await;
+    match runners.get(&ShardId(ctx.shard_id)) {
+        Some(runner) => runner
+            .latency
+            .ok_or("no heartbeat has been acknowledged yet"),
+        None => Err("this shard isn't running"),
+    }
+}
+
 #[async_trait]
 impl<'a> Command<'a> for PingCommand {
     fn name() -> &'static str {
@@ -25,25 +69,109 @@ impl<'a> Command<'a> for PingCommand {
     }
 
     fn description() -> &'static str {
-        "Pings the bot, expect a pong response."
+        "Pings the bot, and reports how long it took to respond."
+    }
+
+    fn response_mode() -> ResponseMode {
+        // checking the integrations can take a few seconds
+        ResponseMode::Deferred { ephemeral: true }
     }
 
-    fn get_application_command_options(i: CreateCommand) -> CreateCommand {
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
         i
     }
 
     async fn handle_application_command<'b>(
         self,
-        _: &'b CommandInteraction,
-        _: &'b AppState,
-        _: &'b Context,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
     ) -> Result<CommandResponse, CommandResponse> {
-        Ok(CommandResponse::ComplexSuccess(
-            CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new()
-                    .content("Pong!")
-                    .ephemeral(true),
+        // the interaction id encodes when discord created it
+        let id: u64 = interaction.id.into();
+        let created_at = (id >> 22) + DISCORD_EPOCH;
+        let interaction_latency = chrono::Utc::now().timestamp_millis() - created_at as i64;
+
+        // ping should still answer when the database is down, so the locale and branding are
+        // best effort
+        let locale: Locale = guild_locale(interaction.guild_id, app_state)
+            .await
+            .unwrap_or_default();
+        let database_latency = match database_latency(app_state).await {
+            Ok(ms) => format!("{}ms", ms),
+            Err(e) => locale.format(Text::PingDatabaseUnavailable, &[&e.to_string()]),
+        };
+        let shard_latency = match shard_latency(ctx).await {
+            Ok(latency) => format!("{}ms", latency.as_millis()),
+            Err(reason) => locale.format(Text::PingShardUnavailable, &[reason]),
+        };
+
+        let maps = match (
+            app_state.maps_api().health(),
+            app_state.maps_api().last_success().map(|at| at.elapsed()),
+        ) {
+            (Err(e), _) => format!("❌ Google Maps: {}", e),
+            (Ok(_), Some(ago)) if ago > STALE_AFTER => format!(
+                "⚠️ Google Maps (degraded, last success {}h ago)",
+                ago.as_secs() / 3600
+            ),
+            (Ok(_), Some(ago)) => {
+                format!("✅ Google Maps (last success {}m ago)", ago.as_secs() / 60)
+            }
+            (Ok(_), None) => String::from("✅ Google Maps (no requests yet)"),
+        };
+        let listings = match (
+            listing::health(Duration::from_secs(5)).await,
+            listing::last_success().map(|at| at.elapsed()),
+        ) {
+            (Err(e), _) => format!("❌ realestate.co.nz: {}", e),
+            (Ok(()), Some(ago)) if ago > STALE_AFTER => format!(
+                "⚠️ realestate.co.nz (degraded, last listing loaded {}h ago)",
+                ago.as_secs() / 3600
             ),
+            (Ok(()), Some(ago)) => format!(
+                "✅ realestate.co.nz (last listing loaded {}m ago)",
+                ago.as_secs() / 60
+            ),
+            (Ok(()), None) => String::from("✅ realestate.co.nz"),
+        };
+        let listing_queue = format!(
+            "at most one request every {}ms, {} waiting",
+            listing::request_interval().as_millis(),
+            listing::queued_requests()
+        );
+        let all_healthy = maps.starts_with('✅') && listings.starts_with('✅');
+        let branding = guild_branding(interaction.guild_id, app_state)
+            .await
+            .unwrap_or_default();
+
+        Ok(CommandResponse::Followup(
+            CreateInteractionResponseFollowup::new()
+                .embed(
+                    branding
+                        .embed()
+                        .title(locale.text(Text::PingTitle))
+                        .field(
+                            locale.text(Text::PingInteractionLatency),
+                            format!("{}ms", interaction_latency),
+                            true,
+                        )
+                        .field(locale.text(Text::PingShardLatency), shard_latency, true)
+                        .field(locale.text(Text::PingDatabase), database_latency, true)
+                        .field(
+                            locale.text(Text::PingIntegrations),
+                            format!("{}\n{}", maps, listings),
+                            false,
+                        )
+                        .field(locale.text(Text::PingListingRequests), listing_queue, false)
+                        .footer(branding.flavour_footer(&mut rand::thread_rng()))
+                        .color(if all_healthy {
+                            EmbedColor::Green as u32
+                        } else {
+                            EmbedColor::Orange as u32
+                        }),
+                )
+                .ephemeral(true),
         ))
     }
 }
@@ -0,0 +1,609 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use log::{error, info};
+use serenity::{
+    all::{
+        ChannelId, CommandInteraction, CommandOptionType, GuildId, ResolvedOption, ResolvedValue,
+    },
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{
+            currency::{format_money, Currency},
+            embed::EmbedColor,
+        },
+        database::{
+            bills::{NewBill, SerenityBillDatabase},
+            guild_config::SerenityGuildConfigDatabase,
+            recurring_bills::{
+                NewRecurringBill, RecurringBillModel, SerenityRecurringBillDatabase,
+            },
+        },
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::Command,
+    pay::{bill_components, bill_embed, shares_for, split_evenly, to_cents, UNCATEGORIZED},
+    util::{
+        display_name_for, guild_branding, guild_currency, is_admin, paginate, Branding,
+        CommandResponse,
+    },
+};
+
+/// the number of recurring bills shown on each page of /recurring list
+const BILLS_PER_PAGE: usize = 10;
+
+pub enum Recurring<'a> {
+    Add {
+        purpose: &'a str,
+        amount: f64,
+        day_of_month: i64,
+        account: Option<&'a str>,
+    },
+    List,
+    Edit {
+        id: i64,
+        purpose: Option<&'a str>,
+        amount: Option<f64>,
+        day_of_month: Option<i64>,
+        account: Option<&'a str>,
+    },
+    Pause(i64),
+    Resume(i64),
+}
+
+/// the first day of the month after the one containing `date`
+fn next_month(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of the month to be valid")
+}
+
+/// the day a bill falls due in the month containing `date`, a day past the end of a short month
+/// falls on the last day of that month instead
+fn due_date(day_of_month: i32, date: NaiveDate) -> NaiveDate {
+    let last_day = (next_month(date) - Duration::days(1)).day();
+    date.with_day((day_of_month as u32).clamp(1, last_day))
+        .expect("clamped day to be valid")
+}
+
+/// check if a bill has already been posted in the month containing `today`
+fn posted_this_month(bill: &RecurringBillModel, today: NaiveDate) -> bool {
+    bill.last_posted
+        .map(|last| last.year() == today.year() && last.month() == today.month())
+        .unwrap_or(false)
+}
+
+/// check if a bill should be posted today
+fn is_due(bill: &RecurringBillModel, today: NaiveDate) -> bool {
+    !bill.paused && today >= due_date(bill.day_of_month, today) && !posted_this_month(bill, today)
+}
+
+/// the day a bill will next be posted
+fn next_post_date(bill: &RecurringBillModel, today: NaiveDate) -> NaiveDate {
+    if posted_this_month(bill, today) {
+        due_date(bill.day_of_month, next_month(today))
+    } else {
+        due_date(bill.day_of_month, today).max(today)
+    }
+}
+
+/// a bill scheduled for a day which has already passed this month should start next month,
+/// rather than being posted straight away
+fn skip_passed_day(day_of_month: i32, today: NaiveDate) -> Option<NaiveDate> {
+    if due_date(day_of_month, today) < today {
+        Some(today)
+    } else {
+        None
+    }
+}
+
+/// format a day of the month as an ordinal, e.g. 1st, 22nd, 13th
+fn ordinal(day: i32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+/// describe a recurring bill on a single line
+fn describe(bill: &RecurringBillModel, currency: Currency) -> String {
+    format!(
+        "`{}` **{}** - {} on the {} of each month{}",
+        bill.id,
+        bill.purpose,
+        format_money(bill.total, currency),
+        ordinal(bill.day_of_month),
+        if bill.paused { " (paused)" } else { "" }
+    )
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for Recurring<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        let (name, options) = match options.into_iter().next() {
+            Some(ResolvedOption {
+                name,
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => (name, options),
+            _ => return Err(String::from("unknown subcommand")),
+        };
+
+        let mut id = None;
+        let mut purpose = None;
+        let mut amount = None;
+        let mut day_of_month = None;
+        let mut account = None;
+
+        for option in options {
+            match (option.name, option.value) {
+                ("id", ResolvedValue::Integer(val)) => id = Some(val),
+                ("purpose", ResolvedValue::String(val)) => purpose = Some(val),
+                ("amount", ResolvedValue::Number(val)) => amount = Some(val),
+                ("day", ResolvedValue::Integer(val)) => day_of_month = Some(val),
+                ("account", ResolvedValue::String(val)) => account = Some(val),
+                _ => {}
+            }
+        }
+
+        match name {
+            "add" => Ok(Recurring::Add {
+                purpose: purpose.ok_or("purpose is required")?,
+                amount: amount.ok_or("amount is required")?,
+                day_of_month: day_of_month.ok_or("day is required")?,
+                account,
+            }),
+            "list" => Ok(Recurring::List),
+            "edit" => Ok(Recurring::Edit {
+                id: id.ok_or("id is required")?,
+                purpose,
+                amount,
+                day_of_month,
+                account,
+            }),
+            "pause" => Ok(Recurring::Pause(id.ok_or("id is required")?)),
+            "resume" => Ok(Recurring::Resume(id.ok_or("id is required")?)),
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
+/// load a recurring bill which the user running the command is allowed to change, only the
+/// flatmate who registered a bill or an admin may change it
+async fn editable_bill(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+    guild_id: u64,
+    id: i64,
+) -> Result<RecurringBillModel, CommandResponse> {
+    let not_found =
+        || CommandResponse::BasicFailure(format!("there is no recurring bill with id {}", id));
+
+    let id = i32::try_from(id).map_err(|_| not_found())?;
+    let bill = match app_state.get_recurring_bill(guild_id, id).await {
+        Ok(Some(bill)) => bill,
+        Ok(None) => return Err(not_found()),
+        Err(e) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "error communicating with database: {}",
+                e
+            )));
+        }
+    };
+
+    if bill.created_by as u64 != u64::from(interaction.user.id)
+        && !is_admin(interaction, app_state, ctx).await
+    {
+        return Err(CommandResponse::BasicFailure(String::from(
+            "only the flatmate who registered this bill can change it",
+        )));
+    }
+
+    Ok(bill)
+}
+
+/// the options shared by the add and edit subcommands, which are only required when adding
+fn bill_options(subcommand: CreateCommandOption, required: bool) -> CreateCommandOption {
+    subcommand
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "purpose",
+                "What is this bill for?",
+            )
+            .required(required)
+            .max_length(100),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "amount",
+                "The amount of the bill, split evenly between all flatmates",
+            )
+            .required(required)
+            .min_number_value(0.01),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "day",
+                "The day of the month to post the bill on",
+            )
+            .required(required)
+            .min_int_value(1)
+            .max_int_value(31),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "account",
+                "The account number to pay into, defaults to head tenant account.",
+            )
+            .required(false),
+        )
+}
+
+#[async_trait]
+impl<'a> Command<'a> for Recurring<'a> {
+    fn name() -> &'static str {
+        "recurring"
+    }
+
+    fn description() -> &'static str {
+        "manage bills which are posted every month, like rent and power"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        let id = |description: &str| {
+            CreateCommandOption::new(CommandOptionType::Integer, "id", description)
+                .required(true)
+                .min_int_value(1)
+        };
+
+        let add = bill_options(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "add",
+                "register a bill to be posted every month",
+            ),
+            true,
+        );
+
+        let list = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show every recurring bill",
+        );
+
+        let edit = bill_options(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "edit",
+                "change a recurring bill",
+            )
+            .add_sub_option(id("The id of the bill, from /recurring list")),
+            false,
+        );
+
+        let pause = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "pause",
+            "stop posting a recurring bill until it is resumed",
+        )
+        .add_sub_option(id("The id of the bill, from /recurring list"));
+
+        let resume = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "resume",
+            "start posting a paused recurring bill again",
+        )
+        .add_sub_option(id("The id of the bill, from /recurring list"));
+
+        cmd.add_option(add)
+            .add_option(list)
+            .add_option(edit)
+            .add_option(pause)
+            .add_option(resume)
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let guild_id: u64 = match interaction.guild_id {
+            Some(guild_id) => guild_id.0.into(),
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "recurring bills can only be managed in a server",
+                )))
+            }
+        };
+        let today = Local::now().date_naive();
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+
+        let (bill, action) = match self {
+            Recurring::Add {
+                purpose,
+                amount,
+                day_of_month,
+                account,
+            } => {
+                let day_of_month = day_of_month as i32;
+                let bill = match app_state
+                    .add_recurring_bill(NewRecurringBill {
+                        guild_id,
+                        channel_id: interaction.channel_id.into(),
+                        created_by: interaction.user.id.into(),
+                        purpose,
+                        total: to_cents(amount),
+                        account: account.unwrap_or(CONFIG.head_tennant_acc_number.as_str()),
+                        day_of_month,
+                        last_posted: skip_passed_day(day_of_month, today),
+                    })
+                    .await
+                {
+                    Ok(bill) => bill,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                (bill, "Registered")
+            }
+            Recurring::List => {
+                let bills = match app_state.get_recurring_bills(guild_id).await {
+                    Ok(bills) => bills,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if bills.is_empty() {
+                    return Err(CommandResponse::BasicFailure(String::from(
+                        "there are no recurring bills, add one with /recurring add",
+                    )));
+                }
+
+                let lines: Vec<String> =
+                    bills.iter().map(|bill| describe(bill, currency)).collect();
+                let (embed, components) = paginate(
+                    interaction.id.into(),
+                    "Recurring bills",
+                    EmbedColor::Green as u32,
+                    &branding,
+                    &lines,
+                    BILLS_PER_PAGE,
+                );
+
+                return Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .embed(embed)
+                            .components(components),
+                    ),
+                ));
+            }
+            Recurring::Edit {
+                id,
+                purpose,
+                amount,
+                day_of_month,
+                account,
+            } => {
+                if purpose.is_none()
+                    && amount.is_none()
+                    && day_of_month.is_none()
+                    && account.is_none()
+                {
+                    return Err(CommandResponse::BasicFailure(String::from(
+                        "provide at least one value to change",
+                    )));
+                }
+
+                let mut bill = editable_bill(interaction, app_state, ctx, guild_id, id).await?;
+                if let Some(purpose) = purpose {
+                    bill.purpose = purpose.to_string();
+                }
+                if let Some(amount) = amount {
+                    bill.total = to_cents(amount);
+                }
+                if let Some(account) = account {
+                    bill.account = account.to_string();
+                }
+                if let Some(day_of_month) = day_of_month {
+                    bill.day_of_month = day_of_month as i32;
+                    if !posted_this_month(&bill, today) {
+                        bill.last_posted =
+                            skip_passed_day(bill.day_of_month, today).or(bill.last_posted);
+                    }
+                }
+
+                (bill, "Updated")
+            }
+            Recurring::Pause(id) => {
+                let mut bill = editable_bill(interaction, app_state, ctx, guild_id, id).await?;
+                bill.paused = true;
+
+                (bill, "Paused")
+            }
+            Recurring::Resume(id) => {
+                let mut bill = editable_bill(interaction, app_state, ctx, guild_id, id).await?;
+                bill.paused = false;
+
+                (bill, "Resumed")
+            }
+        };
+
+        let bill = match app_state.update_recurring_bill(bill).await {
+            Ok(bill) => bill,
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        let mut description = format!("{} {}", action, describe(&bill, currency));
+        if !bill.paused {
+            description.push_str(&format!(
+                "\nIt will next be posted on {}",
+                next_post_date(&bill, today).format("%-d %B")
+            ));
+        }
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(
+                    branding
+                        .embed()
+                        .description(description)
+                        .color(EmbedColor::Green as u32),
+                ),
+            ),
+        ))
+    }
+}
+
+/// post every recurring bill in the guild which is due today as a bill, split evenly between
+/// the current flatmates. Each bill is only posted once a month, so this is safe to call
+/// repeatedly
+pub async fn post_recurring_bills(guild: GuildId, app_state: &AppState, ctx: &Context) {
+    let today = Local::now().date_naive();
+
+    let bills = match app_state.get_recurring_bills(guild.0.into()).await {
+        Ok(bills) => bills,
+        Err(e) => {
+            error!("unable to load recurring bills for guild {}: {}", guild, e);
+            return;
+        }
+    };
+
+    let flatmates = app_state.flatmates();
+    if flatmates.is_empty() {
+        return;
+    }
+
+    let (currency, branding): (Currency, _) = match app_state.get_guild_config(guild.0.into()).await
+    {
+        Ok(config) => (
+            config
+                .as_ref()
+                .and_then(|config| config.currency.parse().ok())
+                .unwrap_or_default(),
+            Branding::from_config(config.as_ref()),
+        ),
+        Err(e) => {
+            error!("unable to load config for guild {}: {}", guild, e);
+            return;
+        }
+    };
+
+    for mut bill in bills.into_iter().filter(|bill| is_due(bill, today)) {
+        let total = bill.total as f64 / 100.0;
+        let amounts = split_evenly(total, &flatmates);
+        let shares = shares_for(&amounts);
+        let creator = display_name_for(&app_state.all_flatmates(), bill.created_by as u64);
+
+        // the bill is stored before it is posted, so a bill which couldn't be stored is never
+        // shown without its buttons. Either failing leaves it due, so it is tried again
+        let stored = match app_state
+            .create_bill(NewBill {
+                purpose: &bill.purpose,
+                total: bill.total,
+                account: &bill.account,
+                receipt_url: "",
+                created_by: bill.created_by as u64,
+                message_id: None,
+                channel_id: bill.channel_id as u64,
+                guild_id: Some(guild.0.into()),
+                category: UNCATEGORIZED,
+                shares,
+            })
+            .await
+        {
+            Ok(stored) => stored,
+            Err(e) => {
+                error!(
+                    "unable to store recurring bill {} in guild {}: {}",
+                    bill.id, guild, e
+                );
+                continue;
+            }
+        };
+
+        let channel = ChannelId::new(bill.channel_id as u64);
+        let message = match channel
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .embed(bill_embed(
+                        &bill.purpose,
+                        &creator,
+                        total,
+                        amounts,
+                        &bill.account,
+                        true,
+                        currency,
+                        &branding,
+                    ))
+                    .components(bill_components(Some(stored.id), &[])),
+            )
+            .await
+        {
+            Ok(message) => message,
+            Err(e) => {
+                error!(
+                    "unable to post recurring bill {} in guild {}: {}",
+                    bill.id, guild, e
+                );
+                if let Err(e) = app_state.delete_bill(stored.id).await {
+                    error!("unable to remove unposted bill {}: {}", stored.id, e);
+                }
+                continue;
+            }
+        };
+
+        if let Err(e) = app_state
+            .set_bill_message(stored.id, message.id.into())
+            .await
+        {
+            error!("unable to record the message of bill {}: {}", stored.id, e);
+        }
+
+        info!("posted recurring bill {} in guild {}", bill.id, guild);
+
+        bill.last_posted = Some(today);
+        if let Err(e) = app_state.update_recurring_bill(bill).await {
+            error!("unable to update recurring bill in guild {}: {}", guild, e);
+        }
+    }
+}
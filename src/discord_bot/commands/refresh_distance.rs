@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use serenity::{
+    all::CommandInteraction,
+    async_trait,
+    builder::{CreateCommand, CreateInteractionResponseFollowup, CreateMessage, EditMessage},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::listing::{distance_embed, fetch_listing_cached},
+        database::{guild_config::SerenityGuildConfigDatabase, listings::SerenityListingDatabase},
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    command::{Command, ResponseMode},
+    util::{CommandResponse, FailureMessageKind},
+};
+
+pub struct RefreshDistanceCommand;
+
+impl<'a> TryFrom<&'a CommandInteraction> for RefreshDistanceCommand {
+    type Error = String;
+    fn try_from(_: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for RefreshDistanceCommand {
+    fn name() -> &'static str {
+        "refresh-distance"
+    }
+
+    fn description() -> &'static str {
+        "Load the distances for the listing in this thread again"
+    }
+
+    fn response_mode() -> ResponseMode {
+        // the listing and every travel mode are loaded again, which can take a few seconds
+        ResponseMode::Deferred { ephemeral: true }
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let posted = match app_state
+            .get_listing_by_thread_id(interaction.channel_id.into())
+            .await
+        {
+            Ok(Some(posted)) => posted,
+            Ok(None) => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "this can only be used in the thread of a listing",
+                )));
+            }
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        let config = match interaction.guild_id {
+            Some(guild_id) => match app_state.get_guild_config(guild_id.0.into()).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+            },
+            None => None,
+        };
+        let timeout = Duration::from_secs(
+            config
+                .as_ref()
+                .and_then(|config| config.listing_timeout)
+                .map(|timeout| timeout as u64)
+                .unwrap_or(CONFIG.listing_timeout),
+        );
+        let ttl = Duration::from_secs(CONFIG.listing_cache_ttl);
+
+        let (listing, cached) =
+            match fetch_listing_cached(posted.listing_id as u64, posted.url.clone(), timeout, ttl)
+                .await
+            {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    return Err(CommandResponse::ComplexFailure {
+                        response: format!("Couldn't load the listing for {}", posted.address),
+                        kind: FailureMessageKind::Error,
+                        log_message: e.to_string(),
+                    });
+                }
+            };
+
+        let embed = match distance_embed(&listing, cached, config.as_ref(), app_state).await {
+            Some(embed) => embed,
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "the distances still couldn't be loaded, try again later",
+                )));
+            }
+        };
+
+        // the original distances are replaced where possible, so the thread isn't cluttered
+        let edited = match posted.distance_message_id {
+            Some(message_id) => {
+                match interaction.channel_id.message(ctx, message_id as u64).await {
+                    Ok(mut message) => message
+                        .edit(ctx, EditMessage::new().content("").embed(embed.clone()))
+                        .await
+                        .is_ok(),
+                    Err(_) => false,
+                }
+            }
+            None => false,
+        };
+
+        if !edited {
+            let sent = match interaction
+                .channel_id
+                .send_message(ctx, CreateMessage::new().embed(embed))
+                .await
+            {
+                Ok(sent) => sent,
+                Err(e) => {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with discord: {}",
+                        e
+                    )));
+                }
+            };
+
+            if let Err(e) = app_state
+                .set_listing_distance_message(posted, sent.id.into())
+                .await
+            {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        }
+
+        Ok(CommandResponse::Followup(
+            CreateInteractionResponseFollowup::new()
+                .content(format!("Refreshed the distances for {}", listing.address))
+                .ephemeral(true),
+        ))
+    }
+}
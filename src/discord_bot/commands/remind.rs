@@ -0,0 +1,633 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use log::error;
+use serenity::{
+    all::{
+        ChannelId, CommandInteraction, CommandOptionType, GuildId, ResolvedOption, ResolvedValue,
+    },
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::embed::EmbedColor,
+        database::{
+            guild_config::SerenityGuildConfigDatabase,
+            reminders::{NewReminder, SerenityReminderDatabase},
+        },
+    },
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    util::{guild_branding, is_admin, paginate, Branding, CommandResponse},
+};
+
+/// the number of reminders shown on each page of /remind list
+const REMINDERS_PER_PAGE: usize = 10;
+
+/// the time of day used when a reminder only specifies a day
+const DEFAULT_HOUR: u32 = 9;
+
+pub enum Remind<'a> {
+    Set {
+        message: &'a str,
+        when: &'a str,
+        ping_everyone: bool,
+    },
+    List,
+    Cancel(i64),
+}
+
+/// parse a time of day such as "7pm", "7:30 pm", "19:00", "noon", or "midnight"
+fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.trim().replace(' ', "");
+    match input.as_str() {
+        "noon" | "midday" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, offset) = if let Some(clock) = input.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = input.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (input.as_str(), None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+
+    let hour = match offset {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(offset) => hour % 12 + offset,
+        // a bare number is ambiguous, so a 24 hour time must include the minutes
+        None if !clock.contains(':') => return None,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// parse a day such as "today", "tomorrow", "tuesday", "next tue", "2023-06-20", or "20/6"
+fn parse_day(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim();
+    match input {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+
+    let weekday = input.strip_prefix("next ").unwrap_or(input);
+    if let Ok(weekday) = weekday.parse::<Weekday>() {
+        let days = (weekday.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64)
+            .rem_euclid(7);
+        return Some(today + Duration::days(days));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%d/%m/%Y") {
+        return Some(date);
+    }
+
+    // a day without a year is the next time that date comes around
+    let (day, month) = input.split_once('/')?;
+    let (day, month) = (day.parse().ok()?, month.parse().ok()?);
+    match NaiveDate::from_ymd_opt(today.year(), month, day) {
+        Some(date) if date >= today => Some(date),
+        _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day),
+    }
+}
+
+/// parse a relative time such as "2 hours", "1h 30m", or "a day and 3 hours"
+fn parse_relative(input: &str) -> Option<Duration> {
+    // split "1h30m" into "1 h 30 m" so every number and unit is its own token
+    let mut spaced = String::new();
+    let mut previous_digit = None;
+    for c in input.chars() {
+        let is_digit = c.is_ascii_digit();
+        if previous_digit.is_some() && previous_digit != Some(is_digit) && c.is_alphanumeric() {
+            spaced.push(' ');
+        }
+        previous_digit = if c.is_alphanumeric() {
+            Some(is_digit)
+        } else {
+            None
+        };
+        spaced.push(c);
+    }
+
+    let mut total = Duration::zero();
+    let mut amount = None;
+    for token in spaced.split(|c: char| c.is_whitespace() || c == ',') {
+        match token {
+            "" | "and" => continue,
+            "a" | "an" => amount = Some(1),
+            token if token.chars().all(|c| c.is_ascii_digit()) => {
+                amount = Some(token.parse::<i64>().ok()?)
+            }
+            unit => {
+                let unit_millis = match unit {
+                    "m" | "min" | "mins" | "minute" | "minutes" => 60 * 1000,
+                    "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60 * 1000,
+                    "d" | "day" | "days" => 24 * 60 * 60 * 1000,
+                    "w" | "wk" | "wks" | "week" | "weeks" => 7 * 24 * 60 * 60 * 1000,
+                    _ => return None,
+                };
+                // the amount is typed by the user, so anything too large to represent is refused
+                // rather than overflowing
+                let millis = amount.take()?.checked_mul(unit_millis)?;
+                total = total.checked_add(&Duration::milliseconds(millis))?;
+            }
+        }
+    }
+
+    if amount.is_some() || total <= Duration::zero() {
+        return None;
+    }
+
+    Some(total)
+}
+
+/// work out when a reminder should be sent, from either a relative time ("in 2 hours") or an
+/// absolute day and time ("tuesday 7pm"). A time on its own is taken to be the next time the
+/// clock reads that time, and a day on its own is taken to be that morning.
+fn parse_when(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let input = input.trim().to_lowercase();
+    let unknown = || {
+        format!(
+            "I couldn't understand \"{}\", try something like \"in 2 hours\" or \"tuesday 7pm\"",
+            input
+        )
+    };
+
+    if let Some(relative) = input.strip_prefix("in ") {
+        return parse_relative(relative)
+            .and_then(|duration| now.checked_add_signed(duration))
+            .ok_or_else(unknown);
+    }
+
+    let input = input.replace(" at ", " ");
+    let input = input.strip_prefix("at ").unwrap_or(&input);
+
+    // try every split of the input into a day followed by a time
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let parsed = (0..=words.len()).find_map(|split| {
+        let (day, time) = (words[..split].join(" "), words[split..].join(" "));
+        let day = if day.is_empty() {
+            None
+        } else {
+            Some(parse_day(&day, now.date())?)
+        };
+        let time = if time.is_empty() {
+            None
+        } else {
+            Some(parse_time(&time)?)
+        };
+        Some((day, time))
+    });
+
+    let when = match parsed {
+        Some((None, None)) | None => return Err(unknown()),
+        Some((Some(day), time)) => {
+            let when = day.and_time(time.unwrap_or_else(|| {
+                NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).expect("valid default time")
+            }));
+
+            // a weekday which has already passed today refers to next week
+            if when <= now
+                && day == now.date()
+                && words.iter().any(|w| w.parse::<Weekday>().is_ok())
+            {
+                when + Duration::weeks(1)
+            } else {
+                when
+            }
+        }
+        Some((None, Some(time))) => {
+            let when = now.date().and_time(time);
+            if when <= now {
+                when + Duration::days(1)
+            } else {
+                when
+            }
+        }
+    };
+
+    if when <= now {
+        return Err(String::from("that time has already passed"));
+    }
+
+    Ok(when)
+}
+
+/// format a time using discord's timestamp markup, so it is shown in each reader's timezone
+fn discord_timestamp(when: NaiveDateTime) -> String {
+    match Local.from_local_datetime(&when).earliest() {
+        Some(when) => format!("<t:{}:f> (<t:{}:R>)", when.timestamp(), when.timestamp()),
+        None => when.format("%-d %B %Y %H:%M").to_string(),
+    }
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for Remind<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        match options.into_iter().next() {
+            Some(ResolvedOption {
+                name: "set",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => {
+                let mut message = None;
+                let mut when = None;
+                let mut ping_everyone = false;
+
+                for option in options {
+                    match (option.name, option.value) {
+                        ("message", ResolvedValue::String(val)) => message = Some(val),
+                        ("when", ResolvedValue::String(val)) => when = Some(val),
+                        ("ping", ResolvedValue::Boolean(val)) => ping_everyone = val,
+                        _ => {}
+                    }
+                }
+
+                Ok(Remind::Set {
+                    message: message.ok_or("message is required")?,
+                    when: when.ok_or("when is required")?,
+                    ping_everyone,
+                })
+            }
+            Some(ResolvedOption {
+                name: "cancel",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => match options.first().map(|o| &o.value) {
+                Some(ResolvedValue::Integer(id)) => Ok(Remind::Cancel(*id)),
+                _ => Err(String::from("id is required")),
+            },
+            Some(ResolvedOption { name: "list", .. }) => Ok(Remind::List),
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for Remind<'a> {
+    fn name() -> &'static str {
+        "remind"
+    }
+
+    fn description() -> &'static str {
+        "set reminders for the flat"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        let set = CreateCommandOption::new(CommandOptionType::SubCommand, "set", "set a reminder")
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "message",
+                    "What to be reminded about",
+                )
+                .required(true)
+                .max_length(200),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "when",
+                    "When to send the reminder, e.g. \"in 2 hours\" or \"tuesday 7pm\"",
+                )
+                .required(true)
+                .max_length(50),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "ping",
+                    "Whether to ping everyone when the reminder is sent",
+                )
+                .required(false),
+            );
+
+        let list = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show every upcoming reminder",
+        );
+
+        let cancel = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "cancel",
+            "cancel an upcoming reminder",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "id",
+                "The id of the reminder, from /remind list",
+            )
+            .required(true)
+            .min_int_value(1),
+        );
+
+        cmd.add_option(set).add_option(list).add_option(cancel)
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let guild_id: u64 = match interaction.guild_id {
+            Some(guild_id) => guild_id.0.into(),
+            None => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "reminders can only be set in a server",
+                )))
+            }
+        };
+
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+        match self {
+            Remind::Set {
+                message,
+                when,
+                ping_everyone,
+            } => {
+                let remind_at = match parse_when(when, Local::now().naive_local()) {
+                    Ok(remind_at) => remind_at,
+                    Err(e) => return Err(CommandResponse::BasicFailure(e)),
+                };
+
+                let reminder = match app_state
+                    .add_reminder(NewReminder {
+                        guild_id,
+                        channel_id: interaction.channel_id.into(),
+                        author_id: interaction.user.id.into(),
+                        message,
+                        remind_at,
+                        ping_everyone,
+                    })
+                    .await
+                {
+                    Ok(reminder) => reminder,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new().embed(
+                            branding
+                                .embed()
+                                .description(format!(
+                                    "I'll remind you about \"{}\" on {}",
+                                    reminder.message,
+                                    discord_timestamp(reminder.remind_at)
+                                ))
+                                .footer(CreateEmbedFooter::new(format!("id {}", reminder.id)))
+                                .color(EmbedColor::Green as u32),
+                        ),
+                    ),
+                ))
+            }
+            Remind::List => {
+                let reminders = match app_state.get_reminders(guild_id).await {
+                    Ok(reminders) => reminders,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if reminders.is_empty() {
+                    return Err(CommandResponse::BasicFailure(String::from(
+                        "there are no upcoming reminders, set one with /remind set",
+                    )));
+                }
+
+                let lines: Vec<String> = reminders
+                    .iter()
+                    .map(|reminder| {
+                        format!(
+                            "`{}` {} - {} (<@{}>)",
+                            reminder.id,
+                            discord_timestamp(reminder.remind_at),
+                            reminder.message,
+                            reminder.author_id
+                        )
+                    })
+                    .collect();
+
+                let (embed, components) = paginate(
+                    interaction.id.into(),
+                    "Reminders",
+                    EmbedColor::Green as u32,
+                    &branding,
+                    &lines,
+                    REMINDERS_PER_PAGE,
+                );
+
+                Ok(CommandResponse::ComplexSuccess(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .embed(embed)
+                            .components(components),
+                    ),
+                ))
+            }
+            Remind::Cancel(id) => {
+                let id = match i32::try_from(id) {
+                    Ok(id) => id,
+                    Err(_) => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "there is no reminder with id {}",
+                            id
+                        )))
+                    }
+                };
+
+                let reminder = match app_state.get_reminders(guild_id).await {
+                    Ok(reminders) => reminders.into_iter().find(|r| r.id == id),
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                let admin = is_admin(interaction, app_state, ctx).await;
+                match reminder {
+                    Some(reminder)
+                        if reminder.author_id as u64 == u64::from(interaction.user.id) || admin => {
+                    }
+                    Some(_) => {
+                        return Err(CommandResponse::BasicFailure(String::from(
+                            "only the person who set a reminder can cancel it",
+                        )))
+                    }
+                    None => {
+                        return Err(CommandResponse::BasicFailure(format!(
+                            "there is no reminder with id {}",
+                            id
+                        )))
+                    }
+                }
+
+                match app_state.delete_reminder(guild_id, id).await {
+                    Ok(Some(reminder)) => Ok(CommandResponse::BasicSuccess(format!(
+                        "Cancelled the reminder about \"{}\"",
+                        reminder.message
+                    ))),
+                    Ok(None) => Err(CommandResponse::BasicFailure(format!(
+                        "there is no reminder with id {}",
+                        id
+                    ))),
+                    Err(e) => Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// check if discord refused to send a reminder in a way retrying can't fix, such as the channel
+/// having been deleted or the bot losing access to it
+fn is_permanent_send_error(e: &serenity::Error) -> bool {
+    match e {
+        serenity::Error::Http(http) => {
+            matches!(http.status_code().map(|s| s.as_u16()), Some(403 | 404))
+        }
+        _ => false,
+    }
+}
+
+/// send every reminder in the guild which is due, deleting each once it has been sent. A
+/// reminder which fails to send is kept to be retried on the next run, unless discord refused it
+/// outright, so a removed channel doesn't fail forever
+pub async fn send_due_reminders(guild: GuildId, app_state: &AppState, ctx: &Context) {
+    let reminders = match app_state
+        .get_due_reminders(guild.0.into(), Local::now().naive_local())
+        .await
+    {
+        Ok(reminders) => reminders,
+        Err(e) => {
+            error!("unable to load reminders for guild {}: {}", guild, e);
+            return;
+        }
+    };
+
+    let (branding, reminder_channel) = match app_state.get_guild_config(guild.0.into()).await {
+        Ok(config) => (
+            Branding::from_config(config.as_ref()),
+            config.and_then(|c| c.reminder_channel),
+        ),
+        Err(e) => {
+            error!("unable to load config for guild {}: {}", guild, e);
+            (Branding::default(), None)
+        }
+    };
+
+    for reminder in reminders {
+        let mut message = CreateMessage::new().embed(
+            branding
+                .embed()
+                .title("Reminder")
+                .description(format!(
+                    "{}\n\nfrom <@{}>",
+                    reminder.message, reminder.author_id
+                ))
+                .color(EmbedColor::Orange as u32),
+        );
+        if reminder.ping_everyone {
+            message = message.content("@everyone");
+        }
+
+        // the guild's reminder channel takes priority over where the reminder was set
+        let channel = reminder_channel.unwrap_or(reminder.channel_id);
+        match ChannelId::new(channel as u64)
+            .send_message(ctx, message)
+            .await
+        {
+            Ok(_) => {}
+            Err(e) if is_permanent_send_error(&e) => {
+                error!(
+                    "dropping reminder {} in guild {}, as it can't be sent: {}",
+                    reminder.id, guild, e
+                );
+            }
+            Err(e) => {
+                // the reminder is kept, so it is sent on the next run instead of being lost
+                error!(
+                    "unable to send reminder {} in guild {}: {}",
+                    reminder.id, guild, e
+                );
+                continue;
+            }
+        }
+
+        if let Err(e) = app_state.delete_reminder(guild.0.into(), reminder.id).await {
+            error!(
+                "unable to delete reminder {} in guild {}: {}",
+                reminder.id, guild, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 6, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_relative_times() {
+        assert_eq!(
+            parse_when("in 1h 30m", now()),
+            Ok(now() + Duration::minutes(90))
+        );
+        assert_eq!(
+            parse_when("in a day and 3 hours", now()),
+            Ok(now() + Duration::hours(27))
+        );
+    }
+
+    #[test]
+    fn refuses_out_of_range_amounts() {
+        // too large for a duration
+        assert!(parse_when("in 9999999999999 weeks", now()).is_err());
+        assert!(parse_when("in 99999999999999999999 minutes", now()).is_err());
+        // a valid duration, but past the last representable date
+        assert!(parse_when("in 99999999 days", now()).is_err());
+    }
+}
@@ -1,10 +1,9 @@
+use std::time::Duration;
+
 use serenity::{
-    all::{CommandInteraction, CommandOptionType},
+    all::{ChannelId, ChannelType, CommandInteraction, CommandOptionType, ResolvedValue},
     async_trait,
-    builder::{
-        CreateCommand, CreateCommandOption, CreateInteractionResponse,
-        CreateInteractionResponseMessage, CreateMessage,
-    },
+    builder::{CreateCommand, CreateCommandOption, CreateMessage},
     prelude::Context,
 };
 
@@ -12,25 +11,33 @@ use crate::state::AppState;
 
 use super::{
     command::Command,
-    util::{CommandResponse, FailureMessageKind},
+    util::{is_admin, CommandResponse, FailureMessageKind},
 };
 
 pub struct SayCommand<'a> {
     message: &'a str,
+    /// the channel to send the message to, defaults to the channel the command was used in
+    channel: Option<(ChannelId, ChannelType)>,
 }
 
 impl<'a> TryFrom<&'a CommandInteraction> for SayCommand<'a> {
     type Error = String;
     fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
-        let message = interaction
-            .data
-            .options
-            .get(0)
-            .ok_or("No message provided")?
-            .value
-            .as_str()
-            .ok_or("No message provided")?;
-        Ok(Self { message })
+        let mut message = None;
+        let mut channel = None;
+
+        for option in interaction.data.options() {
+            match (option.name, option.value) {
+                ("text", ResolvedValue::String(val)) => message = Some(val),
+                ("channel", ResolvedValue::Channel(val)) => channel = Some((val.id, val.kind)),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            message: message.ok_or("No message provided")?,
+            channel,
+        })
     }
 }
 
@@ -44,7 +51,11 @@ impl<'a> Command<'a> for SayCommand<'a> {
         "Says whatever you want!"
     }
 
-    fn get_application_command_options(i: CreateCommand) -> CreateCommand {
+    fn cooldown() -> Option<Duration> {
+        Some(Duration::from_secs(10))
+    }
+
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
         i.add_option(
             CreateCommandOption::new(
                 CommandOptionType::String,
@@ -55,19 +66,52 @@ impl<'a> Command<'a> for SayCommand<'a> {
             .max_length(1900)
             .to_owned(),
         )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "channel",
+                "The channel to send the message in, defaults to this channel",
+            )
+            .required(false)
+            .channel_types(vec![ChannelType::Text, ChannelType::News]),
+        )
     }
 
     async fn handle_application_command<'b>(
         self,
         interaction: &'b CommandInteraction,
-        _: &'b AppState,
+        app_state: &'b AppState,
         ctx: &'b Context,
     ) -> Result<CommandResponse, CommandResponse> {
-        if let Err(e) = interaction
-            .channel_id
+        if !is_admin(interaction, app_state, ctx).await {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "insufficient permissions",
+            )));
+        }
+
+        let channel_id = match self.channel {
+            Some((_, kind)) if !matches!(kind, ChannelType::Text | ChannelType::News) => {
+                return Err(CommandResponse::BasicFailure(String::from(
+                    "I can only send messages in text channels",
+                )));
+            }
+            Some((channel_id, _)) => channel_id,
+            None => interaction.channel_id,
+        };
+
+        if let Err(e) = channel_id
             .send_message(ctx, CreateMessage::new().content(self.message))
             .await
         {
+            if let serenity::Error::Http(ref http) = e {
+                if http.status_code().map(|s| s.as_u16()) == Some(403) {
+                    return Err(CommandResponse::BasicFailure(format!(
+                        "I don't have permission to send messages in <#{}>",
+                        channel_id
+                    )));
+                }
+            }
+
             return Err(CommandResponse::ComplexFailure {
                 response: String::from("Failed to use /say due to error"),
                 kind: FailureMessageKind::Error,
@@ -75,12 +119,9 @@ impl<'a> Command<'a> for SayCommand<'a> {
             });
         }
 
-        Ok(CommandResponse::ComplexSuccess(
-            CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new()
-                    .content(format!("I will send: {}", self.message))
-                    .ephemeral(true),
-            ),
-        ))
+        Ok(CommandResponse::BasicSuccess(format!(
+            "I will send in <#{}>: {}",
+            channel_id, self.message
+        )))
     }
 }
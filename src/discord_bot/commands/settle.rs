@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serenity::{
+    all::CommandInteraction,
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::bills::SerenityBillDatabase,
+    },
+    state::AppState,
+};
+
+use super::{
+    balance::net_balances,
+    util::{display_name_for, guild_branding, guild_currency, CommandResponse},
+};
+
+/// given the net position of each flatmate in cents (positive when they are owed money, negative
+/// when they owe money) find a minimal set of transfers that squares everyone up.
+///
+/// This greedily matches the largest debtor with the largest creditor until all debts are settled.
+fn minimise_transfers(balances: HashMap<u64, i64>) -> Vec<(u64, u64, i64)> {
+    let mut creditors: Vec<(u64, i64)> = balances
+        .iter()
+        .filter(|(_, balance)| **balance > 0)
+        .map(|(id, balance)| (*id, *balance))
+        .collect();
+    let mut debtors: Vec<(u64, i64)> = balances
+        .iter()
+        .filter(|(_, balance)| **balance < 0)
+        .map(|(id, balance)| (*id, -*balance))
+        .collect();
+
+    let mut transfers = Vec::new();
+
+    loop {
+        // sort so the largest amounts are at the end, ready to be popped
+        creditors.sort_by_key(|(id, amount)| (*amount, *id));
+        debtors.sort_by_key(|(id, amount)| (*amount, *id));
+
+        let (creditor, owed) = match creditors.pop() {
+            Some(c) => c,
+            None => break,
+        };
+        let (debtor, owes) = match debtors.pop() {
+            Some(d) => d,
+            None => break,
+        };
+
+        let amount = owed.min(owes);
+        transfers.push((debtor, creditor, amount));
+
+        if owed > amount {
+            creditors.push((creditor, owed - amount));
+        }
+        if owes > amount {
+            debtors.push((debtor, owes - amount));
+        }
+    }
+
+    transfers
+}
+
+/// work out the fewest payments needed to square up every outstanding bill in the guild
+pub(super) async fn settle_up(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+) -> Result<CommandResponse, CommandResponse> {
+    let bills = match app_state
+        .get_bills_for_guild(interaction.guild_id.map(|g| g.0.into()))
+        .await
+    {
+        Ok(bills) => bills,
+        Err(e) => {
+            return Err(CommandResponse::InternalFailure(format!(
+                "error communicating with database: {}",
+                e
+            )));
+        }
+    };
+
+    let currency = guild_currency(interaction.guild_id, app_state).await?;
+    let branding = guild_branding(interaction.guild_id, app_state).await?;
+    let transfers = minimise_transfers(net_balances(&bills));
+
+    let mut embed = branding
+        .embed()
+        .title("Settle up")
+        .color(EmbedColor::Green as u32);
+
+    if transfers.is_empty() {
+        embed = embed.description("Everyone is square, there is nothing to settle!");
+    } else {
+        embed = embed
+            .description("Make these payments to settle every outstanding bill:")
+            .color(EmbedColor::Orange as u32);
+        let flatmates = app_state.all_flatmates();
+        for (from, to, amount) in transfers {
+            embed = embed.field(
+                format!(
+                    "{} → {}",
+                    display_name_for(&flatmates, from),
+                    display_name_for(&flatmates, to)
+                ),
+                format_money(amount, currency),
+                false,
+            );
+        }
+    }
+
+    Ok(CommandResponse::ComplexSuccess(
+        CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().embed(embed)),
+    ))
+}
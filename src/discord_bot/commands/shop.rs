@@ -4,7 +4,7 @@ use log::error;
 use serenity::{
     all::{
         AutocompleteOption, ChannelId, CommandInteraction, CommandOptionType, ComponentInteraction,
-        GuildId, Message, ResolvedValue,
+        GuildId, Message, ResolvedOption, ResolvedValue,
     },
     async_trait,
     builder::{
@@ -19,14 +19,16 @@ use serenity::{
 use crate::{
     discord_bot::{
         common::embed::EmbedColor,
-        database::shopping::{NewShoppingListItem, SerenityShoppingDatabase},
+        database::shopping::{
+            NewShoppingListItem, SerenityShoppingDatabase, ShoppingListItemModel,
+        },
     },
-    state::AppState,
+    state::{AppState, Flatmate},
 };
 
 use super::{
-    command::{AutocompleteCommand, Command, InteractionCommand},
-    util::CommandResponse,
+    command::{Command, InteractionCommand},
+    util::{display_name_for, guild_branding, Branding, CommandResponse},
 };
 
 const EXTRA_STORE_NAMES: &[&str] = &[
@@ -254,7 +256,7 @@ async fn create_loading_message<'b, A: Interactable>(
 }
 
 async fn push_list_item_to_database<'b, A: Interactable>(
-    shop: Shop<'b>,
+    shop: ShopItem<'b>,
     state: &'b AppState,
     interaction: &'b A,
     ctx: &'b Context,
@@ -276,6 +278,7 @@ async fn push_list_item_to_database<'b, A: Interactable>(
                 quantity: shop.quantity,
                 store: shop.store,
                 notes: shop.notes,
+                assigned_to: shop.assigned_to,
             },
         )
         .await
@@ -298,17 +301,24 @@ async fn push_list_item_to_database<'b, A: Interactable>(
 }
 
 async fn create_new_shopping<'b, B: Constructable>(
-    shop: &'b Shop<'b>,
+    shop: &'b ShopItem<'b>,
+    flatmates: &[Flatmate],
+    branding: &Branding,
 ) -> Result<B, CommandResponse> {
     Ok(B::default()
         .add_embed(
-            CreateEmbed::new()
+            branding
+                .embed()
                 // .title("Added to shopping list") //XXX: experiment
                 .description(format!(
-                    "Added x{} {}{} to the shopping list{}{}",
+                    "Added x{} {}{}{} to the shopping list{}{}",
                     shop.quantity,
                     shop.item,
                     if shop.personal { " (personal)" } else { "" },
+                    match shop.assigned_to {
+                        Some(flatmate) => format!(" for {}", display_name_for(flatmates, flatmate)),
+                        None => String::new(),
+                    },
                     if shop.store.is_some() {
                         format!(" from {}", shop.store.unwrap())
                     } else {
@@ -336,25 +346,103 @@ async fn create_new_shopping<'b, B: Constructable>(
         ])]))
 }
 
+/// the prefix of the custom id of the buttons on a shopping list, followed by the id of the item
+const LIST_BOUGHT_PREFIX: &str = "list-bought-";
+
+/// build an embed listing the provided items, with bought items struck through
+fn create_shopping_list_embed(
+    items: &[ShoppingListItemModel],
+    flatmates: &[Flatmate],
+    branding: &Branding,
+) -> CreateEmbed {
+    let description = if items.is_empty() {
+        String::from("The shopping list is empty")
+    } else {
+        let mut description = String::new();
+        for item in items {
+            let line = format!(
+                "x{} {}{}{}{} - <@{}>",
+                item.quantity,
+                item.item,
+                if item.personal { " (personal)" } else { "" },
+                match item.assigned_to {
+                    Some(flatmate) =>
+                        format!(" for {}", display_name_for(flatmates, flatmate as u64)),
+                    None => String::new(),
+                },
+                match &item.store {
+                    Some(store) => format!(" from {}", store),
+                    None => String::new(),
+                },
+                item.user_id,
+            );
+            let line = if item.bought {
+                format!("~~{}~~\n", line)
+            } else {
+                format!("{}\n", line)
+            };
+
+            // discord limits embed descriptions to 4096 characters
+            if description.len() + line.len() > 4000 {
+                description.push_str("...");
+                break;
+            }
+            description.push_str(&line);
+        }
+        description
+    };
+
+    branding
+        .embed()
+        .title("Shopping list")
+        .description(description)
+        .color(EmbedColor::Red as u32)
+}
+
+/// build a "bought" button for each item still to be bought, limited to the 25 buttons discord
+/// allows on a single message
+fn create_shopping_list_components(items: &[ShoppingListItemModel]) -> Vec<CreateActionRow> {
+    let buttons: Vec<CreateButton> = items
+        .iter()
+        .filter(|item| !item.bought)
+        .take(25)
+        .map(|item| {
+            CreateButton::new(format!("{}{}", LIST_BOUGHT_PREFIX, item.id))
+                .style(serenity::all::ButtonStyle::Success)
+                .label(
+                    format!("Bought {}", item.item)
+                        .chars()
+                        .take(80)
+                        .collect::<String>(),
+                )
+        })
+        .collect();
+
+    buttons
+        .chunks(5)
+        .map(|row| CreateActionRow::Buttons(row.to_vec()))
+        .collect()
+}
+
 #[derive(Debug)]
-pub struct Shop<'a> {
+pub struct ShopItem<'a> {
     item: &'a str,
     personal: bool,
     quantity: i64,
     store: Option<&'a str>,
     notes: Option<&'a str>,
+    /// the flatmate the item is for, or none if it is shared
+    assigned_to: Option<u64>,
 }
 
-impl<'a> TryFrom<&'a CommandInteraction> for Shop<'a> {
-    type Error = String;
-    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
-        let options = interaction.data.options();
-
+impl<'a> ShopItem<'a> {
+    fn from_options(options: Vec<ResolvedOption<'a>>) -> Result<Self, String> {
         let mut item: Option<&str> = None;
         let mut personal: Option<bool> = None;
         let mut quantity: Option<i64> = None;
         let mut store: Option<&str> = None;
         let mut notes: Option<&str> = None;
+        let mut assigned_to: Option<u64> = None;
 
         for option in options.into_iter() {
             match (option.name, option.value) {
@@ -363,6 +451,12 @@ impl<'a> TryFrom<&'a CommandInteraction> for Shop<'a> {
                 ("quantity", ResolvedValue::Integer(val)) => quantity = Some(val),
                 ("store", ResolvedValue::String(val)) => store = Some(val),
                 ("notes", ResolvedValue::String(val)) => notes = Some(val),
+                ("for", ResolvedValue::String(val)) => {
+                    assigned_to = Some(
+                        val.parse()
+                            .map_err(|_| String::from("for must be one of the flatmates"))?,
+                    )
+                }
                 (opt, val) => {
                     panic!("unexpected option name: `{}` and value `{:?}`", opt, val)
                 }
@@ -376,16 +470,63 @@ impl<'a> TryFrom<&'a CommandInteraction> for Shop<'a> {
         let personal = personal.unwrap();
         let quantity = quantity.unwrap_or(1);
 
-        Ok(Shop {
+        Ok(ShopItem {
             item,
             personal,
             quantity,
             store,
             notes,
+            assigned_to,
         })
     }
 }
 
+#[derive(Debug)]
+pub enum Shop<'a> {
+    /// add an item to the shopping list
+    Add(ShopItem<'a>),
+    /// show every item still to be bought, optionally including those already bought
+    List { show_purchased: bool },
+    /// remove an item from the shopping list, by id
+    Remove(u64),
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for Shop<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let options = interaction.data.options();
+
+        match options.into_iter().next() {
+            Some(ResolvedOption {
+                name: "add",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => Ok(Shop::Add(ShopItem::from_options(options)?)),
+            Some(ResolvedOption {
+                name: "list",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => Ok(Shop::List {
+                show_purchased: options.iter().any(|o| {
+                    o.name == "show_purchased" && matches!(o.value, ResolvedValue::Boolean(true))
+                }),
+            }),
+            Some(ResolvedOption {
+                name: "remove",
+                value: ResolvedValue::SubCommand(options),
+                ..
+            }) => match options.first().map(|o| &o.value) {
+                Some(ResolvedValue::String(id)) => id
+                    .parse()
+                    .map(Shop::Remove)
+                    .map_err(|_| String::from("item must be selected from the list")),
+                _ => Err(String::from("item is required")),
+            },
+            _ => Err(String::from("unknown subcommand")),
+        }
+    }
+}
+
 #[async_trait]
 impl<'a> Command<'a> for Shop<'a> {
     fn name() -> &'static str {
@@ -393,11 +534,16 @@ impl<'a> Command<'a> for Shop<'a> {
     }
 
     fn description() -> &'static str {
-        "add an item to the shopping list"
+        "manage the shopping list"
     }
 
-    fn get_application_command_options(cmd: CreateCommand) -> CreateCommand {
-        cmd.add_option(
+    fn get_application_command_options(cmd: CreateCommand, app_state: &AppState) -> CreateCommand {
+        let add = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "add",
+            "add an item to the shopping list",
+        )
+        .add_sub_option(
             CreateCommandOption::new(
                 CommandOptionType::String,
                 "item",
@@ -408,7 +554,7 @@ impl<'a> Command<'a> for Shop<'a> {
             .max_length(200)
             .to_owned(),
         )
-        .add_option(
+        .add_sub_option(
             CreateCommandOption::new(
                 CommandOptionType::Boolean,
                 "personal",
@@ -416,7 +562,7 @@ impl<'a> Command<'a> for Shop<'a> {
             )
             .required(true),
         )
-        .add_option({
+        .add_sub_option({
             let mut cmd = CreateCommandOption::new(
                 CommandOptionType::Integer,
                 "quantity",
@@ -429,7 +575,7 @@ impl<'a> Command<'a> for Shop<'a> {
             }
             cmd
         })
-        .add_option(
+        .add_sub_option(
             CreateCommandOption::new(
                 CommandOptionType::String,
                 "store",
@@ -440,7 +586,7 @@ impl<'a> Command<'a> for Shop<'a> {
             .max_length(100)
             .to_owned(),
         )
-        .add_option(
+        .add_sub_option(
             CreateCommandOption::new(
                 CommandOptionType::String,
                 "notes",
@@ -450,6 +596,51 @@ impl<'a> Command<'a> for Shop<'a> {
             .max_length(100)
             .to_owned(),
         )
+        .add_sub_option({
+            let mut option = CreateCommandOption::new(
+                CommandOptionType::String,
+                "for",
+                "The flatmate the item is for, leave empty if it is shared",
+            )
+            .required(false);
+
+            for flatmate in app_state.flatmates().iter() {
+                option = option
+                    .add_string_choice(&flatmate.display_name, flatmate.discord_id.to_string());
+            }
+            option
+        });
+
+        let list = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show every item still to be bought",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "show_purchased",
+                "include recently bought items",
+            )
+            .required(false),
+        );
+
+        let remove = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "remove",
+            "remove an item from the shopping list",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "item",
+                "The item to remove from the shopping list",
+            )
+            .required(true)
+            .set_autocomplete(true),
+        );
+
+        cmd.add_option(add).add_option(list).add_option(remove)
     }
 
     async fn handle_application_command<'b>(
@@ -458,22 +649,111 @@ impl<'a> Command<'a> for Shop<'a> {
         state: &'b AppState,
         ctx: &'b Context,
     ) -> Result<CommandResponse, CommandResponse> {
-        let loading_message = create_loading_message(interaction, ctx).await?;
-        let resp = create_new_shopping(&self).await?;
+        let branding = guild_branding(interaction.guild_id, state).await?;
+        match self {
+            Shop::Add(item) => {
+                let loading_message = create_loading_message(interaction, ctx).await?;
+                let resp = create_new_shopping(&item, &state.all_flatmates(), &branding).await?;
+
+                if let Err(e) = interaction.create_followup(&ctx, resp).await {
+                    error!("error creating followup: {}", e);
+                    return Err(CommandResponse::NoResponse);
+                }
 
-        if let Err(e) = interaction.create_followup(&ctx, resp).await {
-            error!("error creating followup: {}", e);
-            return Err(CommandResponse::NoResponse);
-        }
+                push_list_item_to_database(item, state, interaction, ctx, loading_message).await?;
 
-        push_list_item_to_database(self, state, interaction, ctx, loading_message).await?;
+                Ok(CommandResponse::NoResponse)
+            }
+            Shop::List { show_purchased } => {
+                let items = if show_purchased {
+                    state.get_recent_shopping_list_items(50).await
+                } else {
+                    state.get_unbought_shopping_list_items().await
+                };
+                let items = match items {
+                    Ok(items) => items,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
 
-        Ok(CommandResponse::NoResponse)
+                if let Err(e) = interaction
+                    .create_response(
+                        &ctx,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .embed(create_shopping_list_embed(
+                                    &items,
+                                    &state.all_flatmates(),
+                                    &branding,
+                                ))
+                                .components(create_shopping_list_components(&items)),
+                        ),
+                    )
+                    .await
+                {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with discord: {}",
+                        e
+                    )));
+                }
+
+                Ok(CommandResponse::NoResponse)
+            }
+            Shop::Remove(id) => {
+                let item = match state.get_shopping_list_item_by_id(id).await {
+                    Ok(Some(item)) if !item.bought => item,
+                    Ok(_) => {
+                        return Err(CommandResponse::BasicFailure(String::from(
+                            "that item is not on the shopping list",
+                        )));
+                    }
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if let Err(e) = state.set_shopping_list_item_bought_by_id(id, true).await {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+
+                if let Err(e) = interaction
+                    .create_response(
+                        &ctx,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new().embed(
+                                branding
+                                    .embed()
+                                    .description(format!(
+                                        "Removed x{} {} from the shopping list",
+                                        item.quantity, item.item
+                                    ))
+                                    .color(EmbedColor::Orange as u32),
+                            ),
+                        ),
+                    )
+                    .await
+                {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with discord: {}",
+                        e
+                    )));
+                }
+
+                Ok(CommandResponse::NoResponse)
+            }
+        }
     }
-}
 
-#[async_trait]
-impl<'a> AutocompleteCommand<'a> for Shop<'a> {
     async fn autocomplete<'c>(
         command: &'c CommandInteraction,
         autocomplete: &'c AutocompleteOption,
@@ -483,6 +763,35 @@ impl<'a> AutocompleteCommand<'a> for Shop<'a> {
         let mut response = CreateAutocompleteResponse::new();
         let user_id: u64 = command.user.id.into();
 
+        // removing an item suggests the items still on the list, rather than previous items
+        if command.data.options.first().map(|o| o.name.as_str()) == Some("remove") {
+            let items = match app_state.get_unbought_shopping_list_items().await {
+                Ok(items) => items,
+                Err(e) => {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+            };
+
+            let search_phrase = autocomplete.value.to_lowercase();
+            let choices: Vec<AutocompleteChoice> = items
+                .into_iter()
+                .filter(|item| item.item.to_lowercase().contains(&search_phrase))
+                .take(25)
+                .map(|item| AutocompleteChoice {
+                    name: format!("x{} {}", item.quantity, item.item)
+                        .chars()
+                        .take(100)
+                        .collect(),
+                    value: serde_json::Value::String(item.id.to_string()),
+                })
+                .collect();
+
+            return Ok(response.set_choices(choices));
+        }
+
         let mut items = match app_state
             .get_recent_shopping_list_items_by_user(user_id, 50)
             .await
@@ -607,6 +916,10 @@ impl<'a> InteractionCommand<'a> for Shop<'a> {
         app_state: &'b AppState,
         _: &'b Context,
     ) -> bool {
+        if interaction.data.custom_id.starts_with(LIST_BOUGHT_PREFIX) {
+            return true;
+        }
+
         let msg_id: u64 = interaction.message.id.into();
         match app_state.get_shopping_list_item_by_message_id(msg_id).await {
             Ok(Some(_)) => true,
@@ -625,8 +938,60 @@ impl<'a> InteractionCommand<'a> for Shop<'a> {
     ) -> Result<CommandResponse, CommandResponse> {
         let msg_id: u64 = interaction.message.id.into();
         let user_id: u64 = interaction.user.id.into();
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
 
         match interaction.data.custom_id.as_ref() {
+            custom_id if custom_id.starts_with(LIST_BOUGHT_PREFIX) => {
+                let id: u64 = match custom_id[LIST_BOUGHT_PREFIX.len()..].parse() {
+                    Ok(id) => id,
+                    Err(_) => {
+                        return Err(CommandResponse::InternalFailure(
+                            "Invalid interaction".to_string(),
+                        ));
+                    }
+                };
+
+                if let Err(e) = app_state
+                    .set_shopping_list_item_bought_by_id(id, true)
+                    .await
+                {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with database: {}",
+                        e
+                    )));
+                }
+
+                let items = match app_state.get_unbought_shopping_list_items().await {
+                    Ok(items) => items,
+                    Err(e) => {
+                        return Err(CommandResponse::InternalFailure(format!(
+                            "error communicating with database: {}",
+                            e
+                        )));
+                    }
+                };
+
+                if let Err(e) = interaction
+                    .create_response(
+                        &ctx,
+                        CreateInteractionResponse::UpdateMessage(
+                            CreateInteractionResponseMessage::new()
+                                .embed(create_shopping_list_embed(
+                                    &items,
+                                    &app_state.all_flatmates(),
+                                    &branding,
+                                ))
+                                .components(create_shopping_list_components(&items)),
+                        ),
+                    )
+                    .await
+                {
+                    return Err(CommandResponse::InternalFailure(format!(
+                        "error communicating with discord: {}",
+                        e
+                    )));
+                }
+            }
             "bought" => {
                 if let Err(e) = app_state
                     .set_shopping_list_item_bought(user_id, msg_id, true)
@@ -654,7 +1019,8 @@ impl<'a> InteractionCommand<'a> for Shop<'a> {
                         &ctx,
                         EditMessage::new()
                             .embed(
-                                CreateEmbed::new()
+                                branding
+                                    .embed()
                                     //XXX: title?
                                     .description(format!(
                                         "(BOUGHT) ~~{}~~",
@@ -713,7 +1079,8 @@ impl<'a> InteractionCommand<'a> for Shop<'a> {
                         &ctx,
                         EditMessage::new()
                             .embed(
-                                CreateEmbed::new()
+                                branding
+                                    .embed()
                                     .color(EmbedColor::Orange as u32)
                                     .description(format!(
                                         "(REMOVED) {}",
@@ -760,14 +1127,16 @@ impl<'a> InteractionCommand<'a> for Shop<'a> {
                 };
 
                 create_loading_message(interaction, ctx).await?;
-                let shop = Shop {
+                let shop = ShopItem {
                     item: item.item.as_ref(),
                     personal: item.personal,
                     quantity: item.quantity,
                     store: item.store.as_deref(),
                     notes: item.notes.as_deref(),
+                    assigned_to: item.assigned_to.map(|a| a as u64),
                 };
-                let resp = create_new_shopping(&shop).await?;
+                let resp =
+                    create_new_shopping(&shop, &app_state.all_flatmates(), &branding).await?;
 
                 let msg_id = match interaction.create_followup(&ctx, resp).await {
                     Ok(m) => m,
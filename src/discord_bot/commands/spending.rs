@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Local, NaiveDate};
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, ResolvedValue},
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::bills::SerenityBillDatabase,
+    },
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    pay::{add_category_choices, UNCATEGORIZED},
+    util::{guild_branding, guild_currency, CommandResponse},
+};
+
+/// how far back the spending report looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl Period {
+    /// the first day included in the report, or `None` to include every bill
+    fn start(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Self::Week => Some(today - Duration::days(7)),
+            Self::Month => Some(today - Duration::days(30)),
+            Self::Year => Some(today - Duration::days(365)),
+            Self::All => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Week => "in the last week",
+            Self::Month => "in the last 30 days",
+            Self::Year => "in the last year",
+            Self::All => "since the first bill",
+        }
+    }
+}
+
+/// the bills filed under a category, and what they added up to in cents
+#[derive(Debug, Default)]
+struct CategoryTotal {
+    total: i64,
+    bills: usize,
+    /// the purpose and total of the biggest bill
+    biggest: Option<(String, i64)>,
+}
+
+pub struct SpendingCommand<'a> {
+    category: Option<&'a str>,
+    period: Period,
+}
+
+impl<'a> TryFrom<&'a CommandInteraction> for SpendingCommand<'a> {
+    type Error = String;
+    fn try_from(interaction: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        let mut command = SpendingCommand {
+            category: None,
+            period: Period::Month,
+        };
+
+        for option in interaction.data.options() {
+            match (option.name, option.value) {
+                ("category", ResolvedValue::String(val)) => command.category = Some(val),
+                ("period", ResolvedValue::String(val)) => {
+                    command.period = match val {
+                        "week" => Period::Week,
+                        "year" => Period::Year,
+                        "all" => Period::All,
+                        _ => Period::Month,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+/// capitalise a category so it reads as a heading, e.g. `groceries` becomes `Groceries`
+fn heading(category: &str) -> String {
+    let mut chars = category.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for SpendingCommand<'a> {
+    fn name() -> &'static str {
+        "spending"
+    }
+
+    fn description() -> &'static str {
+        "Show how much the flat has spent on each category of bill"
+    }
+
+    fn get_application_command_options(cmd: CreateCommand, _: &AppState) -> CreateCommand {
+        cmd.add_option(
+            add_category_choices(CreateCommandOption::new(
+                CommandOptionType::String,
+                "category",
+                "Only show the spending on this category",
+            ))
+            .add_string_choice("Uncategorized", UNCATEGORIZED)
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "period",
+                "How far back to look, defaults to the last 30 days",
+            )
+            .required(false)
+            .add_string_choice("Week", "week")
+            .add_string_choice("Month", "month")
+            .add_string_choice("Year", "year")
+            .add_string_choice("All time", "all"),
+        )
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        _: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let bills = match app_state
+            .get_bills_for_guild(interaction.guild_id.map(|g| g.0.into()))
+            .await
+        {
+            Ok(bills) => bills,
+            Err(e) => {
+                return Err(CommandResponse::InternalFailure(format!(
+                    "error communicating with database: {}",
+                    e
+                )));
+            }
+        };
+
+        let start = self.period.start(Local::now().date_naive());
+        let mut totals: HashMap<&str, CategoryTotal> = HashMap::new();
+        for (bill, _) in bills
+            .iter()
+            .filter(|(bill, _)| start.map_or(true, |start| bill.created_at.date() >= start))
+            .filter(|(bill, _)| self.category.map_or(true, |c| bill.category == c))
+        {
+            let entry = totals.entry(bill.category.as_str()).or_default();
+            entry.total += bill.total;
+            entry.bills += 1;
+            if entry
+                .biggest
+                .as_ref()
+                .map_or(true, |(_, b)| bill.total > *b)
+            {
+                entry.biggest = Some((bill.purpose.clone(), bill.total));
+            }
+        }
+
+        if totals.is_empty() {
+            return Err(CommandResponse::BasicFailure(match self.category {
+                Some(category) => {
+                    format!("nothing was spent on {} {}", category, self.period.label())
+                }
+                None => format!("there are no bills {}", self.period.label()),
+            }));
+        }
+
+        let currency = guild_currency(interaction.guild_id, app_state).await?;
+        let branding = guild_branding(interaction.guild_id, app_state).await?;
+
+        // the biggest categories are shown first, an embed only fits 25 of them
+        let overall: i64 = totals.values().map(|t| t.total).sum();
+        let mut totals: Vec<(&str, CategoryTotal)> = totals.into_iter().collect();
+        totals.sort_by(|(a, at), (b, bt)| bt.total.cmp(&at.total).then(a.cmp(b)));
+
+        let title = match self.category {
+            Some(category) => format!("Spending on {} {}", category, self.period.label()),
+            None => format!("Spending {}", self.period.label()),
+        };
+        let mut embed = branding
+            .embed()
+            .title(title)
+            .description(format!("{} in total", format_money(overall, currency)))
+            .color(EmbedColor::Green as u32);
+
+        for (category, total) in totals.into_iter().take(25) {
+            let mut summary = format!(
+                "{} over {} bill{}",
+                format_money(total.total, currency),
+                total.bills,
+                if total.bills == 1 { "" } else { "s" }
+            );
+            if self.category.is_none() && overall > 0 {
+                summary.push_str(&format!(", {}%", total.total * 100 / overall));
+            }
+            if let Some((purpose, biggest)) = total.biggest {
+                summary.push_str(&format!(
+                    "\nbiggest was {} for {}",
+                    purpose,
+                    format_money(biggest, currency)
+                ));
+            }
+            embed = embed.field(heading(category), summary, false);
+        }
+
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(embed),
+            ),
+        ))
+    }
+}
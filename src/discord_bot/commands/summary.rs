@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use log::{error, info};
+use serenity::{
+    all::{ChannelId, GuildId},
+    builder::CreateMessage,
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::format_money, embed::EmbedColor},
+        database::{bills::SerenityBillDatabase, guild_config::SerenityGuildConfigDatabase},
+    },
+    state::{AppState, CONFIG},
+};
+
+use super::{
+    balance::net_balances,
+    util::{display_name_for, Branding},
+};
+
+/// the date the summary was most recently due to be posted, on `day` once it is past `hour`
+fn last_scheduled(now: NaiveDateTime, day: Weekday, hour: u32) -> NaiveDate {
+    let today = now.date();
+    let days_since = (today.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+    let scheduled = today - Duration::days(days_since as i64);
+
+    if scheduled == today && now.hour() < hour {
+        scheduled - Duration::weeks(1)
+    } else {
+        scheduled
+    }
+}
+
+/// post a summary of the last week of bills in the guild: the total billed, each flatmate's
+/// share, the biggest bill, and who still owes money. The summary is only posted once a week, so
+/// this is safe to call repeatedly
+pub async fn post_weekly_summary(guild: GuildId, app_state: &AppState, ctx: &Context) {
+    let channel = match CONFIG.summary_channel {
+        Some(channel) => ChannelId::new(channel),
+        None => return,
+    };
+
+    let day: Weekday = match CONFIG.summary_day.parse() {
+        Ok(day) => day,
+        Err(_) => {
+            error!("{} is not a day of the week", CONFIG.summary_day);
+            return;
+        }
+    };
+
+    let now = Local::now().naive_local();
+    let scheduled = last_scheduled(now, day, CONFIG.summary_hour);
+
+    let (posted, currency, branding) = match app_state.get_guild_config(guild.0.into()).await {
+        Ok(config) => (
+            config.as_ref().and_then(|config| config.summary_posted),
+            config
+                .as_ref()
+                .and_then(|config| config.currency.parse().ok())
+                .unwrap_or_default(),
+            Branding::from_config(config.as_ref()),
+        ),
+        Err(e) => {
+            error!("unable to load config for guild {}: {}", guild, e);
+            return;
+        }
+    };
+    if matches!(posted, Some(posted) if posted >= scheduled) {
+        return;
+    }
+
+    let bills = match app_state.get_bills_for_guild(Some(guild.0.into())).await {
+        Ok(bills) => bills,
+        Err(e) => {
+            error!("unable to load bills for guild {}: {}", guild, e);
+            return;
+        }
+    };
+
+    let since = now - Duration::weeks(1);
+    let this_week: Vec<_> = bills
+        .iter()
+        .filter(|(bill, _)| bill.created_at >= since)
+        .collect();
+    let payments = bills
+        .iter()
+        .flat_map(|(_, shares)| shares.iter())
+        .filter(|share| matches!(share.paid_at, Some(paid_at) if paid_at >= since))
+        .count();
+
+    if this_week.is_empty() && payments == 0 {
+        info!("no bills in guild {} this week, skipping summary", guild);
+    } else {
+        let flatmates = app_state.all_flatmates();
+
+        let mut spend: HashMap<u64, i64> = HashMap::new();
+        for share in this_week.iter().flat_map(|(_, shares)| shares.iter()) {
+            *spend.entry(share.user_id as u64).or_default() += share.amount;
+        }
+        let mut spend: Vec<(u64, i64)> = spend.into_iter().collect();
+        spend.sort_by_key(|(id, amount)| (-amount, *id));
+
+        let mut owing: Vec<(u64, i64)> = net_balances(&bills)
+            .into_iter()
+            .filter(|(_, balance)| *balance < 0)
+            .collect();
+        owing.sort_by_key(|(id, balance)| (*balance, *id));
+
+        let total: i64 = this_week.iter().map(|(bill, _)| bill.total).sum();
+        let mut embed = branding
+            .embed()
+            .title(format!(
+                "Spending for the week of {}",
+                since.format("%-d %B")
+            ))
+            .description(format!(
+                "{} bills, and {} payments this week",
+                this_week.len(),
+                payments
+            ))
+            .field("Total billed", format_money(total, currency), true)
+            .color(EmbedColor::Green as u32);
+
+        if let Some((bill, _)) = this_week.iter().max_by_key(|(bill, _)| bill.total) {
+            embed = embed.field(
+                "Biggest bill",
+                format!(
+                    "**{}** - {}",
+                    bill.purpose,
+                    format_money(bill.total, currency)
+                ),
+                true,
+            );
+        }
+
+        if !spend.is_empty() {
+            embed = embed.field(
+                "Spend per flatmate",
+                spend
+                    .iter()
+                    .map(|(id, amount)| {
+                        format!(
+                            "{} - {}",
+                            display_name_for(&flatmates, *id),
+                            format_money(*amount, currency)
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                false,
+            );
+        }
+
+        embed = embed.field(
+            "Still owing",
+            if owing.is_empty() {
+                String::from("Everyone is square!")
+            } else {
+                owing
+                    .iter()
+                    .map(|(id, balance)| {
+                        format!(
+                            "{} - {}",
+                            display_name_for(&flatmates, *id),
+                            format_money(-balance, currency)
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            },
+            false,
+        );
+
+        if let Err(e) = channel
+            .send_message(ctx, CreateMessage::new().embed(embed))
+            .await
+        {
+            error!("unable to post spending summary in guild {}: {}", guild, e);
+            return;
+        }
+    }
+
+    // weeks without any activity are also marked, so they aren't checked again
+    if let Err(e) = app_state
+        .set_summary_posted(guild.0.into(), now.date())
+        .await
+    {
+        error!(
+            "unable to record spending summary for guild {}: {}",
+            guild, e
+        );
+    }
+}
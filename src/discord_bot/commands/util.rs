@@ -1,7 +1,422 @@
 //! Various utilities to assist with writing application commands for the DIANA bot
 
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
-use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use rand::{seq::SliceRandom, Rng};
+use serenity::{
+    all::{ButtonStyle, CommandInteraction, ComponentInteraction, GuildId, RoleId},
+    builder::{
+        CreateActionRow, CreateButton, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage,
+    },
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::{
+        common::{currency::Currency, locale::Locale},
+        database::guild_config::{GuildConfigModel, SerenityGuildConfigDatabase},
+    },
+    state::{AppState, Flatmate, CONFIG},
+};
+
+/// load the currency a guild shows amounts in, falling back to the default outside of a guild or
+/// if the guild hasn't chosen one
+pub async fn guild_currency(
+    guild_id: Option<GuildId>,
+    app_state: &AppState,
+) -> Result<Currency, CommandResponse> {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(Currency::default()),
+    };
+
+    match app_state.get_guild_config(guild_id.0.into()).await {
+        Ok(config) => Ok(config
+            .and_then(|config| config.currency.parse().ok())
+            .unwrap_or_default()),
+        Err(e) => Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        ))),
+    }
+}
+
+/// load the locale a guild's responses are written in, falling back to english outside of a guild
+/// or if the guild hasn't chosen one
+pub async fn guild_locale(
+    guild_id: Option<GuildId>,
+    app_state: &AppState,
+) -> Result<Locale, CommandResponse> {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(Locale::default()),
+    };
+
+    match app_state.get_guild_config(guild_id.0.into()).await {
+        Ok(config) => Ok(config
+            .and_then(|config| config.locale.parse().ok())
+            .unwrap_or_default()),
+        Err(e) => Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        ))),
+    }
+}
+
+/// how a guild brands the bot's embeds, any part which is unset is left out so embeds keep the
+/// bot's plain styling
+#[derive(Debug, Clone, Default)]
+pub struct Branding {
+    /// the name shown at the top of every embed
+    pub author: Option<String>,
+    /// the url of the image shown in the corner of every embed
+    pub thumbnail: Option<String>,
+    /// the text shown at the bottom of every embed
+    pub footer: Option<String>,
+}
+
+impl Branding {
+    /// the branding set in a guild's config
+    pub fn from_config(config: Option<&GuildConfigModel>) -> Self {
+        match config {
+            Some(config) => Self {
+                author: config.branding_author.clone(),
+                thumbnail: config.branding_thumbnail.clone(),
+                footer: config.branding_footer.clone(),
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// start an embed carrying the guild's branding, every embed the bot sends in a guild is
+    /// built from this
+    pub fn embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::new();
+        if let Some(author) = &self.author {
+            embed = embed.author(CreateEmbedAuthor::new(author));
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            embed = embed.thumbnail(thumbnail);
+        }
+        if let Some(footer) = &self.footer {
+            embed = embed.footer(CreateEmbedFooter::new(footer));
+        }
+        embed
+    }
+
+    /// the footer of embeds which show a random phrase, which the guild's own footer replaces
+    pub fn flavour_footer<R: Rng + ?Sized>(&self, rng: &mut R) -> CreateEmbedFooter {
+        match &self.footer {
+            Some(footer) => CreateEmbedFooter::new(footer),
+            None => flavour_footer(rng),
+        }
+    }
+}
+
+/// load the branding of a guild, outside of a guild embeds are left plain
+pub async fn guild_branding(
+    guild_id: Option<GuildId>,
+    app_state: &AppState,
+) -> Result<Branding, CommandResponse> {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(Branding::default()),
+    };
+
+    match app_state.get_guild_config(guild_id.0.into()).await {
+        Ok(config) => Ok(Branding::from_config(config.as_ref())),
+        Err(e) => Err(CommandResponse::InternalFailure(format!(
+            "error communicating with database: {}",
+            e
+        ))),
+    }
+}
+
+/// get the display name of a flatmate from their discord id, falling back to mentioning the user
+/// if they are not a known flatmate
+pub fn display_name_for(flatmates: &[Flatmate], discord_id: u64) -> String {
+    match flatmates
+        .iter()
+        .find(|flatmate| flatmate.discord_id == discord_id)
+    {
+        Some(flatmate) => flatmate.display_name.clone(),
+        None => format!("<@{}>", discord_id),
+    }
+}
+
+/// the edit distance between two strings, ignoring case
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// find the flatmate a free-text name refers to, allowing for small typos such as "Willam".
+/// A name is only matched if it is within a third of its length of a flatmate's name or display
+/// name, and an error is returned if no flatmate, or more than one, is the closest match
+pub fn find_flatmate<'a>(flatmates: &'a [Flatmate], input: &str) -> Result<&'a Flatmate, String> {
+    let input = input.trim();
+    let threshold = (input.chars().count() / 3).max(1);
+
+    let mut closest: Vec<(&Flatmate, usize)> = flatmates
+        .iter()
+        .map(|flatmate| {
+            let distance =
+                levenshtein(input, &flatmate.name).min(levenshtein(input, &flatmate.display_name));
+            (flatmate, distance)
+        })
+        .filter(|(_, distance)| *distance <= threshold)
+        .collect();
+
+    let best = match closest.iter().map(|(_, distance)| *distance).min() {
+        Some(best) => best,
+        None => return Err(format!("{} is not a current flatmate", input)),
+    };
+    closest.retain(|(_, distance)| *distance == best);
+
+    match closest.as_slice() {
+        [(flatmate, _)] => Ok(flatmate),
+        matches => Err(format!(
+            "{} could be any of {}",
+            input,
+            matches
+                .iter()
+                .map(|(flatmate, _)| flatmate.display_name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// pick a random phrase from the config, taking the rng to use so the choice can be seeded
+pub fn random_phrase<R: Rng + ?Sized>(rng: &mut R) -> &'static str {
+    CONFIG
+        .phrases
+        .choose(rng)
+        .map(|phrase| phrase.as_str())
+        .unwrap_or_default()
+}
+
+/// pick a random thing the bot is powered by from the config, taking the rng to use so the
+/// choice can be seeded
+pub fn random_powered_by<R: Rng + ?Sized>(rng: &mut R) -> &'static str {
+    CONFIG
+        .powered_by
+        .choose(rng)
+        .map(|powered_by| powered_by.as_str())
+        .unwrap_or_default()
+}
+
+/// build an embed footer containing a random phrase and what the bot is powered by
+pub fn flavour_footer<R: Rng + ?Sized>(rng: &mut R) -> CreateEmbedFooter {
+    let phrase = random_phrase(rng);
+    CreateEmbedFooter::new(format!("{}\nPowered by {}", phrase, random_powered_by(rng)))
+}
+
+/// check if the user of an interaction is the guild owner, or holds the admin role. The guild's
+/// own admin role is used if it has set one, otherwise the role from the bot's config
+pub async fn is_admin(
+    interaction: &CommandInteraction,
+    app_state: &AppState,
+    ctx: &Context,
+) -> bool {
+    let is_owner = interaction
+        .guild_id
+        .and_then(|guild_id| ctx.cache.guild(guild_id).map(|g| g.owner_id))
+        .map(|owner_id| owner_id == interaction.user.id)
+        .unwrap_or(false);
+
+    let guild_role = match interaction.guild_id {
+        Some(guild_id) => match app_state.get_guild_config(guild_id.0.into()).await {
+            Ok(config) => config.and_then(|c| c.admin_role).map(|r| r as u64),
+            Err(e) => {
+                warn!("failed to load the admin role of guild {}: {}", guild_id, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let has_role = match (
+        guild_role.or(CONFIG.admin_role),
+        interaction.member.as_ref(),
+    ) {
+        (Some(role), Some(member)) => member.roles.contains(&RoleId::new(role)),
+        _ => false,
+    };
+
+    is_owner || has_role
+}
+
+/// the prefix of the custom id of the buttons used to change page, followed by the key of the
+/// pages and the page to show
+const PAGE_PREFIX: &str = "page-";
+/// how long the pages of a paginated embed are kept for, after which its buttons stop working
+const PAGE_TTL: Duration = Duration::from_secs(60 * 60);
+/// the longest description discord allows in an embed
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// a list split into pages which can be navigated with buttons
+struct Pages {
+    title: String,
+    color: u32,
+    branding: Branding,
+    pages: Vec<String>,
+    created_at: Instant,
+}
+
+lazy_static! {
+    /// the pages of every recent paginated embed, keyed by the interaction that created them
+    static ref PAGES: Mutex<HashMap<u64, Pages>> = Mutex::new(HashMap::new());
+}
+
+/// split lines into pages of at most `per_page` lines, starting a page early if it would be too
+/// long for an embed
+fn split_pages(items: &[String], per_page: usize) -> Vec<String> {
+    let mut pages = vec![];
+    let mut page = String::new();
+    let mut lines = 0;
+
+    for item in items {
+        if lines > 0 && (lines >= per_page || page.len() + item.len() + 1 > MAX_DESCRIPTION_LENGTH)
+        {
+            pages.push(std::mem::take(&mut page));
+            lines = 0;
+        }
+        if lines > 0 {
+            page.push('\n');
+        }
+        page.push_str(item);
+        lines += 1;
+    }
+
+    if !page.is_empty() || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+/// render one page of a paginated embed, with buttons to move between pages if there is more
+/// than one
+fn render_page(pages: &Pages, key: u64, page: usize) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let embed = pages
+        .branding
+        .embed()
+        .title(&pages.title)
+        .description(&pages.pages[page])
+        .color(pages.color);
+
+    if pages.pages.len() == 1 {
+        return (embed, vec![]);
+    }
+
+    let buttons = vec![
+        CreateButton::new(format!("{}{}-{}", PAGE_PREFIX, key, page.saturating_sub(1)))
+            .style(ButtonStyle::Secondary)
+            .label("◀")
+            .disabled(page == 0),
+        CreateButton::new(format!("{}{}-current", PAGE_PREFIX, key))
+            .style(ButtonStyle::Secondary)
+            .label(format!("{}/{}", page + 1, pages.pages.len()))
+            .disabled(true),
+        CreateButton::new(format!("{}{}-{}", PAGE_PREFIX, key, page + 1))
+            .style(ButtonStyle::Secondary)
+            .label("▶")
+            .disabled(page + 1 == pages.pages.len()),
+    ];
+
+    (embed, vec![CreateActionRow::Buttons(buttons)])
+}
+
+/// split a list of items into pages of at most `per_page` items, returning the first page and
+/// the buttons to navigate between them. `key` must be unique to this list, the id of the
+/// interaction being responded to is a good choice
+pub fn paginate(
+    key: u64,
+    title: &str,
+    color: u32,
+    branding: &Branding,
+    items: &[String],
+    per_page: usize,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let pages = Pages {
+        title: title.to_string(),
+        color,
+        branding: branding.clone(),
+        pages: split_pages(items, per_page.max(1)),
+        created_at: Instant::now(),
+    };
+    let first = render_page(&pages, key, 0);
+
+    // only lists with more than one page need to be kept for the buttons
+    if pages.pages.len() > 1 {
+        let mut stored = PAGES.lock().unwrap();
+        stored.retain(|_, pages| pages.created_at.elapsed() < PAGE_TTL);
+        stored.insert(key, pages);
+    }
+
+    first
+}
+
+/// check if a component interaction is a button on a paginated embed
+pub fn is_page_interaction(interaction: &ComponentInteraction) -> bool {
+    interaction.data.custom_id.starts_with(PAGE_PREFIX)
+}
+
+/// move a paginated embed to the page requested by the button that was pressed
+pub fn handle_page_interaction(
+    interaction: &ComponentInteraction,
+) -> Result<CommandResponse, CommandResponse> {
+    let target = interaction.data.custom_id[PAGE_PREFIX.len()..]
+        .split_once('-')
+        .and_then(|(key, page)| Some((key.parse::<u64>().ok()?, page.parse::<usize>().ok()?)));
+    let (key, page) = match target {
+        Some(target) => target,
+        None => {
+            return Err(CommandResponse::InternalFailure(String::from(
+                "Invalid page interaction",
+            )))
+        }
+    };
+
+    let stored = PAGES.lock().unwrap();
+    let pages = match stored.get(&key) {
+        Some(pages) if pages.created_at.elapsed() < PAGE_TTL => pages,
+        _ => {
+            return Err(CommandResponse::BasicFailure(String::from(
+                "this list has expired, run the command again to see it",
+            )))
+        }
+    };
+
+    let (embed, components) = render_page(pages, key, page.min(pages.pages.len() - 1));
+    Ok(CommandResponse::ComplexSuccess(
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+                .components(components),
+        ),
+    ))
+}
 
 #[derive(Debug, Clone, Copy)]
 #[allow(dead_code, clippy::missing_docs_in_private_items)]
@@ -12,17 +427,55 @@ pub enum FailureMessageKind {
     Debug,
 }
 
+/// identifies a single failed interaction, so the error shown to a user can be matched to the
+/// line written to the log
+#[derive(Debug, Clone)]
+pub struct ErrorReference {
+    /// a short random id, shown to the user as "error ref: a1b2c3"
+    pub reference: String,
+    pub guild_id: Option<u64>,
+    /// the command, or the custom id of the component, that failed
+    pub command: String,
+}
+
+impl ErrorReference {
+    pub fn new(guild_id: Option<u64>, command: impl Into<String>) -> Self {
+        Self {
+            reference: format!("{:06x}", rand::thread_rng().gen_range(0..0x1000000u32)),
+            guild_id,
+            command: command.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.guild_id {
+            Some(guild_id) => write!(
+                f,
+                "[ref {}] [guild {}] [{}]",
+                self.reference, guild_id, self.command
+            ),
+            None => write!(f, "[ref {}] [no guild] [{}]", self.reference, self.command),
+        }
+    }
+}
+
 /// a general purpose response type generated by the bot reacting to a slash command
 /// has both basic and complex success and failure states
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum CommandResponse {
-    /// a basic success, will return the contained string in a simple message to the user
+    /// a basic success, will return the contained string in a simple message only visible to
+    /// the user
     BasicSuccess(String),
+    /// a basic success, will return the contained string in a simple message visible to
+    /// everyone in the channel
+    PublicSuccess(String),
     /// a complex success, will return the contained interaction response exactly to the user
     ComplexSuccess(CreateInteractionResponse),
-    /// a basic failure, will return the contained string in a simple message to the user
-    /// and log the message to the console
+    /// a basic failure, will return the contained string in a simple message only visible to
+    /// the user and log the message to the console
     BasicFailure(String),
     /// a complex failure, will return the contained interaction response exactly to the user
     /// and log the message to the console with the provided log level
@@ -38,6 +491,11 @@ pub enum CommandResponse {
     /// but will instead log it to the console, and return a generic "internal error" resposne
     /// to the user
     InternalFailure(String),
+    /// a complex followup, used by deferred commands in place of ComplexSuccess
+    Followup(CreateInteractionResponseFollowup),
+    /// the interaction has already been deferred, so the contained response must be sent as a
+    /// followup rather than an initial response
+    Deferred(Box<CommandResponse>),
     NoResponse,
 }
 
@@ -48,6 +506,7 @@ impl CommandResponse {
             Self::BasicFailure(message) => Some(message),
             Self::ComplexFailure { log_message, .. } => Some(log_message),
             Self::InternalFailure(message) => Some(message),
+            Self::Deferred(response) => response.get_log_message(),
             _ => None,
         }
     }
@@ -57,19 +516,52 @@ impl CommandResponse {
         match self {
             Self::BasicFailure(_) => FailureMessageKind::Error,
             Self::ComplexFailure { kind, .. } => *kind,
+            Self::Deferred(response) => response.get_log_type(),
             _ => FailureMessageKind::Info,
         }
     }
 
-    /// writ ethe message to the log, if there is a loggable message
-    pub fn write_to_log(&self) {
+    /// writ ethe message to the log, if there is a loggable message, tagged with the reference,
+    /// guild, and command of the interaction that produced it
+    pub fn write_to_log(&self, reference: &ErrorReference) {
         if let Some(message) = self.get_log_message() {
             match self.get_log_type() {
-                FailureMessageKind::Error => error!("{}", message),
-                FailureMessageKind::Warn => warn!("{}", message),
-                FailureMessageKind::Info => info!("{}", message),
-                FailureMessageKind::Debug => debug!("{}", message),
+                FailureMessageKind::Error => error!("{} {}", reference, message),
+                FailureMessageKind::Warn => warn!("{} {}", reference, message),
+                FailureMessageKind::Info => info!("{} {}", reference, message),
+                FailureMessageKind::Debug => debug!("{} {}", reference, message),
+            }
+        }
+    }
+
+    /// add the reference of an interaction to any failure message shown to the user, so that
+    /// they can quote it when asking for help
+    pub fn with_reference(self, reference: &ErrorReference) -> Self {
+        match self {
+            Self::BasicFailure(message) => {
+                Self::BasicFailure(format!("{}\nerror ref: {}", message, reference.reference))
             }
+            Self::ComplexFailure {
+                response,
+                kind,
+                log_message,
+            } => Self::ComplexFailure {
+                response: format!("{}\nerror ref: {}", response, reference.reference),
+                kind,
+                log_message,
+            },
+            Self::InternalFailure(message) => Self::ComplexFailure {
+                response: format!(
+                    "An internal error occurred.\nerror ref: {}",
+                    reference.reference
+                ),
+                kind: FailureMessageKind::Error,
+                log_message: message,
+            },
+            Self::Deferred(response) => {
+                Self::Deferred(Box::new(response.with_reference(reference)))
+            }
+            response => response,
         }
     }
 
@@ -81,6 +573,9 @@ impl CommandResponse {
                     .ephemeral(true)
                     .content(message),
             )),
+            CommandResponse::PublicSuccess(message) => Some(CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::default().content(message),
+            )),
             CommandResponse::ComplexSuccess(message) => Some(message),
             CommandResponse::BasicFailure(message) => Some(CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::default()
@@ -99,6 +594,45 @@ impl CommandResponse {
                     .ephemeral(true)
                     .content("An internal error occurred."),
             )),
+            CommandResponse::Followup(_) | CommandResponse::Deferred(_) => {
+                warn!("a followup response can't be sent as an initial response");
+                None
+            }
+            CommandResponse::NoResponse => None,
+        }
+    }
+
+    /// generate a followup to be sent to the user once a deferred interaction has completed
+    pub fn generate_followup(self) -> Option<CreateInteractionResponseFollowup> {
+        match self {
+            // a deferred response inherits the visibility of the deferral, so these only apply
+            // to any followups after the first
+            CommandResponse::BasicSuccess(message) | CommandResponse::BasicFailure(message) => {
+                Some(
+                    CreateInteractionResponseFollowup::new()
+                        .ephemeral(true)
+                        .content(message),
+                )
+            }
+            CommandResponse::PublicSuccess(message) => {
+                Some(CreateInteractionResponseFollowup::new().content(message))
+            }
+            CommandResponse::ComplexFailure { response, .. } => Some(
+                CreateInteractionResponseFollowup::new()
+                    .ephemeral(true)
+                    .content(response),
+            ),
+            CommandResponse::InternalFailure(_) => Some(
+                CreateInteractionResponseFollowup::new()
+                    .ephemeral(true)
+                    .content("An internal error occurred."),
+            ),
+            CommandResponse::Followup(followup) => Some(followup),
+            CommandResponse::Deferred(response) => response.generate_followup(),
+            CommandResponse::ComplexSuccess(_) => {
+                warn!("an initial response can't be sent as a followup");
+                None
+            }
             CommandResponse::NoResponse => None,
         }
     }
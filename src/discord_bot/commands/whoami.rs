@@ -0,0 +1,114 @@
+use serenity::{
+    all::CommandInteraction,
+    async_trait,
+    builder::{CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage},
+    prelude::Context,
+};
+
+use crate::{
+    discord_bot::common::{currency::format_money, embed::EmbedColor},
+    state::AppState,
+};
+
+use super::{
+    command::Command,
+    util::{guild_branding, guild_currency, is_admin, CommandResponse},
+};
+
+pub struct WhoamiCommand;
+
+impl<'a> TryFrom<&'a CommandInteraction> for WhoamiCommand {
+    type Error = String;
+    fn try_from(_: &'a CommandInteraction) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+}
+
+#[async_trait]
+impl<'a> Command<'a> for WhoamiCommand {
+    fn name() -> &'static str {
+        "whoami"
+    }
+
+    fn description() -> &'static str {
+        "Shows whether you are recognised as a flatmate, and what the bot knows about you"
+    }
+
+    fn get_application_command_options(i: CreateCommand, _: &AppState) -> CreateCommand {
+        i
+    }
+
+    async fn handle_application_command<'b>(
+        self,
+        interaction: &'b CommandInteraction,
+        app_state: &'b AppState,
+        ctx: &'b Context,
+    ) -> Result<CommandResponse, CommandResponse> {
+        let discord_id: u64 = interaction.user.id.into();
+        let current = app_state
+            .flatmates()
+            .into_iter()
+            .find(|f| f.discord_id == discord_id);
+        let former = app_state
+            .all_flatmates()
+            .into_iter()
+            .find(|f| f.discord_id == discord_id);
+
+        let mut embed = guild_branding(interaction.guild_id, app_state)
+            .await?
+            .embed()
+            .title("Who am I?")
+            .field("Discord id", discord_id.to_string(), true)
+            .field(
+                "Admin",
+                if is_admin(interaction, app_state, ctx).await {
+                    "yes"
+                } else {
+                    "no"
+                },
+                true,
+            );
+
+        embed = match (current, former) {
+            (Some(flatmate), _) => {
+                let currency = guild_currency(interaction.guild_id, app_state).await?;
+                embed
+                    .description("You are a current flatmate.")
+                    .field("Display name", &flatmate.display_name, true)
+                    .field("Name in /pay", format!("`{}`", flatmate.name), true)
+                    .field(
+                        "Weekly income",
+                        match flatmate.weekly_income {
+                            Some(income) => format_money(income, currency),
+                            None => {
+                                String::from("not set, an admin can set it with /flatmate income")
+                            }
+                        },
+                        false,
+                    )
+                    .color(EmbedColor::Green as u32)
+            }
+            (None, Some(flatmate)) => embed
+                .description(format!(
+                    "You were a flatmate named {}, but have since been removed.",
+                    flatmate.display_name
+                ))
+                .color(EmbedColor::Orange as u32),
+            (None, None) => embed
+                .description(
+                    "You aren't a flatmate, so you won't be included in bills. An admin can add \
+                     you with /flatmate add.",
+                )
+                .color(EmbedColor::Red as u32),
+        };
+
+        // incomes are private, so only the caller sees this
+        Ok(CommandResponse::ComplexSuccess(
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .ephemeral(true),
+            ),
+        ))
+    }
+}
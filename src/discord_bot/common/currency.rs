@@ -0,0 +1,114 @@
+use std::str::FromStr;
+
+/// the currency amounts are shown in, set per guild. Amounts are always stored in cents, so
+/// changing the currency only changes how they are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Currency {
+    #[default]
+    Nzd,
+    Aud,
+    Usd,
+    Cad,
+    Gbp,
+    Eur,
+}
+
+impl Currency {
+    /// every supported currency, in the order they are suggested
+    pub const ALL: [Currency; 6] = [
+        Self::Nzd,
+        Self::Aud,
+        Self::Usd,
+        Self::Cad,
+        Self::Gbp,
+        Self::Eur,
+    ];
+
+    /// the value stored in the database for this currency, its ISO 4217 code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nzd => "NZD",
+            Self::Aud => "AUD",
+            Self::Usd => "USD",
+            Self::Cad => "CAD",
+            Self::Gbp => "GBP",
+            Self::Eur => "EUR",
+        }
+    }
+
+    /// the symbol written before an amount, the dollars other than the default are qualified so
+    /// they can't be mistaken for each other
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Nzd => "$",
+            Self::Aud => "A$",
+            Self::Usd => "US$",
+            Self::Cad => "C$",
+            Self::Gbp => "£",
+            Self::Eur => "€",
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|currency| currency.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown currency {}", s))
+    }
+}
+
+/// format an amount of cents in the provided currency, e.g. `$1,234.50` or `£12.00`
+pub fn format_money(cents: i64, currency: Currency) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+
+    // insert a thousands separator every three digits of the whole amount
+    let whole = (cents / 100).to_string();
+    let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    format!(
+        "{}{}{}.{:02}",
+        sign,
+        currency.symbol(),
+        grouped,
+        cents % 100
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_zero() {
+        assert_eq!(format_money(0, Currency::Nzd), "$0.00");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(format_money(-5, Currency::Nzd), "-$0.05");
+        assert_eq!(format_money(-123_456, Currency::Nzd), "-$1,234.56");
+    }
+
+    #[test]
+    fn groups_millions() {
+        assert_eq!(format_money(123_456_789, Currency::Nzd), "$1,234,567.89");
+        assert_eq!(format_money(100_000_000, Currency::Nzd), "$1,000,000.00");
+    }
+
+    #[test]
+    fn uses_the_currency_symbol() {
+        assert_eq!(format_money(1_050, Currency::Gbp), "£10.50");
+        assert_eq!(format_money(99_999, Currency::Usd), "US$999.99");
+    }
+}
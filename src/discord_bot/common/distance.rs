@@ -1,46 +1,182 @@
-use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use std::str::FromStr;
+
+use serenity::{
+    builder::CreateEmbed,
+    futures::{stream, StreamExt},
+};
 
 use crate::{
-    google_api::maps::GoogleMapsData,
-    state::{AppState, CONFIG},
+    discord_bot::commands::util::Branding,
+    google_api::maps::{GoogleMapsData, TravelMode},
+    state::{AppState, Destination},
 };
 
+/// the most requests made to google maps at once for a single embed, to stay under its rate limit
+const MAX_CONCURRENT_MAPS_REQUESTS: usize = 4;
+
+/// the number of metres in a mile
+const METRES_PER_MILE: f64 = 1609.344;
+
+/// the mean radius of the earth, in metres
+const EARTH_RADIUS_METRES: f64 = 6_371_000.0;
+
+/// a point on the earth, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// the great-circle distance between two points in metres, calculated with the haversine formula
+pub fn haversine_distance(from: Coordinates, to: Coordinates) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.longitude - from.longitude).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METRES * a.sqrt().asin()
+}
+
+/// the units distances are shown in, set per guild
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceUnit {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl DistanceUnit {
+    /// the value stored in the database for this unit
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Metric => "metric",
+            Self::Imperial => "imperial",
+        }
+    }
+}
+
+impl FromStr for DistanceUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "metric" => Ok(Self::Metric),
+            "imperial" => Ok(Self::Imperial),
+            other => Err(format!("unknown distance unit {}", other)),
+        }
+    }
+}
+
+/// format a distance in metres using the provided units, e.g. `850 m`, `12.3 km`, or `7.6 mi`
+pub fn format_distance(metres: u32, unit: DistanceUnit) -> String {
+    match unit {
+        DistanceUnit::Metric if metres < 1000 => format!("{} m", metres),
+        DistanceUnit::Metric => format!("{:.1} km", metres as f64 / 1000.0),
+        DistanceUnit::Imperial => format!("{:.1} mi", metres as f64 / METRES_PER_MILE),
+    }
+}
+
+/// load the distance from an address to each of the provided destinations, for each travel mode,
+/// and build an embed containing one field per destination
 pub async fn load_maps_data_to_embed(
     address: String,
+    destinations: &[Destination],
+    modes: &[TravelMode],
+    unit: DistanceUnit,
+    branding: &Branding,
     state: &AppState,
 ) -> Result<CreateEmbed, Box<dyn std::error::Error + Send + Sync + 'static>> {
-    // create a oneshot channel to await the response
-    let (tx, rx) = tokio::sync::oneshot::channel();
+    // every travel mode is requested at once, buffered keeps the results in the order of the
+    // modes regardless of which finishes first
+    let results: Vec<(TravelMode, GoogleMapsData)> = stream::iter(modes)
+        .map(|mode| {
+            let address = address.clone();
+            async move {
+                // make a global request for the address, each request to the api is given up on
+                // after the configured maps timeout
+                let data: GoogleMapsData = state
+                    .maps_api()
+                    .get_distance(address, destinations, *mode)
+                    .await?;
 
-    // make a global request for the address
-    state
-        .maps_api()
-        .add_to_queue(address, &CONFIG.destinations, tx)
-        .await;
+                Ok::<_, Box<dyn std::error::Error + Send + Sync + 'static>>((*mode, data))
+            }
+        })
+        .buffered(MAX_CONCURRENT_MAPS_REQUESTS)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
 
-    // wait for the oneshot channel to return (maximum of 20 seconds)
-    let data: GoogleMapsData =
-        tokio::time::timeout(std::time::Duration::from_secs(20), rx).await???;
+    let title = results
+        .first()
+        .and_then(|(_, data)| data.origin_addresses.first())
+        .cloned()
+        .unwrap_or(address);
 
-    let embed = CreateEmbed::default();
-
-    let mut embed = embed
-        .title(&data.origin_addresses[0])
+    let mut embed = branding
+        .embed()
+        .title(title)
         .footer(
-            CreateEmbedFooter::new(&CONFIG.phrases[rand::random::<usize>() % CONFIG.phrases.len()])
+            branding
+                .flavour_footer(&mut rand::thread_rng())
                 .icon_url("https://cdn.iconscout.com/icon/free/png-256/google-map-461800.png"),
         )
         .color(0x4285F4);
 
-    for row in data.rows.iter() {
-        for (i, element) in row.elements.iter().enumerate() {
-            embed = embed.field(
-                &CONFIG.destinations[i].label,
-                format!("{} ({})", element.distance.text, element.duration.text),
-                true,
-            );
-        }
+    for (i, destination) in destinations.iter().enumerate() {
+        let value = results
+            .iter()
+            .map(|(mode, data)| {
+                let element = data.rows.first().and_then(|row| row.elements.get(i));
+                match element.map(|e| (&e.distance, &e.duration)) {
+                    Some((Some(distance), Some(duration))) => {
+                        format!(
+                            "{} ({} {})",
+                            format_distance(distance.value, unit),
+                            duration.text,
+                            mode.label()
+                        )
+                    }
+                    _ => format!("{} unavailable", mode.label()),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        embed = embed.field(&destination.label, value, true);
     }
 
     Ok(embed)
 }
+
+/// build an embed containing the straight line distance from a point to each of the provided
+/// destinations, used in place of `load_maps_data_to_embed` when google maps is unavailable
+pub fn straight_line_embed(
+    title: String,
+    origin: Coordinates,
+    destinations: &[Destination],
+    unit: DistanceUnit,
+    branding: &Branding,
+) -> CreateEmbed {
+    let mut embed = branding
+        .embed()
+        .title(title)
+        .description("Google Maps is unavailable, so these distances are approximate")
+        .footer(branding.flavour_footer(&mut rand::thread_rng()))
+        .color(0x4285F4);
+
+    for destination in destinations {
+        let value = match destination.coordinates() {
+            Some(coordinates) => format!(
+                "{} (approx, as the crow flies)",
+                format_distance(haversine_distance(origin, coordinates) as u32, unit)
+            ),
+            None => String::from("unavailable"),
+        };
+
+        embed = embed.field(&destination.label, value, true);
+    }
+
+    embed
+}
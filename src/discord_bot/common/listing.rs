@@ -0,0 +1,721 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::{debug, error};
+use reqwest::Client;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+
+use super::distance::{load_maps_data_to_embed, straight_line_embed, Coordinates, DistanceUnit};
+use crate::{
+    discord_bot::{
+        commands::util::Branding,
+        common::currency::{format_money, Currency},
+        database::guild_config::GuildConfigModel,
+    },
+    state::{AppState, Flatmate, CONFIG},
+    timeout::{with_timeout, Timeout},
+};
+
+const REALESTATE_HOST: &str = "realestate.co.nz/";
+
+/// how many times a listing is requested before giving up on transient errors
+const MAX_FETCH_ATTEMPTS: u32 = 3;
+/// how long to wait before the first retry of a listing, doubled for every retry after it
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// posted in a listing's thread in place of its distances when they couldn't be loaded
+pub const MISSING_DISTANCES: &str = "Unable to load distance information for this listing";
+
+/// the most characters after a `$` read when looking for the rent of a listing
+const MAX_PRICE_LENGTH: usize = 40;
+
+/// the longest name discord allows a thread to have, in characters
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+lazy_static! {
+    /// recently loaded listings and when they were loaded, keyed by listing id
+    static ref LISTING_CACHE: Mutex<HashMap<u64, (Instant, Listing)>> = Mutex::new(HashMap::new());
+    /// when a listing was last successfully loaded from realestate.co.nz
+    static ref LAST_SUCCESS: Mutex<Option<Instant>> = Mutex::new(None);
+    /// the earliest the next request to realestate.co.nz may be sent
+    static ref NEXT_REQUEST: Mutex<Instant> = Mutex::new(Instant::now());
+}
+
+/// the number of requests waiting for their turn to be sent to realestate.co.nz
+static QUEUED_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// the least time between requests to realestate.co.nz
+pub fn request_interval() -> Duration {
+    Duration::from_millis(CONFIG.listing_request_interval)
+}
+
+/// the number of requests waiting for their turn to be sent to realestate.co.nz
+pub fn queued_requests() -> usize {
+    QUEUED_REQUESTS.load(Ordering::Relaxed)
+}
+
+/// counts a request as queued until it is dropped, so requests which time out while waiting
+/// aren't counted forever
+struct QueuedRequest;
+
+impl Drop for QueuedRequest {
+    fn drop(&mut self) {
+        QUEUED_REQUESTS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// wait until a request to realestate.co.nz may be sent, each request is given the next free
+/// slot so they are sent in the order they arrived, at most one per interval
+async fn wait_for_turn() {
+    QUEUED_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    let _queued = QueuedRequest;
+
+    let wait = {
+        let mut next = NEXT_REQUEST.lock().unwrap();
+        let now = Instant::now();
+        let slot = (*next).max(now);
+        *next = slot + request_interval();
+        slot - now
+    };
+
+    if !wait.is_zero() {
+        debug!("waiting {}ms to request realestate.co.nz", wait.as_millis());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// when a listing was last successfully loaded, rather than taken from the cache, if one has been
+pub fn last_success() -> Option<Instant> {
+    *LAST_SUCCESS.lock().unwrap()
+}
+
+#[derive(Debug)]
+pub enum ListingError {
+    NetworkError,
+    NotFound,
+    /// the listing existed but has since been let or withdrawn
+    Removed,
+    /// the listing did not load within the configured timeout
+    Timeout,
+    /// realestate.co.nz refused the request with the contained 4xx status, e.g. a 403 or 429
+    /// when the bot has been blocked for scraping
+    Refused(u16),
+}
+
+impl std::fmt::Display for ListingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkError => write!(f, "Network error"),
+            Self::NotFound => write!(f, "Listing not found"),
+            Self::Removed => write!(f, "Listing no longer available"),
+            Self::Timeout => write!(f, "Timed out"),
+            Self::Refused(status) => write!(f, "Request refused ({})", status),
+        }
+    }
+}
+
+impl std::error::Error for ListingError {}
+
+impl ListingError {
+    /// whether the request may succeed if it is tried again, only timeouts and server errors are
+    /// retried. A listing which doesn't exist never will, and a refused request is only made
+    /// worse by retrying it
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::NetworkError | Self::Timeout)
+    }
+}
+
+impl From<Timeout> for ListingError {
+    fn from(_: Timeout) -> Self {
+        Self::Timeout
+    }
+}
+
+impl From<reqwest::Error> for ListingError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::NetworkError
+        }
+    }
+}
+
+/// a rental listing posted to realestate.co.nz
+#[derive(Debug, Clone)]
+pub struct Listing {
+    /// the id realestate.co.nz assigned to the listing
+    pub id: u64,
+    /// the url the listing was found at
+    pub url: String,
+    /// the address of the property, as shown on the listing
+    pub address: String,
+    /// the rent, if the listing states one
+    pub price: Option<Rent>,
+    pub bedrooms: Option<u32>,
+    pub bathrooms: Option<u32>,
+    pub parking: Option<u32>,
+    /// the location of the property, if the listing states one
+    pub coordinates: Option<Coordinates>,
+}
+
+/// attempt to parse a token from a message as a link to a realestate.co.nz rental listing,
+/// returning the id of the listing and the url
+///
+/// rental listings take the form `https://www.realestate.co.nz/<id>/residential/rental/<slug>`
+pub fn parse_listing_url(token: &str) -> Option<(u64, String)> {
+    let start = token.find(REALESTATE_HOST)?;
+    let path = &token[start + REALESTATE_HOST.len()..];
+
+    let mut segments = path.split('/');
+    let id = segments.next()?.parse::<u64>().ok()?;
+    if segments.next()? != "residential" {
+        return None;
+    }
+    if !matches!(segments.next()?, "rent" | "rental") {
+        return None;
+    }
+
+    Some((id, token.to_string()))
+}
+
+/// derive a readable address from the slug at the end of a listing url, used when the page
+/// itself does not provide one, e.g. `12-smith-street-ponsonby` -> `12 Smith Street Ponsonby`
+fn address_from_slug(url: &str) -> Option<String> {
+    let slug = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|s| !s.is_empty())?;
+
+    Some(
+        slug.split('-')
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                let mut chars = w.chars();
+                match chars.next() {
+                    Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                    None => String::new(),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" "),
+    )
+}
+
+/// read the content of a `<meta property="...">` tag from a html document
+fn meta_property<'a>(html: &'a str, property: &str) -> Option<&'a str> {
+    let marker = format!("property=\"{}\"", property);
+    let tag_start = html[..html.find(&marker)?].rfind('<')?;
+    let tag = &html[tag_start..tag_start + html[tag_start..].find('>')?];
+
+    let content_start = tag.find("content=\"")? + "content=\"".len();
+    let content_len = tag[content_start..].find('"')?;
+    Some(&tag[content_start..content_start + content_len])
+}
+
+/// how often the rent of a listing is charged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RentPeriod {
+    Week,
+    Fortnight,
+    Month,
+}
+
+impl RentPeriod {
+    /// the period described by the text following a price, e.g. `per week`, `pw`, or `weekly`
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches('/').trim();
+        let text = text
+            .strip_prefix("per ")
+            .or_else(|| text.strip_prefix("a "))
+            .unwrap_or(text)
+            .trim();
+        match text {
+            "week" | "wk" | "pw" | "p/w" | "weekly" => Some(Self::Week),
+            "fortnight" | "pf" | "fortnightly" => Some(Self::Fortnight),
+            "month" | "pcm" | "pm" | "monthly" => Some(Self::Month),
+            _ => None,
+        }
+    }
+}
+
+/// the rent of a listing, as stated on the listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rent {
+    /// a single amount in whole dollars, charged every period
+    Parsed { amount: i64, period: RentPeriod },
+    /// a price which couldn't be understood, such as a range, kept as written
+    Raw(String),
+}
+
+impl Rent {
+    /// parse a price such as `$550 per week`, `$550pw`, or `$1,200 weekly`, keeping the text as
+    /// written if it isn't a single amount followed by a known period
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let digits = text.trim_start_matches('$');
+        let len = digits
+            .find(|c: char| !(c.is_ascii_digit() || c == ','))
+            .unwrap_or(digits.len());
+
+        let amount = digits[..len].replace(',', "").parse::<i64>().ok();
+        let period = RentPeriod::parse(&digits[len..].to_lowercase());
+        match (amount, period) {
+            // an amount too large to convert to cents is scraped garbage rather than a real rent
+            (Some(amount), Some(period))
+                if text.starts_with('$') && weekly_cents(amount, period).is_some() =>
+            {
+                Self::Parsed { amount, period }
+            }
+            _ => Self::Raw(text.to_string()),
+        }
+    }
+
+    /// the rent per week in cents, converting from the period it is charged over
+    pub fn weekly_cents(&self) -> Option<i64> {
+        match self {
+            Self::Parsed { amount, period } => weekly_cents(*amount, *period),
+            Self::Raw(_) => None,
+        }
+    }
+
+    /// the rent as used in a thread name, the whole dollars per week if it was understood
+    fn amount_label(&self) -> String {
+        match self {
+            Self::Parsed { amount, period } => match weekly_cents(*amount, *period) {
+                Some(cents) => (cents / 100).to_string(),
+                None => amount.to_string(),
+            },
+            Self::Raw(raw) => raw.clone(),
+        }
+    }
+}
+
+/// convert an amount in whole dollars charged every period to cents per week, or none if the
+/// amount is too large to convert
+fn weekly_cents(amount: i64, period: RentPeriod) -> Option<i64> {
+    let cents = amount.checked_mul(100)?;
+    match period {
+        RentPeriod::Week => Some(cents),
+        RentPeriod::Fortnight => Some(cents / 2),
+        RentPeriod::Month => Some(cents.checked_mul(12)? / 52),
+    }
+}
+
+/// find the rent stated in a html document. The first price followed by a known period is used,
+/// falling back to the first price mentioning a week as written, e.g. `$550 - $600 per week`
+fn listed_rent(html: &str) -> Option<Rent> {
+    let mut fallback = None;
+    for (start, _) in html.match_indices('$') {
+        let snippet = &html[start..];
+        if !snippet[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+        let end = snippet
+            .find(['<', '"', '\n'])
+            .unwrap_or(snippet.len())
+            .min(MAX_PRICE_LENGTH);
+        let snippet = match snippet.get(..end) {
+            Some(snippet) => snippet,
+            None => continue,
+        };
+
+        match Rent::parse(snippet) {
+            rent @ Rent::Parsed { .. } => return Some(rent),
+            Rent::Raw(raw) if fallback.is_none() && raw.to_lowercase().contains("week") => {
+                fallback = Some(Rent::Raw(raw))
+            }
+            Rent::Raw(_) => {}
+        }
+    }
+    fallback
+}
+
+/// read a numeric value from the structured data embedded in a html document,
+/// e.g. `"numberOfBedrooms":3` or `"numberOfBedrooms": "3"` -> `3`
+fn structured_number(html: &str, key: &str) -> Option<u32> {
+    let marker = format!("\"{}\":", key);
+    let value = html[html.find(&marker)? + marker.len()..].trim_start_matches([' ', '"']);
+    let len = value.find(|c: char| !c.is_ascii_digit())?;
+    value[..len].parse().ok()
+}
+
+/// read a decimal value from the structured data embedded in a html document,
+/// e.g. `"latitude":-36.8485` or `"latitude": "-36.8485"` -> `-36.8485`
+fn structured_float(html: &str, key: &str) -> Option<f64> {
+    let marker = format!("\"{}\":", key);
+    let value = html[html.find(&marker)? + marker.len()..].trim_start_matches([' ', '"']);
+    let len = value.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))?;
+    value[..len].parse().ok()
+}
+
+/// name the thread of a listing using a template, replacing `{price}`, `{address}`, and
+/// `{bedrooms}` with the details of the listing. Without a template the name is the weekly price
+/// followed by the address
+pub fn thread_name(listing: &Listing, template: Option<&str>) -> String {
+    let name = match (template, &listing.price) {
+        (Some(template), _) => template
+            .replace(
+                "{price}",
+                &listing
+                    .price
+                    .as_ref()
+                    .map(Rent::amount_label)
+                    .unwrap_or_else(|| String::from("?")),
+            )
+            .replace("{address}", &listing.address)
+            .replace(
+                "{bedrooms}",
+                &listing
+                    .bedrooms
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| String::from("?")),
+            ),
+        (None, Some(price @ Rent::Parsed { .. })) => {
+            format!("${}pw - {}", price.amount_label(), listing.address)
+        }
+        (None, Some(Rent::Raw(price))) => format!("{} - {}", price, listing.address),
+        (None, None) => listing.address.clone(),
+    };
+
+    name.trim().chars().take(MAX_THREAD_NAME_LENGTH).collect()
+}
+
+/// add the details of a listing to an embed as inline fields, skipping any the listing did not state
+pub fn add_listing_details(mut embed: CreateEmbed, listing: &Listing) -> CreateEmbed {
+    let details = [
+        ("Bedrooms", listing.bedrooms),
+        ("Bathrooms", listing.bathrooms),
+        ("Parking", listing.parking),
+    ];
+
+    for (name, value) in details {
+        if let Some(value) = value {
+            embed = embed.field(name, value.to_string(), true);
+        }
+    }
+
+    embed
+}
+
+/// add a field to an embed showing whether the rent of a listing is affordable, given the weekly
+/// income of each flatmate, shown in the guild's currency. The field is left out if the listing
+/// has no price, or if any flatmate has not set their income
+pub fn add_affordability(
+    embed: CreateEmbed,
+    listing: &Listing,
+    flatmates: &[Flatmate],
+    ratio: f64,
+    currency: Currency,
+) -> CreateEmbed {
+    let rent: i64 = match listing.price.as_ref().and_then(Rent::weekly_cents) {
+        Some(rent) => rent,
+        None => return embed,
+    };
+
+    let income: Option<i64> = flatmates.iter().map(|f| f.weekly_income).sum();
+    let income = match income {
+        Some(income) if income > 0 => income,
+        _ => return embed,
+    };
+
+    let share = rent as f64 / income as f64;
+    let name = if share <= ratio {
+        "✅ Affordable"
+    } else {
+        "⚠️ Over budget"
+    };
+
+    embed.field(
+        name,
+        format!(
+            "{}pw is {:.0}% of the flat's combined income of {}pw, the limit is {:.0}%",
+            format_money(rent, currency),
+            share * 100.0,
+            format_money(income, currency),
+            ratio * 100.0
+        ),
+        false,
+    )
+}
+
+/// build the embed posted in a listing's thread, with the distance to each destination and the
+/// details of the listing. Straight line distances are used if google maps is unavailable, and
+/// nothing is returned if the listing has no location to fall back on either
+pub async fn distance_embed(
+    listing: &Listing,
+    cached: Option<Duration>,
+    config: Option<&GuildConfigModel>,
+    app_state: &AppState,
+) -> Option<CreateEmbed> {
+    let unit: DistanceUnit = config
+        .and_then(|config| config.units.parse().ok())
+        .unwrap_or_default();
+    let currency: Currency = config
+        .and_then(|config| config.currency.parse().ok())
+        .unwrap_or_default();
+    let branding = Branding::from_config(config);
+
+    let embed = match load_maps_data_to_embed(
+        listing.address.clone(),
+        &CONFIG.destinations,
+        &CONFIG.travel_modes,
+        unit,
+        &branding,
+        app_state,
+    )
+    .await
+    {
+        Ok(embed) => embed,
+        Err(e) => {
+            error!("unable to load distances for listing {}: {}", listing.id, e);
+            straight_line_embed(
+                listing.address.clone(),
+                listing.coordinates?,
+                &CONFIG.destinations,
+                unit,
+                &branding,
+            )
+        }
+    };
+
+    let mut embed = add_affordability(
+        add_listing_details(embed, listing),
+        listing,
+        &app_state.flatmates(),
+        CONFIG.affordability_ratio,
+        currency,
+    );
+    if let Some(age) = cached {
+        embed = embed.footer(CreateEmbedFooter::new(format!(
+            "Listing details cached from {}m ago",
+            age.as_secs() / 60
+        )));
+    }
+    Some(embed)
+}
+
+/// the error for a response which wasn't successful, only server errors are worth retrying
+fn status_error(status: reqwest::StatusCode) -> ListingError {
+    if status.is_server_error() {
+        ListingError::NetworkError
+    } else {
+        ListingError::Refused(status.as_u16())
+    }
+}
+
+/// check that realestate.co.nz can be reached, giving up after the provided timeout
+pub async fn health(timeout: Duration) -> Result<(), ListingError> {
+    wait_for_turn().await;
+    let response = with_timeout(
+        "realestate.co.nz",
+        timeout,
+        Client::new().head("https://www.realestate.co.nz/").send(),
+    )
+    .await??;
+
+    if !response.status().is_success() {
+        return Err(status_error(response.status()));
+    }
+
+    Ok(())
+}
+
+/// load the details of a listing from realestate.co.nz, giving up after the provided timeout
+pub async fn fetch_listing(
+    id: u64,
+    url: String,
+    timeout: Duration,
+) -> Result<Listing, ListingError> {
+    wait_for_turn().await;
+    // the deadline covers loading the whole page, not just the response headers
+    let (status, path, html) = with_timeout("realestate.co.nz", timeout, async {
+        let response = Client::new().get(&url).send().await?;
+        let status = response.status();
+        let path = response.url().path().to_string();
+        Ok::<_, reqwest::Error>((status, path, response.text().await?))
+    })
+    .await??;
+
+    if status == 404 {
+        return Err(ListingError::NotFound);
+    }
+    // withdrawn listings are either marked as gone, or redirect back to the search results
+    if status == 410 || !path.contains(&id.to_string()) {
+        return Err(ListingError::Removed);
+    }
+    if !status.is_success() {
+        return Err(status_error(status));
+    }
+
+    // the title is of the form "<address> - <listing type> | realestate.co.nz"
+    let address = meta_property(&html, "og:title")
+        .and_then(|title| title.split(" - ").next())
+        .and_then(|title| title.split(" | ").next())
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .or_else(|| address_from_slug(&url))
+        .ok_or(ListingError::NotFound)?;
+
+    let price = listed_rent(&html);
+    let bedrooms = structured_number(&html, "numberOfBedrooms");
+    let bathrooms = structured_number(&html, "numberOfBathroomsTotal");
+    let parking = structured_number(&html, "numberOfParkingSpaces");
+    let coordinates = match (
+        structured_float(&html, "latitude"),
+        structured_float(&html, "longitude"),
+    ) {
+        (Some(latitude), Some(longitude)) => Some(Coordinates {
+            latitude,
+            longitude,
+        }),
+        _ => None,
+    };
+
+    debug!("loaded listing {}: {} ({:?})", id, address, price);
+
+    Ok(Listing {
+        id,
+        url,
+        address,
+        price,
+        bedrooms,
+        bathrooms,
+        parking,
+        coordinates,
+    })
+}
+
+/// forget every cached listing, so the next request for each is loaded from realestate.co.nz.
+/// Tests use this to start from an empty cache
+#[cfg(test)]
+pub fn clear_listing_cache() {
+    LISTING_CACHE.lock().unwrap().clear();
+}
+
+/// load the details of a listing, reusing a copy loaded within the last `ttl` rather than
+/// fetching the page again, and retrying with a backoff on transient errors. If the listing came
+/// from the cache, how long ago it was loaded is returned alongside it
+pub async fn fetch_listing_cached(
+    id: u64,
+    url: String,
+    timeout: Duration,
+    ttl: Duration,
+) -> Result<(Listing, Option<Duration>), ListingError> {
+    {
+        let mut cache = LISTING_CACHE.lock().unwrap();
+        cache.retain(|_, (loaded, _)| loaded.elapsed() < ttl);
+        if let Some((loaded, listing)) = cache.get(&id) {
+            debug!("using cached listing {}", id);
+            return Ok((listing.clone(), Some(loaded.elapsed())));
+        }
+    }
+
+    let mut attempt = 1;
+    let listing = loop {
+        match fetch_listing(id, url.clone(), timeout).await {
+            Ok(listing) => {
+                *LAST_SUCCESS.lock().unwrap() = Some(Instant::now());
+                break listing;
+            }
+            Err(e) if e.is_transient() && attempt < MAX_FETCH_ATTEMPTS => {
+                let backoff = RETRY_BACKOFF * 2u32.pow(attempt - 1);
+                debug!(
+                    "attempt {} to load listing {} failed ({}), retrying in {}s",
+                    attempt,
+                    id,
+                    e,
+                    backoff.as_secs()
+                );
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    if !ttl.is_zero() {
+        LISTING_CACHE
+            .lock()
+            .unwrap()
+            .insert(id, (Instant::now(), listing.clone()));
+    }
+
+    Ok((listing, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(id: u64) -> Listing {
+        Listing {
+            id,
+            url: format!(
+                "https://www.realestate.co.nz/{}/residential/rental/test",
+                id
+            ),
+            address: String::from("1 Test Street"),
+            price: None,
+            bedrooms: Some(3),
+            bathrooms: None,
+            parking: None,
+            coordinates: None,
+        }
+    }
+
+    #[test]
+    fn parses_rent() {
+        let rent = Rent::parse("$550 per week");
+        assert_eq!(
+            rent,
+            Rent::Parsed {
+                amount: 550,
+                period: RentPeriod::Week
+            }
+        );
+        assert_eq!(rent.weekly_cents(), Some(55_000));
+        assert_eq!(Rent::parse("$2,600 pcm").weekly_cents(), Some(60_000));
+    }
+
+    #[test]
+    fn implausible_rent_is_kept_as_written() {
+        let raw = "$100,000,000,000,000,000 per week";
+        assert_eq!(Rent::parse(raw), Rent::Raw(String::from(raw)));
+        let raw = "$10,000,000,000,000,000 monthly";
+        assert_eq!(Rent::parse(raw), Rent::Raw(String::from(raw)));
+    }
+
+    #[tokio::test]
+    async fn cached_listings_are_reused_until_cleared() {
+        clear_listing_cache();
+        let cached = listing(42);
+        LISTING_CACHE
+            .lock()
+            .unwrap()
+            .insert(cached.id, (Instant::now(), cached.clone()));
+
+        // the url can't be reached, so this only succeeds if the cache is used
+        let (loaded, age) = fetch_listing_cached(
+            42,
+            String::from("http://127.0.0.1:9/"),
+            Duration::from_millis(1),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+        assert_eq!(loaded.address, cached.address);
+        assert!(age.is_some());
+
+        clear_listing_cache();
+        assert!(LISTING_CACHE.lock().unwrap().is_empty());
+    }
+}
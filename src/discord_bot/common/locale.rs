@@ -0,0 +1,222 @@
+use std::str::FromStr;
+
+/// the language the bot's responses are written in, set per guild. Anything which hasn't been
+/// translated into a guild's locale is shown in english instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    Maori,
+}
+
+impl Locale {
+    /// every supported locale, in the order they are suggested
+    pub const ALL: [Locale; 2] = [Self::English, Self::Maori];
+
+    /// the value stored in the database for this locale, its ISO 639-1 code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Maori => "mi",
+        }
+    }
+
+    /// the name of the language, written in that language
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Maori => "Te Reo Māori",
+        }
+    }
+
+    /// the text of a message in this locale, falling back to english if it hasn't been translated
+    pub fn text(&self, text: Text) -> &'static str {
+        let translated = match self {
+            Self::English => None,
+            Self::Maori => maori(text),
+        };
+        translated.unwrap_or_else(|| english(text))
+    }
+
+    /// the text of a message in this locale with its placeholders filled in, `{0}` is replaced
+    /// by the first value, `{1}` by the second, and so on
+    pub fn format(&self, text: Text, values: &[&str]) -> String {
+        // the text is scanned once, so a value which itself contains a placeholder, such as a
+        // bill purpose typed by a user, is inserted as written
+        let mut rest = self.text(text);
+        let mut formatted = String::with_capacity(rest.len());
+        while let Some(start) = rest.find('{') {
+            formatted.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let placeholder = rest.find('}').and_then(|end| {
+                let value = values.get(rest[1..end].parse::<usize>().ok()?)?;
+                Some((value, end))
+            });
+            match placeholder {
+                Some((value, end)) => {
+                    formatted.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    formatted.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        formatted.push_str(rest);
+        formatted
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "en" | "english" => Ok(Self::English),
+            "mi" | "maori" | "māori" | "te reo" | "te reo maori" | "te reo māori" => {
+                Ok(Self::Maori)
+            }
+            _ => Err(format!("unknown locale {}", s)),
+        }
+    }
+}
+
+/// every message which can be translated, the english text of each is in [`english`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    PingTitle,
+    PingInteractionLatency,
+    PingDatabase,
+    /// `{0}` is the error
+    PingDatabaseUnavailable,
+    PingShardLatency,
+    /// `{0}` is why the latency isn't known
+    PingShardUnavailable,
+    PingIntegrations,
+    PingListingRequests,
+    PayPreview,
+    PayUnsaved,
+    PayParticipantsNeedSplit,
+    PayRestNeedsManualSplit,
+    PayFormAmountsGiven,
+    PayEvenSplitNeedsTotal,
+    PayRestNeedsTotal,
+    PayNoAmounts,
+    PayFormExpired,
+    PayFormNoAmounts,
+    PayAllPaid,
+    PayOutstanding,
+    PayNotOwed,
+    PayAlreadyMarked,
+    /// `{0}` is the name of the flatmate who paid
+    PayMarkedPaid,
+    PayOnlyCreatorSettles,
+    PayAlreadySettled,
+    PayOnlyCreatorCancels,
+    PayKept,
+    PayCancelled,
+    /// `{0}` is the id of the earlier bill, `{1}` its purpose, and `{2}` its total
+    PayDuplicate,
+    PayDuplicateCreated,
+    PayDuplicateDropped,
+    PayDuplicateExpired,
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::PingTitle => "Pong!",
+        Text::PingInteractionLatency => "Interaction latency",
+        Text::PingDatabase => "Database round-trip",
+        Text::PingDatabaseUnavailable => "unavailable ({0})",
+        Text::PingShardLatency => "Gateway heartbeat",
+        Text::PingShardUnavailable => "unavailable ({0})",
+        Text::PingIntegrations => "Integrations",
+        Text::PingListingRequests => "realestate.co.nz requests",
+        Text::PayPreview => "Preview, run this again without `preview` to post the bill",
+        Text::PayUnsaved => "the bill couldn't be saved, so it hasn't been posted, try again",
+        Text::PayParticipantsNeedSplit => {
+            "participants only apply to an even split or the form, otherwise just leave out the \
+             amounts of anyone not involved"
+        }
+        Text::PayRestNeedsManualSplit => "the rest only applies to a manual split",
+        Text::PayFormAmountsGiven => {
+            "amounts are entered in the form, so leave out the flatmate options"
+        }
+        Text::PayEvenSplitNeedsTotal => "an even split requires a total to divide",
+        Text::PayRestNeedsTotal => {
+            "covering the rest requires a total to take the other amounts from"
+        }
+        Text::PayNoAmounts => {
+            "no amounts were provided for any flatmate, set split to form to enter them in a form \
+             instead"
+        }
+        Text::PayFormExpired => "this form has expired, run /pay create again",
+        Text::PayFormNoAmounts => "no amounts were entered for any flatmate",
+        Text::PayAllPaid => "Every bill has been paid!",
+        Text::PayOutstanding => "Outstanding bills",
+        Text::PayNotOwed => "you don't owe anything on this bill",
+        Text::PayAlreadyMarked => "you've already marked this as paid",
+        Text::PayMarkedPaid => "{0} paid!",
+        Text::PayOnlyCreatorSettles => "only the creator can mark this bill as paid",
+        Text::PayAlreadySettled => "this bill has already been paid",
+        Text::PayOnlyCreatorCancels => "only the creator can cancel this bill",
+        Text::PayKept => "The bill has been kept.",
+        Text::PayCancelled => "The bill has been cancelled.",
+        Text::PayDuplicate => {
+            "This looks like a duplicate of bill #{0}, {1} for {2}, which you created a few \
+             minutes ago. Create it anyway?"
+        }
+        Text::PayDuplicateCreated => "The bill has been created.",
+        Text::PayDuplicateDropped => "The bill wasn't created.",
+        Text::PayDuplicateExpired => "this confirmation has expired, run /pay create again",
+    }
+}
+
+/// the messages translated into te reo Māori so far
+fn maori(text: Text) -> Option<&'static str> {
+    match text {
+        Text::PayAllPaid => Some("Kua utua ngā nama katoa!"),
+        Text::PayOutstanding => Some("Ngā nama kāore anō kia utua"),
+        Text::PayMarkedPaid => Some("Kua utua e {0}!"),
+        Text::PayKept => Some("Kua puritia te nama."),
+        Text::PayCancelled => Some("Kua whakakorehia te nama."),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders() {
+        assert_eq!(
+            Locale::English.format(Text::PayDuplicate, &["3", "Power", "$10.00"]),
+            "This looks like a duplicate of bill #3, Power for $10.00, which you created a few \
+             minutes ago. Create it anyway?"
+        );
+        assert_eq!(
+            Locale::Maori.format(Text::PayMarkedPaid, &["John"]),
+            "Kua utua e John!"
+        );
+    }
+
+    #[test]
+    fn values_are_inserted_as_written() {
+        assert_eq!(
+            Locale::English.format(Text::PayDuplicate, &["3", "{2} {0} {", "$10.00"]),
+            "This looks like a duplicate of bill #3, {2} {0} { for $10.00, which you created a \
+             few minutes ago. Create it anyway?"
+        );
+    }
+
+    #[test]
+    fn placeholders_without_a_value_are_kept() {
+        assert_eq!(
+            Locale::English.format(Text::PingDatabaseUnavailable, &[]),
+            "unavailable ({0})"
+        );
+    }
+}
@@ -1,2 +1,6 @@
+pub mod currency;
 pub mod distance;
 pub mod embed;
+pub mod listing;
+pub mod locale;
+pub mod progress;
@@ -0,0 +1,77 @@
+use log::warn;
+use serenity::{
+    all::CommandInteraction,
+    model::prelude::{ChannelId, MessageId},
+    prelude::Context,
+};
+
+/// shown on a message while the bot is still working on it
+const WORKING: char = '⏳';
+
+/// replaces `WORKING` once the work is done
+const DONE: char = '✅';
+
+/// replaces `WORKING` if the work failed
+const FAILED: char = '❌';
+
+/// a ⏳ reaction on a message which is being processed, swapped for ✅ or ❌ once the outcome is
+/// known, so slow operations are acknowledged straight away. The reactions are only a courtesy,
+/// so failing to add or remove them is logged and otherwise ignored
+pub struct Progress {
+    channel_id: ChannelId,
+    message_id: MessageId,
+}
+
+impl Progress {
+    /// react to a message with ⏳
+    pub async fn start(channel_id: ChannelId, message_id: MessageId, ctx: &Context) -> Self {
+        if let Err(e) = channel_id.create_reaction(ctx, message_id, WORKING).await {
+            warn!("unable to show progress on message {}: {}", message_id, e);
+        }
+        Self {
+            channel_id,
+            message_id,
+        }
+    }
+
+    /// react with ⏳ to the response of a deferred command. Ephemeral responses can't be reacted
+    /// to, so must not use this
+    pub async fn start_for_response(
+        interaction: &CommandInteraction,
+        ctx: &Context,
+    ) -> Option<Self> {
+        match interaction.get_response(ctx).await {
+            Ok(message) => Some(Self::start(message.channel_id, message.id, ctx).await),
+            Err(e) => {
+                warn!("unable to load deferred response to show progress: {}", e);
+                None
+            }
+        }
+    }
+
+    /// replace the ⏳ with ✅ if the work succeeded, or ❌ if it failed
+    pub async fn finish(self, success: bool, ctx: &Context) {
+        if let Err(e) = self
+            .channel_id
+            .delete_reaction(ctx, self.message_id, None, WORKING)
+            .await
+        {
+            warn!(
+                "unable to clear progress on message {}: {}",
+                self.message_id, e
+            );
+        }
+
+        let outcome = if success { DONE } else { FAILED };
+        if let Err(e) = self
+            .channel_id
+            .create_reaction(ctx, self.message_id, outcome)
+            .await
+        {
+            warn!(
+                "unable to show outcome on message {}: {}",
+                self.message_id, e
+            );
+        }
+    }
+}
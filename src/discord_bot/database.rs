@@ -31,6 +31,8 @@ pub mod shopping {
         pub notes: Option<&'a str>,
         pub quantity: i64,
         pub personal: bool,
+        /// the flatmate the item is for, if it is not shared
+        pub assigned_to: Option<u64>,
     }
 
     #[async_trait]
@@ -67,6 +69,12 @@ pub mod shopping {
             setting: bool,
         ) -> DatabaseResult<()>;
 
+        async fn set_shopping_list_item_bought_by_id(
+            &self,
+            id: u64,
+            setting: bool,
+        ) -> DatabaseResult<()>;
+
         async fn get_recent_shopping_list_items_by_user(
             &self,
             user: u64,
@@ -92,7 +100,7 @@ pub mod shopping {
             // XXX: should work for multiple guilds at some point
             let shopping_list: Option<ShoppingListModel> = ShoppingListEntity::find()
                 .order_by_desc(<ShoppingListEntity as EntityTrait>::Column::CreatedAt)
-                .one(&*self.database)
+                .one(&*self.database.connection())
                 .await?;
 
             if shopping_list.is_none() {
@@ -103,7 +111,7 @@ pub mod shopping {
             // load the items for the shopping list
             let items = shopping_list
                 .find_related(ShoppingListItemEntity)
-                .all(&*self.database)
+                .all(&*self.database.connection())
                 .await?;
 
             Ok(Some((shopping_list, items)))
@@ -138,11 +146,11 @@ pub mod shopping {
                         creation_message_guild_id: ActiveValue::Set(guild_id.map(|g| g as i64)),
                     };
 
-                    shopping_list.insert(&*self.database).await?;
+                    shopping_list.insert(&*self.database.connection()).await?;
 
                     ShoppingListEntity::find()
                         .order_by_desc(<ShoppingListEntity as EntityTrait>::Column::CreatedAt)
-                        .one(&*self.database)
+                        .one(&*self.database.connection())
                         .await?
                         .unwrap()
                 }
@@ -162,8 +170,9 @@ pub mod shopping {
                 personal: ActiveValue::Set(item.personal),
                 store: ActiveValue::Set(item.store.map(|s| s.to_string())),
                 notes: ActiveValue::Set(item.notes.map(|n| n.to_string())),
+                assigned_to: ActiveValue::Set(item.assigned_to.map(|a| a as i64)),
             };
-            item.insert(&*self.database).await?;
+            item.insert(&*self.database.connection()).await?;
 
             Ok(())
         }
@@ -174,7 +183,7 @@ pub mod shopping {
         ) -> DatabaseResult<Option<ShoppingListItemModel>> {
             let item = ShoppingListItemEntity::find()
                 .filter(<ShoppingListItemEntity as EntityTrait>::Column::Id.eq(id as i64))
-                .one(&*self.database)
+                .one(&*self.database.connection())
                 .await?;
 
             Ok(item)
@@ -189,7 +198,7 @@ pub mod shopping {
                     <ShoppingListItemEntity as EntityTrait>::Column::MessageId
                         .eq(message_id as i64),
                 )
-                .one(&*self.database)
+                .one(&*self.database.connection())
                 .await?;
 
             Ok(item)
@@ -207,13 +216,33 @@ pub mod shopping {
                         .eq(message_id as i64),
                 )
                 .filter(<ShoppingListItemEntity as EntityTrait>::Column::UserId.eq(user as i64))
-                .one(&*self.database)
+                .one(&*self.database.connection())
                 .await?;
 
             if let Some(shopping_list_item) = shopping_list_item {
                 let mut shopping_list_item = shopping_list_item.into_active_model();
                 shopping_list_item.bought = ActiveValue::Set(setting);
-                shopping_list_item.update(&*self.database).await?;
+                shopping_list_item
+                    .update(&*self.database.connection())
+                    .await?;
+            }
+
+            Ok(())
+        }
+
+        async fn set_shopping_list_item_bought_by_id(
+            &self,
+            id: u64,
+            setting: bool,
+        ) -> DatabaseResult<()> {
+            let shopping_list_item = self.get_shopping_list_item_by_id(id).await?;
+
+            if let Some(shopping_list_item) = shopping_list_item {
+                let mut shopping_list_item = shopping_list_item.into_active_model();
+                shopping_list_item.bought = ActiveValue::Set(setting);
+                shopping_list_item
+                    .update(&*self.database.connection())
+                    .await?;
             }
 
             Ok(())
@@ -228,7 +257,7 @@ pub mod shopping {
                 .filter(<ShoppingListItemEntity as EntityTrait>::Column::UserId.eq(user as i64))
                 .order_by_desc(<ShoppingListItemEntity as EntityTrait>::Column::CreatedAt)
                 .limit(count)
-                .all(&*self.database)
+                .all(&*self.database.connection())
                 .await?;
 
             Ok(shopping_list)
@@ -241,7 +270,7 @@ pub mod shopping {
             let shopping_list: Vec<ShoppingListItemModel> = ShoppingListItemEntity::find()
                 .order_by_desc(<ShoppingListItemEntity as EntityTrait>::Column::CreatedAt)
                 .limit(count)
-                .all(&*self.database)
+                .all(&*self.database.connection())
                 .await?;
 
             Ok(shopping_list)
@@ -253,10 +282,1181 @@ pub mod shopping {
             let shopping_list: Vec<ShoppingListItemModel> = ShoppingListItemEntity::find()
                 .filter(<ShoppingListItemEntity as EntityTrait>::Column::Bought.eq(false))
                 .order_by_desc(<ShoppingListItemEntity as EntityTrait>::Column::CreatedAt)
-                .all(&*self.database)
+                .all(&*self.database.connection())
                 .await?;
 
             Ok(shopping_list)
         }
     }
 }
+
+pub mod bills {
+    use crate::state::AppState;
+    use chrono::Local;
+    use chrono::NaiveDateTime;
+    use sea_orm::sea_query::Expr;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::ModelTrait;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use sea_orm::TransactionTrait;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::bill::ActiveModel as BillActiveModel;
+    pub use entity::bill::Entity as BillEntity;
+    pub use entity::bill::Model as BillModel;
+
+    pub use entity::bill_share::ActiveModel as BillShareActiveModel;
+    pub use entity::bill_share::Entity as BillShareEntity;
+    pub use entity::bill_share::Model as BillShareModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewBill<'a> {
+        pub purpose: &'a str,
+        /// the total of the bill, in cents
+        pub total: i64,
+        pub account: &'a str,
+        pub receipt_url: &'a str,
+        pub created_by: u64,
+
+        /// the message the bill is posted in, bills are stored before they are posted so this is
+        /// usually filled in afterwards with `set_bill_message`
+        pub message_id: Option<u64>,
+        pub channel_id: u64,
+        pub guild_id: Option<u64>,
+        /// the category the bill is filed under, e.g. groceries
+        pub category: &'a str,
+
+        /// the discord id of each flatmate, and the amount they owe in cents
+        pub shares: Vec<(u64, i64)>,
+    }
+
+    #[async_trait]
+    pub trait SerenityBillDatabase {
+        async fn create_bill(&self, bill: NewBill<'_>) -> DatabaseResult<BillModel>;
+
+        async fn get_bill(
+            &self,
+            id: i32,
+        ) -> DatabaseResult<Option<(BillModel, Vec<BillShareModel>)>>;
+
+        async fn get_bill_by_message_id(
+            &self,
+            message_id: u64,
+        ) -> DatabaseResult<Option<(BillModel, Vec<BillShareModel>)>>;
+
+        /// record the message a stored bill was posted in
+        async fn set_bill_message(&self, bill_id: i32, message_id: u64) -> DatabaseResult<()>;
+
+        async fn delete_bill(&self, id: i32) -> DatabaseResult<()>;
+
+        /// mark a share as paid or unpaid, along with an optional note from the payer. Returns
+        /// how many shares were updated, which is 0 if the share was already in that state
+        async fn set_bill_share_paid(
+            &self,
+            share_id: i32,
+            paid: bool,
+            note: Option<&str>,
+        ) -> DatabaseResult<u64>;
+
+        /// mark every unpaid share of a bill as paid at once, returning how many were updated
+        async fn settle_bill(&self, bill_id: i32, note: &str) -> DatabaseResult<u64>;
+
+        async fn get_bills_for_guild(
+            &self,
+            guild_id: Option<u64>,
+        ) -> DatabaseResult<Vec<(BillModel, Vec<BillShareModel>)>>;
+
+        /// every bill a flatmate created in a guild since a point in time, most recent first
+        async fn get_recent_bills_by_creator(
+            &self,
+            guild_id: Option<u64>,
+            created_by: u64,
+            since: NaiveDateTime,
+        ) -> DatabaseResult<Vec<BillModel>>;
+    }
+
+    #[async_trait]
+    impl SerenityBillDatabase for AppState {
+        async fn create_bill(&self, bill: NewBill<'_>) -> DatabaseResult<BillModel> {
+            // the bill and its shares are written together, so a failure part way through
+            // can't leave a bill without shares, or shares without a bill
+            let connection = self.database.connection();
+            let txn = connection.begin().await?;
+
+            let new_bill = BillActiveModel {
+                id: ActiveValue::NotSet,
+                purpose: ActiveValue::Set(bill.purpose.to_string()),
+                total: ActiveValue::Set(bill.total),
+                account: ActiveValue::Set(bill.account.to_string()),
+                receipt_url: ActiveValue::Set(bill.receipt_url.to_string()),
+                created_by: ActiveValue::Set(bill.created_by as i64),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+                // 0 until the bill has been posted, as no message has that id
+                message_id: ActiveValue::Set(bill.message_id.unwrap_or(0) as i64),
+                channel_id: ActiveValue::Set(bill.channel_id as i64),
+                guild_id: ActiveValue::Set(bill.guild_id.map(|g| g as i64)),
+                category: ActiveValue::Set(bill.category.to_string()),
+            }
+            .insert(&txn)
+            .await?;
+
+            for (user_id, amount) in bill.shares {
+                BillShareActiveModel {
+                    id: ActiveValue::NotSet,
+                    bill_id: ActiveValue::Set(new_bill.id),
+                    user_id: ActiveValue::Set(user_id as i64),
+                    amount: ActiveValue::Set(amount),
+                    paid: ActiveValue::Set(false),
+                    paid_at: ActiveValue::Set(None),
+                    note: ActiveValue::Set(None),
+                }
+                .insert(&txn)
+                .await?;
+            }
+
+            txn.commit().await?;
+
+            Ok(new_bill)
+        }
+
+        async fn get_bill(
+            &self,
+            id: i32,
+        ) -> DatabaseResult<Option<(BillModel, Vec<BillShareModel>)>> {
+            let bill = BillEntity::find_by_id(id)
+                .one(&*self.database.connection())
+                .await?;
+
+            let bill = match bill {
+                Some(bill) => bill,
+                None => return Ok(None),
+            };
+
+            let shares = bill
+                .find_related(BillShareEntity)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(Some((bill, shares)))
+        }
+
+        async fn get_bill_by_message_id(
+            &self,
+            message_id: u64,
+        ) -> DatabaseResult<Option<(BillModel, Vec<BillShareModel>)>> {
+            let bill = BillEntity::find()
+                .filter(<BillEntity as EntityTrait>::Column::MessageId.eq(message_id as i64))
+                .one(&*self.database.connection())
+                .await?;
+
+            let bill = match bill {
+                Some(bill) => bill,
+                None => return Ok(None),
+            };
+
+            let shares = bill
+                .find_related(BillShareEntity)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(Some((bill, shares)))
+        }
+
+        async fn set_bill_message(&self, bill_id: i32, message_id: u64) -> DatabaseResult<()> {
+            BillEntity::update_many()
+                .col_expr(
+                    <BillEntity as EntityTrait>::Column::MessageId,
+                    Expr::value(message_id as i64),
+                )
+                .filter(<BillEntity as EntityTrait>::Column::Id.eq(bill_id))
+                .exec(&*self.database.connection())
+                .await?;
+
+            Ok(())
+        }
+
+        async fn delete_bill(&self, id: i32) -> DatabaseResult<()> {
+            // the shares and the bill are removed together, so a failure can't leave a bill
+            // without its shares
+            let connection = self.database.connection();
+            let txn = connection.begin().await?;
+
+            BillShareEntity::delete_many()
+                .filter(<BillShareEntity as EntityTrait>::Column::BillId.eq(id))
+                .exec(&txn)
+                .await?;
+
+            BillEntity::delete_by_id(id).exec(&txn).await?;
+
+            txn.commit().await?;
+
+            Ok(())
+        }
+
+        async fn set_bill_share_paid(
+            &self,
+            share_id: i32,
+            paid: bool,
+            note: Option<&str>,
+        ) -> DatabaseResult<u64> {
+            // the state is checked in the same statement as it is changed, so two submissions at
+            // once can't both mark the share
+            let result = BillShareEntity::update_many()
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::Paid,
+                    Expr::value(paid),
+                )
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::PaidAt,
+                    Expr::value(paid.then(|| Local::now().naive_local())),
+                )
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::Note,
+                    Expr::value(note.map(|n| n.to_string())),
+                )
+                .filter(<BillShareEntity as EntityTrait>::Column::Id.eq(share_id))
+                .filter(<BillShareEntity as EntityTrait>::Column::Paid.eq(!paid))
+                .exec(&*self.database.connection())
+                .await?;
+
+            Ok(result.rows_affected)
+        }
+
+        async fn settle_bill(&self, bill_id: i32, note: &str) -> DatabaseResult<u64> {
+            let result = BillShareEntity::update_many()
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::Paid,
+                    Expr::value(true),
+                )
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::PaidAt,
+                    Expr::value(Local::now().naive_local()),
+                )
+                .col_expr(
+                    <BillShareEntity as EntityTrait>::Column::Note,
+                    Expr::value(note),
+                )
+                .filter(<BillShareEntity as EntityTrait>::Column::BillId.eq(bill_id))
+                .filter(<BillShareEntity as EntityTrait>::Column::Paid.eq(false))
+                .exec(&*self.database.connection())
+                .await?;
+
+            Ok(result.rows_affected)
+        }
+
+        async fn get_bills_for_guild(
+            &self,
+            guild_id: Option<u64>,
+        ) -> DatabaseResult<Vec<(BillModel, Vec<BillShareModel>)>> {
+            let column = <BillEntity as EntityTrait>::Column::GuildId;
+            let bills = BillEntity::find()
+                .filter(match guild_id {
+                    Some(guild_id) => column.eq(guild_id as i64),
+                    None => column.is_null(),
+                })
+                .order_by_asc(<BillEntity as EntityTrait>::Column::CreatedAt)
+                .find_with_related(BillShareEntity)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(bills)
+        }
+
+        async fn get_recent_bills_by_creator(
+            &self,
+            guild_id: Option<u64>,
+            created_by: u64,
+            since: NaiveDateTime,
+        ) -> DatabaseResult<Vec<BillModel>> {
+            let column = <BillEntity as EntityTrait>::Column::GuildId;
+            let bills = BillEntity::find()
+                .filter(match guild_id {
+                    Some(guild_id) => column.eq(guild_id as i64),
+                    None => column.is_null(),
+                })
+                .filter(<BillEntity as EntityTrait>::Column::CreatedBy.eq(created_by as i64))
+                .filter(<BillEntity as EntityTrait>::Column::CreatedAt.gte(since))
+                .order_by_desc(<BillEntity as EntityTrait>::Column::CreatedAt)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(bills)
+        }
+    }
+}
+
+pub mod listings {
+    use crate::state::AppState;
+    use chrono::Local;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::IntoActiveModel;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::listing::ActiveModel as ListingActiveModel;
+    pub use entity::listing::Entity as ListingEntity;
+    pub use entity::listing::Model as ListingModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewListing<'a> {
+        pub listing_id: u64,
+        pub url: &'a str,
+        pub address: &'a str,
+        pub channel_id: u64,
+        pub message_id: u64,
+        pub thread_id: u64,
+    }
+
+    #[async_trait]
+    pub trait SerenityListingDatabase {
+        async fn get_posted_listing(
+            &self,
+            channel_id: u64,
+            listing_id: u64,
+        ) -> DatabaseResult<Option<ListingModel>>;
+
+        async fn record_posted_listing(
+            &self,
+            listing: NewListing<'_>,
+        ) -> DatabaseResult<ListingModel>;
+
+        async fn get_listings_by_message_id(
+            &self,
+            message_id: u64,
+        ) -> DatabaseResult<Vec<ListingModel>>;
+
+        /// get every listing posted in a channel, the most recently posted first
+        async fn get_listings_for_channel(
+            &self,
+            channel_id: u64,
+        ) -> DatabaseResult<Vec<ListingModel>>;
+
+        async fn get_listing_by_thread_id(
+            &self,
+            thread_id: u64,
+        ) -> DatabaseResult<Option<ListingModel>>;
+
+        /// record the message in a listing's thread which holds its distances
+        async fn set_listing_distance_message(
+            &self,
+            listing: ListingModel,
+            message_id: u64,
+        ) -> DatabaseResult<ListingModel>;
+    }
+
+    #[async_trait]
+    impl SerenityListingDatabase for AppState {
+        async fn get_posted_listing(
+            &self,
+            channel_id: u64,
+            listing_id: u64,
+        ) -> DatabaseResult<Option<ListingModel>> {
+            let listing = ListingEntity::find()
+                .filter(<ListingEntity as EntityTrait>::Column::ChannelId.eq(channel_id as i64))
+                .filter(<ListingEntity as EntityTrait>::Column::ListingId.eq(listing_id as i64))
+                .one(&*self.database.connection())
+                .await?;
+
+            Ok(listing)
+        }
+
+        async fn record_posted_listing(
+            &self,
+            listing: NewListing<'_>,
+        ) -> DatabaseResult<ListingModel> {
+            let listing = ListingActiveModel {
+                id: ActiveValue::NotSet,
+                listing_id: ActiveValue::Set(listing.listing_id as i64),
+                url: ActiveValue::Set(listing.url.to_string()),
+                address: ActiveValue::Set(listing.address.to_string()),
+                channel_id: ActiveValue::Set(listing.channel_id as i64),
+                message_id: ActiveValue::Set(listing.message_id as i64),
+                thread_id: ActiveValue::Set(listing.thread_id as i64),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+                distance_message_id: ActiveValue::NotSet,
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(listing)
+        }
+
+        async fn get_listings_by_message_id(
+            &self,
+            message_id: u64,
+        ) -> DatabaseResult<Vec<ListingModel>> {
+            let listings = ListingEntity::find()
+                .filter(<ListingEntity as EntityTrait>::Column::MessageId.eq(message_id as i64))
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(listings)
+        }
+
+        async fn get_listings_for_channel(
+            &self,
+            channel_id: u64,
+        ) -> DatabaseResult<Vec<ListingModel>> {
+            let listings = ListingEntity::find()
+                .filter(<ListingEntity as EntityTrait>::Column::ChannelId.eq(channel_id as i64))
+                .order_by_desc(<ListingEntity as EntityTrait>::Column::CreatedAt)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(listings)
+        }
+
+        async fn get_listing_by_thread_id(
+            &self,
+            thread_id: u64,
+        ) -> DatabaseResult<Option<ListingModel>> {
+            let listing = ListingEntity::find()
+                .filter(<ListingEntity as EntityTrait>::Column::ThreadId.eq(thread_id as i64))
+                .one(&*self.database.connection())
+                .await?;
+
+            Ok(listing)
+        }
+
+        async fn set_listing_distance_message(
+            &self,
+            listing: ListingModel,
+            message_id: u64,
+        ) -> DatabaseResult<ListingModel> {
+            let mut listing = listing.into_active_model();
+            listing.distance_message_id = ActiveValue::Set(Some(message_id as i64));
+            let listing = listing.update(&*self.database.connection()).await?;
+
+            Ok(listing)
+        }
+    }
+}
+
+pub mod flatmates {
+    use crate::state::AppState;
+    use chrono::Local;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::IntoActiveModel;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::flatmate::ActiveModel as FlatmateActiveModel;
+    pub use entity::flatmate::Entity as FlatmateEntity;
+    pub use entity::flatmate::Model as FlatmateModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewFlatmate<'a> {
+        pub discord_id: u64,
+        pub name: &'a str,
+        pub display_name: &'a str,
+    }
+
+    #[async_trait]
+    pub trait SerenityFlatmateDatabase {
+        /// get every flatmate, including those who have since been removed
+        async fn get_flatmates(&self) -> DatabaseResult<Vec<FlatmateModel>>;
+
+        /// add a flatmate, restoring them if they were previously removed
+        async fn add_flatmate(&self, flatmate: NewFlatmate<'_>) -> DatabaseResult<FlatmateModel>;
+
+        /// mark a flatmate as removed, returning None if they are not a current flatmate
+        async fn remove_flatmate(&self, discord_id: u64) -> DatabaseResult<Option<FlatmateModel>>;
+
+        /// set the weekly income of a current flatmate in cents, returning None if they are not a
+        /// current flatmate
+        async fn set_flatmate_income(
+            &self,
+            discord_id: u64,
+            weekly_income: Option<i64>,
+        ) -> DatabaseResult<Option<FlatmateModel>>;
+    }
+
+    #[async_trait]
+    impl SerenityFlatmateDatabase for AppState {
+        async fn get_flatmates(&self) -> DatabaseResult<Vec<FlatmateModel>> {
+            let flatmates = FlatmateEntity::find()
+                .order_by_asc(<FlatmateEntity as EntityTrait>::Column::Id)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(flatmates)
+        }
+
+        async fn add_flatmate(&self, flatmate: NewFlatmate<'_>) -> DatabaseResult<FlatmateModel> {
+            let existing = FlatmateEntity::find()
+                .filter(
+                    <FlatmateEntity as EntityTrait>::Column::DiscordId
+                        .eq(flatmate.discord_id as i64),
+                )
+                .one(&*self.database.connection())
+                .await?;
+
+            if let Some(existing) = existing {
+                let mut existing = existing.into_active_model();
+                existing.name = ActiveValue::Set(flatmate.name.to_string());
+                existing.display_name = ActiveValue::Set(flatmate.display_name.to_string());
+                existing.removed_at = ActiveValue::Set(None);
+                return Ok(existing.update(&*self.database.connection()).await?);
+            }
+
+            let flatmate = FlatmateActiveModel {
+                id: ActiveValue::NotSet,
+                discord_id: ActiveValue::Set(flatmate.discord_id as i64),
+                name: ActiveValue::Set(flatmate.name.to_string()),
+                display_name: ActiveValue::Set(flatmate.display_name.to_string()),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+                removed_at: ActiveValue::Set(None),
+                weekly_income: ActiveValue::Set(None),
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(flatmate)
+        }
+
+        async fn remove_flatmate(&self, discord_id: u64) -> DatabaseResult<Option<FlatmateModel>> {
+            let flatmate = FlatmateEntity::find()
+                .filter(<FlatmateEntity as EntityTrait>::Column::DiscordId.eq(discord_id as i64))
+                .filter(<FlatmateEntity as EntityTrait>::Column::RemovedAt.is_null())
+                .one(&*self.database.connection())
+                .await?;
+
+            let mut flatmate = match flatmate {
+                Some(flatmate) => flatmate.into_active_model(),
+                None => return Ok(None),
+            };
+            flatmate.removed_at = ActiveValue::Set(Some(Local::now().naive_local()));
+
+            Ok(Some(flatmate.update(&*self.database.connection()).await?))
+        }
+
+        async fn set_flatmate_income(
+            &self,
+            discord_id: u64,
+            weekly_income: Option<i64>,
+        ) -> DatabaseResult<Option<FlatmateModel>> {
+            let flatmate = FlatmateEntity::find()
+                .filter(<FlatmateEntity as EntityTrait>::Column::DiscordId.eq(discord_id as i64))
+                .filter(<FlatmateEntity as EntityTrait>::Column::RemovedAt.is_null())
+                .one(&*self.database.connection())
+                .await?;
+
+            let mut flatmate = match flatmate {
+                Some(flatmate) => flatmate.into_active_model(),
+                None => return Ok(None),
+            };
+            flatmate.weekly_income = ActiveValue::Set(weekly_income);
+
+            Ok(Some(flatmate.update(&*self.database.connection()).await?))
+        }
+    }
+}
+
+pub mod chores {
+    use crate::state::AppState;
+    use chrono::Local;
+    use chrono::NaiveDate;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::IntoActiveModel;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::chore::ActiveModel as ChoreActiveModel;
+    pub use entity::chore::Entity as ChoreEntity;
+    pub use entity::chore::Model as ChoreModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    #[async_trait]
+    pub trait SerenityChoreDatabase {
+        async fn get_chores(&self, guild_id: u64) -> DatabaseResult<Vec<ChoreModel>>;
+
+        async fn add_chore(
+            &self,
+            guild_id: u64,
+            name: &str,
+            frequency_weeks: i32,
+        ) -> DatabaseResult<ChoreModel>;
+
+        /// move a chore along the rota, assigning it to a flatmate for the week starting `week`
+        async fn assign_chore(
+            &self,
+            chore: ChoreModel,
+            rotation: i32,
+            assigned_to: u64,
+            week: NaiveDate,
+        ) -> DatabaseResult<ChoreModel>;
+    }
+
+    #[async_trait]
+    impl SerenityChoreDatabase for AppState {
+        async fn get_chores(&self, guild_id: u64) -> DatabaseResult<Vec<ChoreModel>> {
+            let chores = ChoreEntity::find()
+                .filter(<ChoreEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .order_by_asc(<ChoreEntity as EntityTrait>::Column::Id)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(chores)
+        }
+
+        async fn add_chore(
+            &self,
+            guild_id: u64,
+            name: &str,
+            frequency_weeks: i32,
+        ) -> DatabaseResult<ChoreModel> {
+            let chore = ChoreActiveModel {
+                id: ActiveValue::NotSet,
+                guild_id: ActiveValue::Set(guild_id as i64),
+                name: ActiveValue::Set(name.to_string()),
+                frequency_weeks: ActiveValue::Set(frequency_weeks),
+                rotation: ActiveValue::Set(0),
+                assigned_to: ActiveValue::Set(None),
+                last_assigned: ActiveValue::Set(None),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(chore)
+        }
+
+        async fn assign_chore(
+            &self,
+            chore: ChoreModel,
+            rotation: i32,
+            assigned_to: u64,
+            week: NaiveDate,
+        ) -> DatabaseResult<ChoreModel> {
+            let mut chore = chore.into_active_model();
+            chore.rotation = ActiveValue::Set(rotation);
+            chore.assigned_to = ActiveValue::Set(Some(assigned_to as i64));
+            chore.last_assigned = ActiveValue::Set(Some(week));
+
+            Ok(chore.update(&*self.database.connection()).await?)
+        }
+    }
+}
+
+pub mod reminders {
+    use crate::state::AppState;
+    use chrono::Local;
+    use chrono::NaiveDateTime;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::ModelTrait;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::reminder::ActiveModel as ReminderActiveModel;
+    pub use entity::reminder::Entity as ReminderEntity;
+    pub use entity::reminder::Model as ReminderModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewReminder<'a> {
+        pub guild_id: u64,
+        pub channel_id: u64,
+        pub author_id: u64,
+        pub message: &'a str,
+        pub remind_at: NaiveDateTime,
+        pub ping_everyone: bool,
+    }
+
+    #[async_trait]
+    pub trait SerenityReminderDatabase {
+        async fn get_reminders(&self, guild_id: u64) -> DatabaseResult<Vec<ReminderModel>>;
+
+        /// get every reminder in the guild which should have been sent by `now`
+        async fn get_due_reminders(
+            &self,
+            guild_id: u64,
+            now: NaiveDateTime,
+        ) -> DatabaseResult<Vec<ReminderModel>>;
+
+        async fn add_reminder(&self, reminder: NewReminder<'_>) -> DatabaseResult<ReminderModel>;
+
+        /// delete a reminder, returning it if it existed in the guild
+        async fn delete_reminder(
+            &self,
+            guild_id: u64,
+            id: i32,
+        ) -> DatabaseResult<Option<ReminderModel>>;
+    }
+
+    #[async_trait]
+    impl SerenityReminderDatabase for AppState {
+        async fn get_reminders(&self, guild_id: u64) -> DatabaseResult<Vec<ReminderModel>> {
+            let reminders = ReminderEntity::find()
+                .filter(<ReminderEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .order_by_asc(<ReminderEntity as EntityTrait>::Column::RemindAt)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(reminders)
+        }
+
+        async fn get_due_reminders(
+            &self,
+            guild_id: u64,
+            now: NaiveDateTime,
+        ) -> DatabaseResult<Vec<ReminderModel>> {
+            let reminders = ReminderEntity::find()
+                .filter(<ReminderEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .filter(<ReminderEntity as EntityTrait>::Column::RemindAt.lte(now))
+                .order_by_asc(<ReminderEntity as EntityTrait>::Column::RemindAt)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(reminders)
+        }
+
+        async fn add_reminder(&self, reminder: NewReminder<'_>) -> DatabaseResult<ReminderModel> {
+            let reminder = ReminderActiveModel {
+                id: ActiveValue::NotSet,
+                guild_id: ActiveValue::Set(reminder.guild_id as i64),
+                channel_id: ActiveValue::Set(reminder.channel_id as i64),
+                author_id: ActiveValue::Set(reminder.author_id as i64),
+                message: ActiveValue::Set(reminder.message.to_string()),
+                remind_at: ActiveValue::Set(reminder.remind_at),
+                ping_everyone: ActiveValue::Set(reminder.ping_everyone),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(reminder)
+        }
+
+        async fn delete_reminder(
+            &self,
+            guild_id: u64,
+            id: i32,
+        ) -> DatabaseResult<Option<ReminderModel>> {
+            let reminder = match ReminderEntity::find_by_id(id)
+                .filter(<ReminderEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .one(&*self.database.connection())
+                .await?
+            {
+                Some(reminder) => reminder,
+                None => return Ok(None),
+            };
+
+            reminder
+                .clone()
+                .delete(&*self.database.connection())
+                .await?;
+
+            Ok(Some(reminder))
+        }
+    }
+}
+
+pub mod recurring_bills {
+    use crate::state::AppState;
+    use chrono::Local;
+    use chrono::NaiveDate;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::IntoActiveModel;
+    use sea_orm::QueryFilter;
+    use sea_orm::QueryOrder;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::recurring_bill::ActiveModel as RecurringBillActiveModel;
+    pub use entity::recurring_bill::Entity as RecurringBillEntity;
+    pub use entity::recurring_bill::Model as RecurringBillModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewRecurringBill<'a> {
+        pub guild_id: u64,
+        pub channel_id: u64,
+        pub created_by: u64,
+        pub purpose: &'a str,
+        /// the total of the bill, in cents
+        pub total: i64,
+        pub account: &'a str,
+        pub day_of_month: i32,
+        /// the last day the bill was posted, set to skip the current month
+        pub last_posted: Option<NaiveDate>,
+    }
+
+    #[async_trait]
+    pub trait SerenityRecurringBillDatabase {
+        async fn get_recurring_bills(
+            &self,
+            guild_id: u64,
+        ) -> DatabaseResult<Vec<RecurringBillModel>>;
+
+        async fn get_recurring_bill(
+            &self,
+            guild_id: u64,
+            id: i32,
+        ) -> DatabaseResult<Option<RecurringBillModel>>;
+
+        async fn add_recurring_bill(
+            &self,
+            bill: NewRecurringBill<'_>,
+        ) -> DatabaseResult<RecurringBillModel>;
+
+        /// save every editable field of a recurring bill
+        async fn update_recurring_bill(
+            &self,
+            bill: RecurringBillModel,
+        ) -> DatabaseResult<RecurringBillModel>;
+    }
+
+    #[async_trait]
+    impl SerenityRecurringBillDatabase for AppState {
+        async fn get_recurring_bills(
+            &self,
+            guild_id: u64,
+        ) -> DatabaseResult<Vec<RecurringBillModel>> {
+            let bills = RecurringBillEntity::find()
+                .filter(<RecurringBillEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .order_by_asc(<RecurringBillEntity as EntityTrait>::Column::Id)
+                .all(&*self.database.connection())
+                .await?;
+
+            Ok(bills)
+        }
+
+        async fn get_recurring_bill(
+            &self,
+            guild_id: u64,
+            id: i32,
+        ) -> DatabaseResult<Option<RecurringBillModel>> {
+            let bill = RecurringBillEntity::find_by_id(id)
+                .filter(<RecurringBillEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .one(&*self.database.connection())
+                .await?;
+
+            Ok(bill)
+        }
+
+        async fn add_recurring_bill(
+            &self,
+            bill: NewRecurringBill<'_>,
+        ) -> DatabaseResult<RecurringBillModel> {
+            let bill = RecurringBillActiveModel {
+                id: ActiveValue::NotSet,
+                guild_id: ActiveValue::Set(bill.guild_id as i64),
+                channel_id: ActiveValue::Set(bill.channel_id as i64),
+                created_by: ActiveValue::Set(bill.created_by as i64),
+                purpose: ActiveValue::Set(bill.purpose.to_string()),
+                total: ActiveValue::Set(bill.total),
+                account: ActiveValue::Set(bill.account.to_string()),
+                day_of_month: ActiveValue::Set(bill.day_of_month),
+                paused: ActiveValue::Set(false),
+                last_posted: ActiveValue::Set(bill.last_posted),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(bill)
+        }
+
+        async fn update_recurring_bill(
+            &self,
+            bill: RecurringBillModel,
+        ) -> DatabaseResult<RecurringBillModel> {
+            let mut active = bill.clone().into_active_model();
+            active.purpose = ActiveValue::Set(bill.purpose);
+            active.total = ActiveValue::Set(bill.total);
+            active.account = ActiveValue::Set(bill.account);
+            active.day_of_month = ActiveValue::Set(bill.day_of_month);
+            active.paused = ActiveValue::Set(bill.paused);
+            active.last_posted = ActiveValue::Set(bill.last_posted);
+
+            Ok(active.update(&*self.database.connection()).await?)
+        }
+    }
+}
+
+pub mod guild_config {
+    use crate::{
+        discord_bot::common::{currency::Currency, distance::DistanceUnit, locale::Locale},
+        state::AppState,
+    };
+    use chrono::{Local, NaiveDate};
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::IntoActiveModel;
+    use sea_orm::QueryFilter;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::guild_config::ActiveModel as GuildConfigActiveModel;
+    pub use entity::guild_config::Entity as GuildConfigEntity;
+    pub use entity::guild_config::Model as GuildConfigModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    #[async_trait]
+    pub trait SerenityGuildConfigDatabase {
+        async fn get_guild_config(&self, guild_id: u64)
+            -> DatabaseResult<Option<GuildConfigModel>>;
+
+        /// change a single setting of a guild, creating its config if it doesn't exist yet
+        async fn set_guild_setting(
+            &self,
+            guild_id: u64,
+            setting: GuildSetting,
+        ) -> DatabaseResult<GuildConfigModel>;
+
+        /// record the hash of the commands last registered with a guild
+        async fn set_commands_hash(&self, guild_id: u64, hash: &str) -> DatabaseResult<()>;
+
+        /// record the day the weekly spending summary was last posted in a guild
+        async fn set_summary_posted(&self, guild_id: u64, date: NaiveDate) -> DatabaseResult<()>;
+    }
+
+    /// a single validated setting of a guild, where None restores the default
+    #[derive(Debug, Clone)]
+    pub enum GuildSetting {
+        Units(DistanceUnit),
+        /// the currency amounts are shown in
+        Currency(Currency),
+        /// the language responses are written in
+        Locale(Locale),
+        /// the name shown at the top of every embed
+        BrandingAuthor(Option<String>),
+        /// the url of the image shown in the corner of every embed
+        BrandingThumbnail(Option<String>),
+        /// the text shown at the bottom of every embed
+        BrandingFooter(Option<String>),
+        /// the template listing threads are named with
+        ThreadName(Option<String>),
+        /// how long to wait for a listing to load, in seconds
+        ListingTimeout(Option<u64>),
+        /// the channel the chore rota is announced in
+        ChoreChannel(Option<u64>),
+        /// the only channel listings get threads in, None allows every channel
+        ListingsChannel(Option<u64>),
+        /// the role allowed to use admin only commands, in addition to the guild owner
+        AdminRole(Option<u64>),
+        /// the channel reminders are sent in, None sends each where it was set
+        ReminderChannel(Option<u64>),
+    }
+
+    /// load the config for a guild to be edited, creating it with the defaults if it does not
+    /// exist yet
+    async fn edit_guild_config(
+        app_state: &AppState,
+        guild_id: u64,
+    ) -> DatabaseResult<GuildConfigActiveModel> {
+        if let Some(existing) = app_state.get_guild_config(guild_id).await? {
+            return Ok(existing.into_active_model());
+        }
+
+        let config = GuildConfigActiveModel {
+            id: ActiveValue::NotSet,
+            guild_id: ActiveValue::Set(guild_id as i64),
+            units: ActiveValue::Set(DistanceUnit::default().as_str().to_string()),
+            updated_at: ActiveValue::Set(Local::now().naive_local()),
+            thread_template: ActiveValue::Set(None),
+            listing_timeout: ActiveValue::Set(None),
+            chore_channel: ActiveValue::Set(None),
+            listings_channel: ActiveValue::Set(None),
+            commands_hash: ActiveValue::Set(None),
+            summary_posted: ActiveValue::Set(None),
+            currency: ActiveValue::Set(Currency::default().as_str().to_string()),
+            branding_author: ActiveValue::Set(None),
+            branding_thumbnail: ActiveValue::Set(None),
+            branding_footer: ActiveValue::Set(None),
+            locale: ActiveValue::Set(Locale::default().as_str().to_string()),
+            admin_role: ActiveValue::Set(None),
+            reminder_channel: ActiveValue::Set(None),
+        }
+        .insert(&*app_state.database.connection())
+        .await?;
+
+        Ok(config.into_active_model())
+    }
+
+    #[async_trait]
+    impl SerenityGuildConfigDatabase for AppState {
+        async fn get_guild_config(
+            &self,
+            guild_id: u64,
+        ) -> DatabaseResult<Option<GuildConfigModel>> {
+            let config = GuildConfigEntity::find()
+                .filter(<GuildConfigEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .one(&*self.database.connection())
+                .await?;
+
+            Ok(config)
+        }
+
+        async fn set_guild_setting(
+            &self,
+            guild_id: u64,
+            setting: GuildSetting,
+        ) -> DatabaseResult<GuildConfigModel> {
+            let mut config = edit_guild_config(self, guild_id).await?;
+            match setting {
+                GuildSetting::Units(unit) => {
+                    config.units = ActiveValue::Set(unit.as_str().to_string())
+                }
+                GuildSetting::Currency(currency) => {
+                    config.currency = ActiveValue::Set(currency.as_str().to_string())
+                }
+                GuildSetting::Locale(locale) => {
+                    config.locale = ActiveValue::Set(locale.as_str().to_string())
+                }
+                GuildSetting::BrandingAuthor(author) => {
+                    config.branding_author = ActiveValue::Set(author)
+                }
+                GuildSetting::BrandingThumbnail(url) => {
+                    config.branding_thumbnail = ActiveValue::Set(url)
+                }
+                GuildSetting::BrandingFooter(footer) => {
+                    config.branding_footer = ActiveValue::Set(footer)
+                }
+                GuildSetting::ThreadName(template) => {
+                    config.thread_template = ActiveValue::Set(template)
+                }
+                GuildSetting::ListingTimeout(timeout) => {
+                    config.listing_timeout = ActiveValue::Set(timeout.map(|t| t as i64))
+                }
+                GuildSetting::ChoreChannel(channel) => {
+                    config.chore_channel = ActiveValue::Set(channel.map(|c| c as i64))
+                }
+                GuildSetting::ListingsChannel(channel) => {
+                    config.listings_channel = ActiveValue::Set(channel.map(|c| c as i64))
+                }
+                GuildSetting::AdminRole(role) => {
+                    config.admin_role = ActiveValue::Set(role.map(|r| r as i64))
+                }
+                GuildSetting::ReminderChannel(channel) => {
+                    config.reminder_channel = ActiveValue::Set(channel.map(|c| c as i64))
+                }
+            }
+            config.updated_at = ActiveValue::Set(Local::now().naive_local());
+
+            Ok(config.update(&*self.database.connection()).await?)
+        }
+
+        async fn set_commands_hash(&self, guild_id: u64, hash: &str) -> DatabaseResult<()> {
+            let mut config = edit_guild_config(self, guild_id).await?;
+            config.commands_hash = ActiveValue::Set(Some(hash.to_string()));
+            config.update(&*self.database.connection()).await?;
+
+            Ok(())
+        }
+
+        async fn set_summary_posted(&self, guild_id: u64, date: NaiveDate) -> DatabaseResult<()> {
+            let mut config = edit_guild_config(self, guild_id).await?;
+            config.summary_posted = ActiveValue::Set(Some(date));
+            config.update(&*self.database.connection()).await?;
+
+            Ok(())
+        }
+    }
+}
+
+pub mod audit_log {
+    use crate::state::AppState;
+    use chrono::Local;
+    use chrono::NaiveDateTime;
+    use sea_orm::ActiveValue;
+    use sea_orm::ColumnTrait;
+    use sea_orm::EntityTrait;
+    use sea_orm::QueryFilter;
+    use serenity::async_trait;
+
+    use super::DatabaseResult;
+
+    pub use entity::audit_log::ActiveModel as AuditLogActiveModel;
+    pub use entity::audit_log::Entity as AuditLogEntity;
+    pub use entity::audit_log::Model as AuditLogModel;
+
+    use sea_orm::ActiveModelTrait;
+
+    pub struct NewAuditEntry<'a> {
+        pub guild_id: Option<u64>,
+        pub user_id: u64,
+        /// the command that was run, including any subcommand, e.g. `/pay create`
+        pub command: &'a str,
+    }
+
+    #[async_trait]
+    pub trait SerenityAuditLogDatabase {
+        /// record that a user successfully ran a command
+        async fn record_command(&self, entry: NewAuditEntry<'_>) -> DatabaseResult<()>;
+
+        /// delete every entry in the guild recorded before `before`, returning how many were
+        /// removed
+        async fn prune_audit_log(
+            &self,
+            guild_id: u64,
+            before: NaiveDateTime,
+        ) -> DatabaseResult<u64>;
+    }
+
+    #[async_trait]
+    impl SerenityAuditLogDatabase for AppState {
+        async fn record_command(&self, entry: NewAuditEntry<'_>) -> DatabaseResult<()> {
+            AuditLogActiveModel {
+                id: ActiveValue::NotSet,
+                guild_id: ActiveValue::Set(entry.guild_id.map(|id| id as i64)),
+                user_id: ActiveValue::Set(entry.user_id as i64),
+                command: ActiveValue::Set(entry.command.to_string()),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+            }
+            .insert(&*self.database.connection())
+            .await?;
+
+            Ok(())
+        }
+
+        async fn prune_audit_log(
+            &self,
+            guild_id: u64,
+            before: NaiveDateTime,
+        ) -> DatabaseResult<u64> {
+            let result = AuditLogEntity::delete_many()
+                .filter(<AuditLogEntity as EntityTrait>::Column::GuildId.eq(guild_id as i64))
+                .filter(<AuditLogEntity as EntityTrait>::Column::CreatedAt.lt(before))
+                .exec(&*self.database.connection())
+                .await?;
+
+            Ok(result.rows_affected)
+        }
+    }
+}
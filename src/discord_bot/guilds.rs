@@ -1,34 +1,203 @@
 //! A handler for a guild, each guild will have one handler instance to manage it
 
 use std::{
-    sync::{atomic::Ordering, Arc},
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
-use log::{error, info, trace, warn};
+use chrono::Local;
+use log::{debug, error, info, trace, warn};
 use serenity::{
-    all::Interaction,
-    builder::{CreateAutocompleteResponse, CreateInteractionResponse},
+    all::{CommandInteraction, Interaction, ResolvedOption, ResolvedValue},
+    builder::{
+        CreateAutocompleteResponse, CreateCommand, CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+    },
     client::Context,
     futures::{stream::FuturesUnordered, StreamExt},
-    model::{id::GuildId, prelude::Message},
+    model::{
+        id::GuildId,
+        prelude::{Message, Reaction},
+    },
 };
 use tokio::{
     select,
     sync::mpsc::{UnboundedReceiver, UnboundedSender},
-    sync::RwLock,
+    sync::{OwnedSemaphorePermit, RwLock, Semaphore},
     task::JoinHandle,
 };
 
 use super::manager::{DiscordEvent, InternalSender};
 use crate::{
     discord_bot::{
-        commands::{application_command, autocomplete, command, interaction as handle_interaction},
-        messages::non_command_message,
+        commands::{
+            application_command, autocomplete, command, interaction as handle_interaction, modal,
+            post_recurring_bills, post_weekly_summary, rotate_chores, send_due_reminders,
+            util::{CommandResponse, ErrorReference},
+        },
+        database::{
+            audit_log::{NewAuditEntry, SerenityAuditLogDatabase},
+            guild_config::SerenityGuildConfigDatabase,
+        },
+        messages::{non_command_message, reaction_added},
     },
-    state::AppState,
+    state::{env_or, AppState, CONFIG},
 };
 
+/// how often each guild runs its periodic tasks
+const PERIODIC_TASK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// how many interactions can be waiting for a free slot before new ones are turned away
+const MAX_QUEUED_INTERACTIONS: usize = 64;
+
+/// how many times registering a guild's commands is attempted before giving up
+const MAX_SYNC_ATTEMPTS: u32 = 8;
+
+/// how long to wait after the first failed attempt to register commands, doubling each attempt
+const SYNC_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// the longest to wait between attempts to register commands
+const MAX_SYNC_RETRY_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// limits how many interactions a guild handles at once, so a burst of interactions can't
+/// overwhelm the database and external apis
+#[derive(Clone)]
+struct InteractionLimit {
+    permits: Arc<Semaphore>,
+    /// the number of interactions waiting for a permit
+    waiting: Arc<AtomicUsize>,
+}
+
+impl InteractionLimit {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            waiting: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// wait for a slot to handle an interaction in, which is freed when the permit is dropped
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        let permit = self.permits.clone().acquire_owned().await.ok();
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        permit
+    }
+}
+
+/// tell the user who triggered an interaction that the bot is too busy to handle it
+async fn respond_busy(interaction: Interaction, context: Context) {
+    let busy = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .content("The bot is busy right now, try again in a moment"),
+    );
+
+    let result = match interaction {
+        Interaction::Command(command) => command.create_response(&context, busy).await,
+        Interaction::Component(component) => component.create_response(&context, busy).await,
+        Interaction::Modal(submit) => submit.create_response(&context, busy).await,
+        Interaction::Autocomplete(autocomplete) => {
+            autocomplete
+                .create_response(
+                    &context,
+                    CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::default()),
+                )
+                .await
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        error!("Unable to send busy response: {:?}", e);
+    }
+}
+
+/// send the response to an application command, as a followup if the command was deferred
+async fn send_command_response(
+    command: &CommandInteraction,
+    context: &Context,
+    response: CommandResponse,
+) {
+    let result = match response {
+        CommandResponse::Deferred(response) => match response.generate_followup() {
+            Some(followup) => command.create_followup(context, followup).await.map(|_| ()),
+            None => Ok(()),
+        },
+        response => match response.generate_response() {
+            Some(resp) => command.create_response(context, resp).await,
+            None => Ok(()),
+        },
+    };
+
+    if let Err(e) = result {
+        error!("Unable to send response: {:?}", e);
+    }
+}
+
+/// the command an interaction ran, including any subcommands, e.g. `/pay create`
+fn command_path(command: &CommandInteraction) -> String {
+    let mut path = format!("/{}", command.data.name);
+
+    let mut options = command.data.options();
+    while let Some(ResolvedOption {
+        name,
+        value: ResolvedValue::SubCommand(nested) | ResolvedValue::SubCommandGroup(nested),
+        ..
+    }) = options.into_iter().next()
+    {
+        path.push(' ');
+        path.push_str(name);
+        options = nested;
+    }
+
+    path
+}
+
+/// record who ran a command in the audit log. The write is spawned so it doesn't hold up the
+/// interaction, and only the command is stored, not the values it was given
+fn record_in_audit_log(command: &CommandInteraction, app_state: &AppState) {
+    let path = command_path(command);
+    let guild_id: Option<u64> = command.guild_id.map(|g| g.0.into());
+    let user_id: u64 = command.user.id.into();
+    info!(
+        "{} ({}) ran {} in guild {:?}",
+        command.user.name, user_id, path, guild_id
+    );
+
+    let app_state = app_state.clone();
+    tokio::spawn(async move {
+        let entry = NewAuditEntry {
+            guild_id,
+            user_id,
+            command: &path,
+        };
+        if let Err(e) = app_state.record_command(entry).await {
+            error!("unable to record {} in the audit log: {}", path, e);
+        }
+    });
+}
+
+/// remove entries from a guild's audit log which are older than the configured retention
+async fn prune_audit_log(guild: GuildId, app_state: &AppState) {
+    let before =
+        Local::now().naive_local() - chrono::Duration::days(CONFIG.audit_log_retention as i64);
+    match app_state.prune_audit_log(guild.0.into(), before).await {
+        Ok(0) => {}
+        Ok(removed) => debug!("pruned {} audit log entries in guild {}", removed, guild),
+        Err(e) => error!("unable to prune the audit log of guild {}: {}", guild, e),
+    }
+}
+
 /// handle an interaction generated by slash command.
 /// matches over the type of interaction and then handles it appropriately, generating a response that can be sent to the user
 async fn handle_slash_command(interaction: Interaction, context: Context, app_state: AppState) {
@@ -40,28 +209,41 @@ async fn handle_slash_command(interaction: Interaction, context: Context, app_st
             match res {
                 Ok(response) => {
                     trace!("Sending response: {:?}", response);
-
-                    if let Some(resp) = response.generate_response() {
-                        if let Err(e) = raw_command.create_response(&context, resp).await {
-                            error!("Unable to send response: {:?}", e);
-                        }
-                    }
+                    send_command_response(&raw_command, &context, response).await;
+                    record_in_audit_log(&raw_command, &app_state);
                 }
                 Err(response) => {
-                    response.write_to_log();
-
-                    if let Some(resp) = response.generate_response() {
-                        if let Err(e) = raw_command.create_response(&context, resp).await {
-                            error!("Unable to send response: {:?}", e);
-                        }
-                    }
+                    let reference = ErrorReference::new(
+                        raw_command.guild_id.map(|g| g.0.into()),
+                        format!("/{}", raw_command.data.name),
+                    );
+                    response.write_to_log(&reference);
+                    let response = response.with_reference(&reference);
+                    send_command_response(&raw_command, &context, response).await;
                 }
             }
         }
         Interaction::Component(component) => {
             trace!("Received component interaction: {:?}", component);
-            if let Err(e) = handle_interaction(&component, &app_state, &context).await {
-                error!("Unable to handle component interaction: {:?}", e);
+            let res = handle_interaction(&component, &app_state, &context).await;
+
+            let response = match res {
+                Ok(response) => response,
+                Err(response) => {
+                    let reference = ErrorReference::new(
+                        component.guild_id.map(|g| g.0.into()),
+                        component.data.custom_id.clone(),
+                    );
+                    response.write_to_log(&reference);
+                    response.with_reference(&reference)
+                }
+            };
+
+            trace!("Sending response: {:?}", response);
+            if let Some(resp) = response.generate_response() {
+                if let Err(e) = component.create_response(&context, resp).await {
+                    error!("Unable to send response: {:?}", e);
+                }
             }
         }
         Interaction::Autocomplete(interaction) => {
@@ -69,7 +251,10 @@ async fn handle_slash_command(interaction: Interaction, context: Context, app_st
             let resp = match res {
                 Ok(r) => r,
                 Err(r) => {
-                    r.write_to_log();
+                    r.write_to_log(&ErrorReference::new(
+                        interaction.guild_id.map(|g| g.0.into()),
+                        format!("/{} autocomplete", interaction.data.name),
+                    ));
                     CreateAutocompleteResponse::default()
                 }
             };
@@ -81,10 +266,40 @@ async fn handle_slash_command(interaction: Interaction, context: Context, app_st
             }
         }
         Interaction::Modal(submit) => {
-            error!("Received modal submit: {:?}", submit);
+            trace!("Received modal submit: {:?}", submit);
+            let res = modal(&submit, &app_state, &context).await;
+
+            let response = match res {
+                Ok(response) => response,
+                Err(response) => {
+                    let reference = ErrorReference::new(
+                        submit.guild_id.map(|g| g.0.into()),
+                        submit.data.custom_id.clone(),
+                    );
+                    response.write_to_log(&reference);
+                    response.with_reference(&reference)
+                }
+            };
+
+            trace!("Sending response: {:?}", response);
+            let result = match response {
+                CommandResponse::Deferred(response) => match response.generate_followup() {
+                    Some(followup) => submit.create_followup(&context, followup).await.map(|_| ()),
+                    None => Ok(()),
+                },
+                response => match response.generate_response() {
+                    Some(resp) => submit.create_response(&context, resp).await,
+                    None => Ok(()),
+                },
+            };
+            if let Err(e) = result {
+                error!("Unable to send response: {:?}", e);
+            }
         }
-        // ping commands should not get here
-        _ => unreachable!(),
+        // pings are filtered out before an interaction is handled, and over the gateway discord
+        // doesn't expect a pong, so a stray one is logged rather than bringing down the task
+        Interaction::Ping(_) => warn!("ignoring ping interaction which reached the command layer"),
+        other => warn!("ignoring unsupported interaction: {:?}", other.kind()),
     }
 }
 
@@ -95,6 +310,169 @@ async fn handle_sent_message(message: Message, context: Context, app_state: AppS
         .unwrap();
 }
 
+async fn handle_reaction(reaction: Reaction, context: Context, app_state: AppState) {
+    trace!("Received reaction: {:?}", reaction);
+    reaction_added(&reaction, &app_state, &context).await;
+}
+
+/// run the tasks which are checked on a timer rather than triggered by an event, each task must
+/// be safe to run repeatedly as this is called every `PERIODIC_TASK_INTERVAL`
+async fn run_periodic_tasks(guild: GuildId, context: Context, app_state: AppState) {
+    trace!("Running periodic tasks for guild {}", guild);
+    rotate_chores(guild, &app_state, &context).await;
+    send_due_reminders(guild, &app_state, &context).await;
+    post_recurring_bills(guild, &app_state, &context).await;
+    post_weekly_summary(guild, &app_state, &context).await;
+    prune_audit_log(guild, &app_state).await;
+}
+
+/// register a guild's commands, retrying with an exponential backoff. Requests which discord
+/// rejects outright, such as when the bot lacks the scope to add commands, are not retried, and
+/// rate limits are already waited out by serenity before an error is returned
+async fn sync_commands_with_retry(guild: GuildId, app_state: &AppState, context: &Context) {
+    let mut backoff = SYNC_RETRY_BACKOFF;
+    for attempt in 1..=MAX_SYNC_ATTEMPTS {
+        let e = match sync_commands(guild, app_state, context).await {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+
+        let permanent = match &e {
+            serenity::Error::Http(http) => http
+                .status_code()
+                .map(|s| s.is_client_error() && s.as_u16() != 429)
+                .unwrap_or(false),
+            _ => false,
+        };
+        if permanent || attempt == MAX_SYNC_ATTEMPTS {
+            error!(
+                "giving up registering commands for guild {} after {} attempts, commands will \
+                 be unavailable until the bot restarts: {}",
+                guild, attempt, e
+            );
+            return;
+        }
+
+        warn!(
+            "attempt {} to register commands for guild {} failed, retrying in {}s: {}",
+            attempt,
+            guild,
+            backoff.as_secs(),
+            e
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_SYNC_RETRY_BACKOFF);
+    }
+}
+
+/// strip the parts of a serialized command that discord fills in with defaults, so a command
+/// loaded from discord can be compared with one built locally
+fn normalise_command_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| match v {
+                    serde_json::Value::Null | serde_json::Value::Bool(false) => false,
+                    serde_json::Value::Array(a) => !a.is_empty(),
+                    serde_json::Value::Object(o) => !o.is_empty(),
+                    _ => true,
+                })
+                .map(|(k, v)| (k, normalise_command_json(v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(normalise_command_json).collect())
+        }
+        v => v,
+    }
+}
+
+/// the name, description, and options of a command, which are compared to decide if it changed
+fn command_signature<T: serde::Serialize>(command: &T) -> Option<(String, serde_json::Value)> {
+    let value = serde_json::to_value(command).ok()?;
+    let name = value.get("name")?.as_str()?.to_string();
+    let signature = serde_json::json!({
+        "description": value.get("description").cloned(),
+        "options": value.get("options").cloned(),
+    });
+    Some((name, normalise_command_json(signature)))
+}
+
+/// a hash of the signature of every command, so a guild whose commands haven't changed since
+/// they were last registered can be skipped. The hasher may change between rust releases, which
+/// only costs a single extra sync
+fn commands_hash(signatures: &[(String, serde_json::Value, CreateCommand)]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for (name, signature, _) in signatures {
+        name.hash(&mut hasher);
+        signature.to_string().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// register the application commands with a guild, only creating, editing, or deleting the
+/// commands that differ from those already registered. Guilds whose commands are unchanged since
+/// they were last registered are skipped entirely, unless FORCE_COMMAND_SYNC is set
+pub(crate) async fn sync_commands(
+    guild: GuildId,
+    app_state: &AppState,
+    context: &Context,
+) -> Result<(), serenity::Error> {
+    let mut wanted = Vec::new();
+    for command in application_command(app_state) {
+        match command_signature(&command) {
+            Some((name, signature)) => wanted.push((name, signature, command)),
+            None => error!("unable to serialize command for guild {}", guild),
+        }
+    }
+
+    let hash = commands_hash(&wanted);
+    let force = env_or("FORCE_COMMAND_SYNC", false).unwrap_or_else(|e| {
+        warn!("{}, not forcing a command sync", e);
+        false
+    });
+    if !force {
+        match app_state.get_guild_config(guild.0.into()).await {
+            Ok(Some(config)) if config.commands_hash.as_deref() == Some(hash.as_str()) => {
+                debug!("commands for guild {} are up to date", guild);
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("unable to load command hash for guild {}: {}", guild, e),
+        }
+    }
+
+    let existing = guild.get_commands(context).await?;
+
+    for (name, signature, command) in wanted.iter().cloned() {
+        match existing.iter().find(|c| c.name == name) {
+            Some(current) => {
+                if command_signature(current).map(|(_, s)| s) != Some(signature) {
+                    info!("updating command /{} for guild {}", name, guild);
+                    guild.edit_command(context, current.id, command).await?;
+                }
+            }
+            None => {
+                info!("creating command /{} for guild {}", name, guild);
+                guild.create_command(context, command).await?;
+            }
+        }
+    }
+
+    for current in existing.iter() {
+        if !wanted.iter().any(|(name, _, _)| *name == current.name) {
+            info!("deleting command /{} for guild {}", current.name, guild);
+            guild.delete_command(context, current.id).await?;
+        }
+    }
+
+    if let Err(e) = app_state.set_commands_hash(guild.0.into(), &hash).await {
+        warn!("unable to store command hash for guild {}: {}", guild, e);
+    }
+
+    Ok(())
+}
+
 /// a handler which manages a guild, interacting with and responding to all events as required
 pub struct GuildHandler {
     /// the id of the guild being managed, generated by discord
@@ -119,8 +497,8 @@ pub struct GuildHandler {
 }
 
 impl GuildHandler {
-    /// create a new handler to manage a guild. A handler will begin by automatically searching for it's message to monitor for interaction.
-    /// If the message does not exist, it will automatically create it.
+    /// create a new handler to manage a guild. Once started, a handler registers its commands
+    /// with the guild and then handles the interactions, messages, and reactions it is sent.
     /// In the future, it will also listen for/manage the state of each guild, e.g. what roles should be assigned.
     /// This should make it very configurable utilising slash commands etc.
     pub fn new(
@@ -178,14 +556,15 @@ impl GuildHandler {
             app_state.num_connected.fetch_add(1, Ordering::Relaxed);
 
             self.handle = Some(tokio::task::spawn(async move {
-                // register all commands
-                while let Err(e) = guild.set_commands(&context, application_command()).await {
-                    error!("failed to register commands for guild {}: {}", guild, e);
-                    tokio::time::sleep(Duration::from_secs(10)).await;
-                }
+                // register all commands, the guild's messages and reactions are still handled
+                // if this fails
+                sync_commands_with_retry(guild, &app_state, &context).await;
 
                 let mut internal_rx = internal_rx.write().await;
                 let mut task_handles = FuturesUnordered::new();
+                let mut periodic = tokio::time::interval(PERIODIC_TASK_INTERVAL);
+                let mut periodic_run: Option<JoinHandle<()>> = None;
+                let interactions = InteractionLimit::new(CONFIG.max_concurrent_interactions);
 
                 loop {
                     select! {
@@ -203,8 +582,20 @@ impl GuildHandler {
                                     }
 
                                     let t_ctx = context.clone();
+
+                                    // rather than queueing forever, tell the user to come back later
+                                    if interactions.waiting() >= MAX_QUEUED_INTERACTIONS {
+                                        warn!("too many interactions queued for guild {}, rejecting", guild);
+                                        task_handles.push(tokio::task::spawn(async move {
+                                            respond_busy(*interaction, t_ctx).await;
+                                        }));
+                                        continue;
+                                    }
+
                                     let t_app_state = app_state.clone();
+                                    let t_interactions = interactions.clone();
                                     task_handles.push(tokio::task::spawn(async move {
+                                        let _permit = t_interactions.acquire().await;
                                         handle_slash_command(*interaction, t_ctx, t_app_state).await;
                                     }))
                                 },
@@ -215,11 +606,31 @@ impl GuildHandler {
                                         handle_sent_message(*msg, t_ctx, t_app_state).await;
                                     }))
                                 }
+                                DiscordEvent::Reaction(reaction) => {
+                                    let t_ctx = context.clone();
+                                    let t_app_state = app_state.clone();
+                                    task_handles.push(tokio::task::spawn(async move {
+                                        handle_reaction(*reaction, t_ctx, t_app_state).await;
+                                    }))
+                                }
                                 e => {
                                     error!("bot ignoring unexpected event: {:?}", e);
                                 }
                             }
                         },
+                        _ = periodic.tick() => {
+                            // the tasks read what is due before marking it done, so a slow run
+                            // overlapping the next could post the same thing twice
+                            if periodic_run.as_ref().map_or(false, |run| !run.is_finished()) {
+                                debug!("periodic tasks for guild {} are still running, skipping", guild);
+                                continue;
+                            }
+                            let t_ctx = context.clone();
+                            let t_app_state = app_state.clone();
+                            periodic_run = Some(tokio::task::spawn(async move {
+                                run_periodic_tasks(guild, t_ctx, t_app_state).await;
+                            }));
+                        },
                         // drain task handles as they complete
                         _ = task_handles.next(), if !task_handles.is_empty() => {},
                         else => break,
@@ -231,6 +642,9 @@ impl GuildHandler {
                     //XXX: timeout is not implemented yet
                     while task_handles.next().await.is_some() {}
                 }
+                if let Some(run) = periodic_run {
+                    let _ = run.await;
+                }
 
                 println!("No longer monitoring server with id {:?}", guild);
 
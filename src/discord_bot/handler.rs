@@ -10,7 +10,7 @@ use serenity::{
     model::{
         gateway::Ready,
         guild::{Guild, Member, UnavailableGuild},
-        prelude::Message,
+        prelude::{Message, Reaction},
     },
 };
 
@@ -58,6 +58,22 @@ impl EventHandler for Handler {
         }
     }
 
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        let reader = ctx.data.read().await;
+
+        let internal_sender = match reader.get::<InternalSender>() {
+            Some(internal_sender) => internal_sender,
+            None => {
+                error!("InternalSender not found in context");
+                return;
+            }
+        };
+
+        if let Err(e) = internal_sender.send(DiscordEvent::Reaction(Box::new(reaction))) {
+            error!("Error sending reaction to internal sender: {:?}", e);
+        }
+    }
+
     async fn guild_member_addition(&self, _ctx: Context, _new_member: Member) {
         warn!("New member joined, handler function not yet implemented");
         // todo!() //TODO: use this to readd a users roles if they have previously been verified
@@ -1,14 +1,14 @@
 //! The global manager for the bot, which manages all guilds as individual tasks
 //! and coordinates events between them.
 
-use std::{collections::HashMap, ops::DerefMut, time::Duration};
+use std::{collections::HashMap, ops::DerefMut, sync::Arc, time::Duration};
 
-use log::{error, warn};
+use log::{error, info, warn};
 use serenity::{
-    all::Interaction,
+    all::{Interaction, ShardManager},
     futures::{stream::FuturesUnordered, StreamExt},
-    model::prelude::Message,
-    prelude::{GatewayIntents, TypeMapKey},
+    model::prelude::{Message, Reaction},
+    prelude::{GatewayIntents, Mutex, TypeMapKey},
     Client,
 };
 use tokio::{
@@ -18,6 +18,33 @@ use tokio::{
 
 use super::{guilds::GuildHandler, handler::Handler};
 
+/// how long each guild handler has to finish its in-flight work when the bot shuts down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the guild whose handler an interaction is sent to. Pings, interactions the bot doesn't handle,
+/// and interactions from outside a guild are logged and have none
+fn interaction_guild(interaction: &Interaction) -> Option<u64> {
+    let guild_id = match interaction {
+        Interaction::Ping(_) => {
+            error!("got ping application command, which was not handled");
+            return None;
+        }
+        Interaction::Command(c) => c.guild_id,
+        Interaction::Component(c) => c.guild_id,
+        Interaction::Autocomplete(c) => c.guild_id,
+        Interaction::Modal(c) => c.guild_id,
+        other => {
+            warn!("ignoring unsupported interaction: {:?}", other.kind());
+            return None;
+        }
+    };
+
+    if guild_id.is_none() {
+        error!("got interaction without guild id");
+    }
+    guild_id.map(|g| g.0.into())
+}
+
 /// An event that may occur between the various discord services
 #[derive(Debug)]
 pub enum DiscordEvent {
@@ -30,6 +57,8 @@ pub enum DiscordEvent {
     Interaction(Box<Interaction>),
     /// a new message received from any guild
     Message(Box<Message>),
+    /// a reaction added to a message in any guild
+    Reaction(Box<Reaction>),
     /// a shutdown command to be sent to a guild, when received the guild should cease all activity and shut down
     Shutdown,
 }
@@ -55,6 +84,53 @@ impl DerefMut for InternalSender {
     }
 }
 
+/// the shard manager of the client, so commands can report on the gateway connection
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+/// wait for the process to be asked to stop, with either SIGINT or SIGTERM
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    select! {
+        _ = tokio::signal::ctrl_c() => info!("received SIGINT"),
+        _ = terminate => info!("received SIGTERM"),
+    }
+}
+
+/// close every guild handler at once, each is given the same timeout to finish its in-flight
+/// work before it is aborted
+async fn shutdown_all(guild_handlers: &mut HashMap<u64, GuildHandler>, timeout: Duration) {
+    info!("closing {} guild handlers", guild_handlers.len());
+
+    let mut closing: FuturesUnordered<_> = guild_handlers
+        .drain()
+        .map(|(_, mut handler)| async move {
+            if let Err(e) = handler.close(timeout).await {
+                error!("failed to close a guild handler {}", e);
+            }
+        })
+        .collect();
+
+    while closing.next().await.is_some() {}
+}
+
 /// A builder for the discord bot
 pub struct DiscordBotBuilder<T> {
     /// the discord token to use for authentication with the discord api
@@ -131,16 +207,18 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
             .await?;
 
         let (i_tx, mut i_rx) = unbounded_channel();
+        let shutdown_tx = i_tx.clone();
 
         // scoping this off means we'll drop the write guard properly
         {
             let mut data = client.data.write().await;
             data.insert::<InternalSender>(InternalSender(i_tx));
+            data.insert::<ShardManagerContainer>(client.shard_manager.clone());
             // data.insert::<BotDiscordId>(BotDiscordId::new(client.user_id.0));
             data.insert::<T>(self.app_state.clone());
         }
 
-        let handle = tokio::task::spawn(async move {
+        let mut handle = tokio::task::spawn(async move {
             let mut thread_handles = FuturesUnordered::new();
             let mut guild_handlers: HashMap<u64, GuildHandler> = HashMap::default();
 
@@ -159,6 +237,11 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
                                 }
                                 guild_handlers.insert(key, handler);
                             },
+                            DiscordEvent::Shutdown => {
+                                shutdown_all(&mut guild_handlers, SHUTDOWN_TIMEOUT).await;
+                                while thread_handles.next().await.is_some() {}
+                                break;
+                            },
                             DiscordEvent::DeletedGuild(guild) => {
                                 // remove guild handler
                                 let mut g_h = match guild_handlers.remove(&guild) {
@@ -178,24 +261,9 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
                                 thread_handles.push(t_h);
                             },
                             DiscordEvent::Interaction(interaction) => {
-                                let guild_id: Option<serenity::model::id::GuildId> = match *interaction {
-                                    Interaction::Ping(_) => {
-                                        error!("got ping application command, which was not handled");
-                                        continue;
-                                    },
-                                    Interaction::Command(ref c) => c.guild_id,
-                                    Interaction::Component(ref c) => c.guild_id,
-                                    Interaction::Autocomplete(ref c) => c.guild_id,
-                                    Interaction::Modal(ref c) => c.guild_id,
-                                    _ => todo!("Currently, all branches are covered - more may become available in the future!"),
-                                };
-
-                                let guild_id: u64 = match guild_id {
-                                    Some(g_id) => g_id.0.into(),
-                                    None => {
-                                        error!("got interaction without guild id");
-                                        continue;
-                                    }
+                                let guild_id = match interaction_guild(&interaction) {
+                                    Some(guild_id) => guild_id,
+                                    None => continue,
                                 };
 
                                 let g_h = match guild_handlers.get(&guild_id) {
@@ -230,6 +298,29 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
                                     error!("failed to send message to guild handler {}", e);
                                 }
                             }
+                            DiscordEvent::Reaction(reaction) => {
+                                let guild_id: u64 = match reaction.guild_id {
+                                    Some(g_id) => g_id.into(),
+                                    None => {
+                                        warn!("got reaction without guild id");
+                                        continue;
+                                    }
+                                };
+
+                                let g_h = match guild_handlers.get(&guild_id) {
+                                    Some(s) => s.internal_tx.clone(),
+                                    None => {
+                                        // reactions can arrive before the guild is created, or
+                                        // after it has been removed
+                                        warn!("got reaction for unmanaged guild id {}", guild_id);
+                                        continue;
+                                    }
+                                };
+
+                                if let Err(e) = g_h.send(DiscordEvent::Reaction(reaction)) {
+                                    error!("failed to send reaction to guild handler {}", e);
+                                }
+                            }
                             e => error!("unexpected discord event received {:?}", e),
                         }
                     },
@@ -241,7 +332,20 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
             }
         });
 
-        client.start().await?;
+        let shard_manager = client.shard_manager.clone();
+
+        select! {
+            result = client.start() => result?,
+            _ = shutdown_signal() => {
+                // let every guild finish what it is doing before disconnecting from discord, so
+                // no interaction is left half processed
+                shutdown_tx.send(DiscordEvent::Shutdown)?;
+                if let Err(e) = (&mut handle).await {
+                    error!("guild manager failed during shutdown: {}", e);
+                }
+                shard_manager.lock().await.shutdown_all().await;
+            }
+        }
 
         // once the discord bot has shutdown, we can expect to close the manager,
         // so we need to clean up the task for it.
@@ -250,3 +354,25 @@ impl<T: Send + Sync + 'static + Clone + TypeMapKey<Value = T>> DiscordBot<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn ping_interactions_are_ignored() {
+        let ping: Interaction = serde_json::from_value(json!({
+            "id": "100",
+            "application_id": "200",
+            "type": 1,
+            "token": "token",
+            "version": 1,
+        }))
+        .unwrap();
+
+        assert!(matches!(ping, Interaction::Ping(_)));
+        assert_eq!(interaction_guild(&ping), None);
+    }
+}
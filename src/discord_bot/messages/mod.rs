@@ -1,5 +1,13 @@
+mod realestate;
+
 use crate::state::AppState;
-use serenity::{async_trait, model::prelude::Message, prelude::Context};
+use log::{debug, error};
+use realestate::{tally_listing_votes, RealEstateDistance};
+use serenity::{
+    async_trait,
+    model::prelude::{Message, Reaction},
+    prelude::Context,
+};
 
 #[async_trait]
 trait MessageReactor<'a>: TryFrom<&'a Message> {
@@ -16,30 +24,73 @@ trait MessageReactor<'a>: TryFrom<&'a Message> {
     async fn process(self, message: &Message, app_state: &AppState, ctx: &Context);
 }
 
+/// run the precheck of each of the provided reactors against a message in the order they are
+/// listed, spawning the processing of every reactor that matches so slow reactors don't hold up
+/// the others, then wait for all of them to finish
 macro_rules! reactor {
     ( $cmd:expr, $state:expr, $context:expr, $( $x:ty ),* $(,)? )  => {
         {
             /// ensures that the provided type has relevant traits
             fn _ensure_traits<'a, T: MessageReactor<'a, Error=String>>() {}
+            let mut handles = vec![];
             $(
                 _ensure_traits::<$x>();
 
                 if <$x>::precheck($cmd) {
-                    if let Ok(v_cmd) = <$x>::try_from($cmd) {
-                        v_cmd.process($cmd, $state, $context).await;
-                    }
+                    let message = ($cmd).clone();
+                    let app_state = ($state).clone();
+                    let ctx = ($context).clone();
+                    handles.push((<$x>::name(), tokio::spawn(async move {
+                        match <$x>::try_from(&message) {
+                            Ok(v_cmd) => v_cmd.process(&message, &app_state, &ctx).await,
+                            Err(e) => debug!("{} did not react to message: {}", <$x>::name(), e),
+                        }
+                    })));
                 }
             )*
+
+            for (name, handle) in handles {
+                if let Err(e) = handle.await {
+                    error!("{} failed while processing message: {}", name, e);
+                }
+            }
         }
     };
 }
 
+/// list the name and description of each of the provided reactor types
+macro_rules! describe_reactors {
+    ( $( $x:ty ),* $(,)? ) => {
+        {
+            /// ensures that the provided type has relevant traits
+            fn _ensure_traits<'a, T: MessageReactor<'a, Error=String>>() {}
+            let mut descriptions = vec![];
+            $(
+                _ensure_traits::<$x>();
+                descriptions.push((<$x>::name(), <$x>::description()));
+            )*
+            descriptions
+        }
+    };
+}
+
+/// the name and description of every registered reactor, in the order they are run
+pub fn reactor_descriptions() -> Vec<(&'static str, &'static str)> {
+    describe_reactors!(RealEstateDistance)
+}
+
 pub async fn non_command_message(
     message: &Message,
     app_state: &AppState,
     ctx: &Context,
 ) -> Result<(), String> {
-    // reactor!(message, app_state, ctx, );
+    // every reactor is registered here, in the order their prechecks should be run
+    reactor!(message, app_state, ctx, RealEstateDistance);
 
     Ok(())
 }
+
+/// handle a reaction being added to any message in the guild
+pub async fn reaction_added(reaction: &Reaction, app_state: &AppState, ctx: &Context) {
+    tally_listing_votes(reaction, app_state, ctx).await;
+}
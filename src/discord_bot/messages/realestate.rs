@@ -0,0 +1,441 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use serenity::{
+    async_trait,
+    builder::{CreateMessage, CreateThread, EditThread},
+    model::prelude::{ChannelId, ChannelType, Message, Reaction, ReactionType},
+    prelude::Context,
+};
+
+use super::MessageReactor;
+use crate::{
+    discord_bot::{
+        common::{
+            listing::{
+                distance_embed, fetch_listing_cached, parse_listing_url, thread_name, ListingError,
+                MISSING_DISTANCES,
+            },
+            progress::Progress,
+        },
+        database::{
+            guild_config::{GuildConfigModel, SerenityGuildConfigDatabase},
+            listings::{NewListing, SerenityListingDatabase},
+        },
+    },
+    state::{AppState, CONFIG},
+};
+
+/// the reactions added to listings so the flat can vote on them
+const VOTE_REACTIONS: [&str; 3] = ["👍", "👎", "🤔"];
+
+/// the prefix added to the thread of a listing once it has enough votes
+const STAR_PREFIX: &str = "⭐ ";
+
+/// how long to wait before warning the guild owner about missing permissions in the same
+/// channel again
+const PERMISSION_WARNING_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+lazy_static! {
+    /// when the owner was last warned about missing permissions in each channel
+    static ref PERMISSION_WARNINGS: Mutex<HashMap<ChannelId, Instant>> = Mutex::new(HashMap::new());
+}
+
+/// check if discord rejected a request because the bot lacks a permission
+fn is_permission_error(e: &serenity::Error) -> bool {
+    match e {
+        serenity::Error::Http(http) => http.status_code().map(|s| s.as_u16()) == Some(403),
+        _ => false,
+    }
+}
+
+/// let the guild owner know the bot is missing a permission in the channel a listing was posted
+/// in, as otherwise the listing is silently ignored. Each channel is only warned about once per
+/// `PERMISSION_WARNING_INTERVAL`
+async fn warn_missing_permission(message: &Message, permission: &str, ctx: &Context) {
+    {
+        let mut warnings = PERMISSION_WARNINGS.lock().unwrap();
+        if let Some(warned) = warnings.get(&message.channel_id) {
+            if warned.elapsed() < PERMISSION_WARNING_INTERVAL {
+                return;
+            }
+        }
+        warnings.insert(message.channel_id, Instant::now());
+    }
+
+    let guild = match message.guild_id {
+        Some(guild_id) => match guild_id.to_partial_guild(ctx).await {
+            Ok(guild) => guild,
+            Err(e) => {
+                error!("unable to load guild {} to warn its owner: {}", guild_id, e);
+                return;
+            }
+        },
+        None => return,
+    };
+
+    let dm = match guild.owner_id.create_dm_channel(ctx).await {
+        Ok(dm) => dm,
+        Err(e) => {
+            error!("unable to message the owner of guild {}: {}", guild.id, e);
+            return;
+        }
+    };
+
+    if let Err(e) = dm
+        .send_message(
+            ctx,
+            CreateMessage::new().content(format!(
+                "I don't have permission to **{}** in <#{}> of {}, so listings posted there are \
+                 being missed. Give me that permission, or choose a different channel with \
+                 `/config set listings-channel`.",
+                permission, message.channel_id, guild.name
+            )),
+        )
+        .await
+    {
+        error!("unable to message the owner of guild {}: {}", guild.id, e);
+        return;
+    }
+
+    info!(
+        "warned the owner of guild {} about missing permission {} in {}",
+        guild.id, permission, message.channel_id
+    );
+}
+
+/// creates a thread for every realestate.co.nz rental listing posted, containing the commute
+/// times from the property to each of the configured destinations
+pub struct RealEstateDistance {
+    /// the id and url of every distinct listing in the message
+    listings: Vec<(u64, String)>,
+}
+
+impl<'a> TryFrom<&'a Message> for RealEstateDistance {
+    type Error = String;
+
+    fn try_from(message: &'a Message) -> Result<Self, Self::Error> {
+        let mut listings: Vec<(u64, String)> = Vec::new();
+
+        for token in message.content.split_whitespace() {
+            // links wrapped in <> have their embeds suppressed by discord
+            let token = token
+                .trim_start_matches('<')
+                .trim_end_matches(|c: char| matches!(c, '>' | '.' | ',' | ')' | '!' | '?'));
+
+            if let Some((id, url)) = parse_listing_url(token) {
+                if !listings.iter().any(|(existing, _)| *existing == id) {
+                    listings.push((id, url));
+                }
+            }
+        }
+
+        if listings.is_empty() {
+            return Err(String::from("no realestate.co.nz listing found in message"));
+        }
+
+        Ok(Self { listings })
+    }
+}
+
+#[async_trait]
+impl<'a> MessageReactor<'a> for RealEstateDistance {
+    fn name() -> &'static str {
+        "realestate"
+    }
+
+    fn description() -> &'static str {
+        "Create a thread with commute times for realestate.co.nz rental listings"
+    }
+
+    fn precheck(message: &Message) -> bool {
+        !message.author.bot && message.content.contains("realestate.co.nz/")
+    }
+
+    async fn process(self, message: &Message, app_state: &AppState, ctx: &Context) {
+        let config = match message.guild_id {
+            Some(guild_id) => match app_state.get_guild_config(guild_id.0.into()).await {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("unable to load config for guild {}: {}", guild_id, e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // listings posted outside of the listings channel are just being shared, not considered
+        if let Some(channel) = config.as_ref().and_then(|c| c.listings_channel) {
+            if u64::from(message.channel_id) != channel as u64 {
+                debug!("ignoring listing posted outside of the listings channel");
+                return;
+            }
+        }
+
+        // loading a listing can take a while, so the message is marked as being worked on
+        let progress = Progress::start(message.channel_id, message.id, ctx).await;
+
+        let mut threaded = false;
+        let mut failed = false;
+        for (i, (id, url)) in self.listings.into_iter().enumerate() {
+            match process_listing(id, url, i == 0, config.as_ref(), message, app_state, ctx).await {
+                ListingOutcome::Threaded => threaded = true,
+                ListingOutcome::Reposted => {}
+                ListingOutcome::Failed => failed = true,
+            }
+        }
+        progress.finish(!failed, ctx).await;
+
+        if threaded {
+            for vote in VOTE_REACTIONS {
+                if let Err(e) = message
+                    .react(ctx, ReactionType::Unicode(vote.to_string()))
+                    .await
+                {
+                    error!("unable to add vote reaction {}: {}", vote, e);
+                }
+            }
+        }
+    }
+}
+
+/// what happened to a single listing in a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListingOutcome {
+    /// a thread was created for the listing
+    Threaded,
+    /// the listing was already posted in the channel, so the existing thread was linked
+    Reposted,
+    /// the listing couldn't be loaded, or its thread couldn't be created
+    Failed,
+}
+
+/// create a thread for a single listing, and post the distance embed into it
+///
+/// discord only allows a single thread to be created from a message, so only the first listing
+/// is threaded from the message itself, any others get a standalone thread in the same channel
+async fn process_listing(
+    id: u64,
+    url: String,
+    from_message: bool,
+    config: Option<&GuildConfigModel>,
+    message: &Message,
+    app_state: &AppState,
+    ctx: &Context,
+) -> ListingOutcome {
+    match app_state
+        .get_posted_listing(message.channel_id.into(), id)
+        .await
+    {
+        Ok(Some(existing)) => {
+            if let Err(e) = message.react(ctx, '🔁').await {
+                error!("unable to react to reposted listing {}: {}", id, e);
+            }
+            if let Err(e) = message
+                .reply(
+                    ctx,
+                    format!(
+                        "This listing was already posted, see <#{}>",
+                        existing.thread_id
+                    ),
+                )
+                .await
+            {
+                error!("unable to reply to reposted listing {}: {}", id, e);
+            }
+            return ListingOutcome::Reposted;
+        }
+        Ok(None) => {}
+        Err(e) => error!("unable to check if listing {} was posted: {}", id, e),
+    }
+
+    let timeout = Duration::from_secs(
+        config
+            .and_then(|config| config.listing_timeout)
+            .map(|timeout| timeout as u64)
+            .unwrap_or(CONFIG.listing_timeout),
+    );
+    let ttl = Duration::from_secs(CONFIG.listing_cache_ttl);
+    let (listing, cached) = match fetch_listing_cached(id, url, timeout, ttl).await {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("unable to load listing {}: {}", id, e);
+            let reply = match e {
+                ListingError::NotFound | ListingError::Removed => {
+                    // flag dead links so nobody else bothers clicking them
+                    if let Err(e) = message.react(ctx, '❌').await {
+                        error!("unable to react to removed listing {}: {}", id, e);
+                    }
+                    "This listing is no longer available"
+                }
+                ListingError::Refused(_) => {
+                    "realestate.co.nz is refusing requests from the bot right now, try again later"
+                }
+                _ => "Couldn't fetch listing details, try again",
+            };
+            if let Err(e) = message.reply(ctx, reply).await {
+                error!("unable to reply to listing {}: {}", id, e);
+            }
+            return ListingOutcome::Failed;
+        }
+    };
+
+    let name = thread_name(
+        &listing,
+        config.and_then(|config| config.thread_template.as_deref()),
+    );
+
+    let thread = if from_message {
+        message
+            .channel_id
+            .create_public_thread(ctx, message.id, CreateThread::new(name))
+            .await
+    } else {
+        message
+            .channel_id
+            .create_private_thread(ctx, CreateThread::new(name).kind(ChannelType::PublicThread))
+            .await
+    };
+
+    let thread = match thread {
+        Ok(thread) => thread,
+        Err(e) => {
+            error!("unable to create thread for listing {}: {}", listing.id, e);
+            if is_permission_error(&e) {
+                warn_missing_permission(message, "Create Public Threads", ctx).await;
+            }
+            return ListingOutcome::Failed;
+        }
+    };
+
+    let recorded = match app_state
+        .record_posted_listing(NewListing {
+            listing_id: listing.id,
+            url: &listing.url,
+            address: &listing.address,
+            channel_id: message.channel_id.into(),
+            message_id: message.id.into(),
+            thread_id: thread.id.into(),
+        })
+        .await
+    {
+        Ok(recorded) => Some(recorded),
+        Err(e) => {
+            error!("unable to record listing {}: {}", listing.id, e);
+            None
+        }
+    };
+
+    let response = match distance_embed(&listing, cached, config, app_state).await {
+        Some(embed) => CreateMessage::new().embed(embed),
+        None => CreateMessage::new().content(MISSING_DISTANCES),
+    };
+
+    if !from_message {
+        if let Err(e) = thread
+            .send_message(
+                ctx,
+                CreateMessage::new().content(format!("Posted in {}", message.link())),
+            )
+            .await
+        {
+            error!("unable to link listing {} to message: {}", listing.id, e);
+        }
+    }
+
+    match thread.send_message(ctx, response).await {
+        // the message is recorded so /refresh-distance can fill it in later
+        Ok(sent) => {
+            if let Some(recorded) = recorded {
+                if let Err(e) = app_state
+                    .set_listing_distance_message(recorded, sent.id.into())
+                    .await
+                {
+                    error!(
+                        "unable to record distances for listing {}: {}",
+                        listing.id, e
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            error!("unable to send distances for listing {}: {}", listing.id, e);
+            if is_permission_error(&e) {
+                warn_missing_permission(message, "Send Messages in Threads", ctx).await;
+            }
+        }
+    }
+
+    ListingOutcome::Threaded
+}
+
+/// star the threads of any listings on the reacted message once they have enough 👍 votes
+pub async fn tally_listing_votes(reaction: &Reaction, app_state: &AppState, ctx: &Context) {
+    if reaction.emoji != ReactionType::Unicode(VOTE_REACTIONS[0].to_string()) {
+        return;
+    }
+
+    let listings = match app_state
+        .get_listings_by_message_id(reaction.message_id.into())
+        .await
+    {
+        Ok(listings) if !listings.is_empty() => listings,
+        Ok(_) => return,
+        Err(e) => {
+            error!("unable to load listings for reaction: {}", e);
+            return;
+        }
+    };
+
+    let message = match reaction.message(ctx).await {
+        Ok(message) => message,
+        Err(e) => {
+            error!("unable to load message for reaction: {}", e);
+            return;
+        }
+    };
+
+    // the bot's own reaction is not a vote
+    let votes = message
+        .reactions
+        .iter()
+        .find(|r| r.reaction_type == reaction.emoji)
+        .map(|r| r.count - r.me as u64)
+        .unwrap_or_default();
+
+    if votes < CONFIG.listing_vote_threshold {
+        return;
+    }
+
+    for listing in listings {
+        let thread = ChannelId::new(listing.thread_id as u64);
+
+        let name = match thread.to_channel(ctx).await.map(|c| c.guild()) {
+            Ok(Some(channel)) => channel.name,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("unable to load thread for listing {}: {}", listing.id, e);
+                continue;
+            }
+        };
+
+        if name.starts_with(STAR_PREFIX) {
+            continue;
+        }
+
+        // thread names are limited to 100 characters
+        let name = format!("{}{}", STAR_PREFIX, name)
+            .chars()
+            .take(100)
+            .collect::<String>();
+
+        if let Err(e) = thread.edit_thread(ctx, EditThread::new().name(name)).await {
+            error!("unable to star thread for listing {}: {}", listing.id, e);
+        }
+    }
+}
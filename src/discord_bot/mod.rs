@@ -2,8 +2,8 @@
 //! The bot is built on top of the Serenity discord crate.
 
 mod commands;
-mod common;
-mod database;
+pub(crate) mod common;
+pub(crate) mod database;
 mod filters;
 mod guilds;
 mod handler;
@@ -0,0 +1,175 @@
+//! A database backed cache of google maps responses, to avoid repeatedly requesting the same address
+
+use std::time::Duration;
+
+use chrono::Local;
+use entity::maps_cache::{
+    ActiveModel as MapsCacheActiveModel, Column as MapsCacheColumn, Entity as MapsCacheEntity,
+};
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::state::{DatabaseHandle, Destination};
+
+use super::maps::{GoogleMapsData, TravelMode};
+
+/// how long a cached response remains valid for
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+pub type CacheResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+/// abbreviations of street types, which are only expanded when they follow the name of a street
+/// so that places like "St Heliers" are left alone
+const STREET_TYPES: [(&str, &str); 16] = [
+    ("st", "street"),
+    ("rd", "road"),
+    ("ave", "avenue"),
+    ("av", "avenue"),
+    ("dr", "drive"),
+    ("pl", "place"),
+    ("cres", "crescent"),
+    ("cr", "crescent"),
+    ("tce", "terrace"),
+    ("ln", "lane"),
+    ("hwy", "highway"),
+    ("pde", "parade"),
+    ("cl", "close"),
+    ("ct", "court"),
+    ("sq", "square"),
+    ("gr", "grove"),
+];
+
+/// abbreviations which always mean the same thing wherever they appear in an address
+const ABBREVIATIONS: [(&str, &str); 3] = [("mt", "mount"), ("nth", "north"), ("sth", "south")];
+
+/// words which introduce a unit number, e.g. "unit 2, 12 smith street"
+const UNIT_PREFIXES: [&str; 4] = ["unit", "flat", "apt", "apartment"];
+
+/// normalise an address so that differently formatted spellings of the same place share a cache
+/// entry, e.g. "Unit 2, 12 Smith St" and "2/12 smith street" both become "2/12 smith street"
+pub fn normalise_address(address: &str) -> String {
+    let address = address
+        .to_lowercase()
+        .replace(',', " , ")
+        .replace('.', " ")
+        .replace('/', " / ");
+
+    let mut words: Vec<&str> = Vec::new();
+    // the previous word, if it was part of the name of a street rather than a number
+    let mut after_name = false;
+    let mut tokens = address.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "," {
+            after_name = false;
+            continue;
+        }
+
+        // units are always written before the street number, separated by a slash
+        if UNIT_PREFIXES.contains(&token) {
+            if let Some(unit) = tokens.next() {
+                words.push(unit);
+                words.push("/");
+            }
+            after_name = false;
+            continue;
+        }
+
+        let expanded = ABBREVIATIONS
+            .iter()
+            .chain(STREET_TYPES.iter().filter(|_| after_name))
+            .find(|(short, _)| *short == token)
+            .map(|(_, long)| *long)
+            .unwrap_or(token);
+
+        words.push(expanded);
+        after_name = expanded != "/" && !expanded.chars().any(|c| c.is_ascii_digit());
+    }
+
+    words.join(" ").replace(" / ", "/")
+}
+
+/// build the key used to identify a set of destinations in the cache
+fn destinations_key(destinations: &[Destination]) -> String {
+    destinations
+        .iter()
+        .map(|d| normalise_address(&d.address))
+        .collect::<Vec<String>>()
+        .join("|")
+}
+
+#[derive(Clone)]
+pub struct MapsCache {
+    database: DatabaseHandle,
+    ttl: Duration,
+}
+
+impl MapsCache {
+    pub fn new(database: DatabaseHandle) -> Self {
+        Self {
+            database,
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// load a previously cached response, if one exists and has not expired
+    pub async fn get(
+        &self,
+        origin: &str,
+        destinations: &[Destination],
+        mode: TravelMode,
+    ) -> CacheResult<Option<GoogleMapsData>> {
+        let oldest = Local::now().naive_local() - chrono::Duration::from_std(self.ttl)?;
+
+        let entry = MapsCacheEntity::find()
+            .filter(MapsCacheColumn::Address.eq(normalise_address(origin)))
+            .filter(MapsCacheColumn::Mode.eq(mode.as_str()))
+            .filter(MapsCacheColumn::Destinations.eq(destinations_key(destinations)))
+            .filter(MapsCacheColumn::CreatedAt.gt(oldest))
+            .order_by_desc(MapsCacheColumn::CreatedAt)
+            .one(&*self.database.connection())
+            .await?;
+
+        match entry {
+            Some(entry) => Ok(Some(serde_json::from_str(&entry.response)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// store a response in the cache, replacing any existing entry for the same request
+    pub async fn insert(
+        &self,
+        origin: &str,
+        destinations: &[Destination],
+        mode: TravelMode,
+        data: &GoogleMapsData,
+    ) -> CacheResult<()> {
+        let address = normalise_address(origin);
+        let destinations = destinations_key(destinations);
+
+        MapsCacheEntity::delete_many()
+            .filter(MapsCacheColumn::Address.eq(address.as_str()))
+            .filter(MapsCacheColumn::Mode.eq(mode.as_str()))
+            .filter(MapsCacheColumn::Destinations.eq(destinations.as_str()))
+            .exec(&*self.database.connection())
+            .await?;
+
+        MapsCacheActiveModel {
+            id: ActiveValue::NotSet,
+            address: ActiveValue::Set(address),
+            raw_address: ActiveValue::Set(Some(origin.to_string())),
+            mode: ActiveValue::Set(mode.as_str().to_string()),
+            destinations: ActiveValue::Set(destinations),
+            response: ActiveValue::Set(serde_json::to_string(data)?),
+            created_at: ActiveValue::Set(Local::now().naive_local()),
+        }
+        .insert(&*self.database.connection())
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for MapsCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapsCache").field("ttl", &self.ttl).finish()
+    }
+}
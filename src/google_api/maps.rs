@@ -1,21 +1,40 @@
-use std::time::Instant;
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
-use log::{debug, error};
+use log::{debug, error, warn};
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::{Receiver, Sender};
 
-use crate::state::Destination;
+use crate::{
+    state::{DatabaseHandle, Destination, CONFIG},
+    timeout::{with_timeout, Timeout},
+};
+
+use super::cache::MapsCache;
 
 const API_URL: &str = "https://maps.googleapis.com/maps/api/distancematrix/json";
 
+/// the maximum number of times a rate limited request will be attempted
+const MAX_ATTEMPTS: u32 = 4;
+/// the delay before the first retry of a rate limited request, doubled for every subsequent attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// how long requests are refused for once the daily api limit has been reached
+const API_LIMIT_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
 pub type GoogleMapApiResponse = Result<GoogleMapsData, GoogleMapError>;
 
 #[derive(Debug)]
 pub enum GoogleMapError {
     NetworkError,
     APILimitReached,
+    /// the api is throttling requests, and did not recover after retrying with backoff
+    RateLimited,
     InvalidAddress,
+    /// the api did not respond within the configured timeout
+    Timeout,
     // Unknown(String),
 }
 
@@ -23,8 +42,10 @@ impl std::fmt::Display for GoogleMapError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::APILimitReached => write!(f, "API limit reached"),
+            Self::RateLimited => write!(f, "Rate limited"),
             Self::InvalidAddress => write!(f, "Invalid address"),
             Self::NetworkError => write!(f, "Network error"),
+            Self::Timeout => write!(f, "Timed out"),
             // Self::Unknown(s) => write!(f, "Unknown error: {}", s),
         }
     }
@@ -32,7 +53,21 @@ impl std::fmt::Display for GoogleMapError {
 
 impl std::error::Error for GoogleMapError {}
 
-#[derive(Deserialize, Debug, Clone)]
+impl From<Timeout> for GoogleMapError {
+    fn from(_: Timeout) -> Self {
+        Self::Timeout
+    }
+}
+
+/// the outcome of the most recent request made to the api, shared between the worker and its handles
+#[derive(Debug, Clone, Default)]
+struct MapsHealth {
+    last_success: Option<Instant>,
+    /// when the last failed request was made, and why it failed
+    last_error: Option<(Instant, String)>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleMapsData {
     pub destination_addresses: Vec<String>,
     pub origin_addresses: Vec<String>,
@@ -40,34 +75,70 @@ pub struct GoogleMapsData {
     pub status: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleMapsRow {
     pub elements: Vec<GoogleMapsElement>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleMapsElement {
-    pub distance: GoogleMapsDistance,
-    pub duration: GoogleMapsDuration,
+    /// not present when the destination could not be routed to
+    pub distance: Option<GoogleMapsDistance>,
+    /// not present when the destination could not be routed to
+    pub duration: Option<GoogleMapsDuration>,
     pub status: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleMapsDistance {
     pub text: String,
     pub value: u32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleMapsDuration {
     pub text: String,
     pub value: u32,
 }
 
+/// the mode of transport to calculate distances and travel times for
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TravelMode {
+    #[default]
+    Driving,
+    Walking,
+    Bicycling,
+    Transit,
+}
+
+impl TravelMode {
+    /// the value google expects in the `mode` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Driving => "driving",
+            Self::Walking => "walking",
+            Self::Bicycling => "bicycling",
+            Self::Transit => "transit",
+        }
+    }
+
+    /// a short human readable description of the mode, for use in embeds
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Driving => "drive",
+            Self::Walking => "walk",
+            Self::Bicycling => "cycle",
+            Self::Transit => "transit",
+        }
+    }
+}
+
 #[derive(Debug)]
 struct GoogleMapsRequest {
     origin: String,
     destinations: Vec<Destination>,
+    mode: TravelMode,
     sender: tokio::sync::oneshot::Sender<GoogleMapApiResponse>,
 }
 
@@ -94,7 +165,9 @@ impl MapsApiBuilder {
             key: self.key.unwrap(),
             internal_receiver: rx,
             internal_sender: tx,
-            timeout: None,
+            limited_until: None,
+            request_timeout: Duration::from_secs(CONFIG.maps_timeout),
+            health: Arc::new(RwLock::new(MapsHealth::default())),
         }
     }
 }
@@ -105,7 +178,11 @@ pub struct GoogleMapsApi {
     key: String,
     internal_receiver: Receiver<GoogleMapsRequest>,
     internal_sender: Sender<GoogleMapsRequest>,
-    timeout: Option<Instant>,
+    /// requests are refused until this time, set when the daily api limit is reached
+    limited_until: Option<Instant>,
+    /// how long to wait for the api to respond to each request
+    request_timeout: Duration,
+    health: Arc<RwLock<MapsHealth>>,
 }
 
 impl GoogleMapsApi {
@@ -117,19 +194,22 @@ impl GoogleMapsApi {
         &mut self,
         origin: &str,
         destinations: Vec<Destination>,
+        mode: TravelMode,
     ) -> GoogleMapApiResponse {
         //TODO create a check to see if this request has previously been processed
 
-        if let Some(timeout) = self.timeout {
-            if timeout > Instant::now() {
+        if let Some(limited_until) = self.limited_until {
+            if limited_until > Instant::now() {
                 return Err(GoogleMapError::APILimitReached);
             }
-            self.timeout = None;
+            self.limited_until = None;
         }
 
         let url: String = {
             let mut url = String::from(API_URL);
             url.push_str("?units=metric");
+            url.push_str("&mode=");
+            url.push_str(mode.as_str());
             url.push_str("&key=");
             url.push_str(&self.key);
             url.push_str("&origins=");
@@ -156,11 +236,16 @@ impl GoogleMapsApi {
         if !response.status().is_success() {
             // check if we are rate limited
             if response.status() == 403 {
-                self.timeout = Some(Instant::now() + std::time::Duration::from_secs(60 * 60));
+                self.limited_until = Some(Instant::now() + API_LIMIT_BACKOFF);
                 debug!("API limit reached");
                 return Err(GoogleMapError::APILimitReached);
             }
 
+            if response.status() == 429 {
+                debug!("API rate limited");
+                return Err(GoogleMapError::RateLimited);
+            }
+
             error!(
                 "Google Maps API returned error: {}\nbody:{}",
                 response.status(),
@@ -175,15 +260,36 @@ impl GoogleMapsApi {
             .await
             .map_err(|_| GoogleMapError::InvalidAddress)?;
 
+        // google reports throttling in the body of an otherwise successful response
+        if data.status == "OVER_QUERY_LIMIT" {
+            debug!("API rate limited");
+            return Err(GoogleMapError::RateLimited);
+        }
+
         Ok(data)
     }
 
     pub async fn run(&mut self) {
         loop {
             let request = self.internal_receiver.recv().await.unwrap();
-            let response = self
-                .get_distance(&request.origin, request.destinations)
-                .await;
+            // requests are made one at a time, so one which never completes would stall every
+            // request queued behind it
+            let response = with_timeout(
+                "google maps",
+                self.request_timeout,
+                self.get_distance(&request.origin, request.destinations, request.mode),
+            )
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+
+            {
+                let mut health = self.health.write().unwrap();
+                match &response {
+                    Ok(_) => health.last_success = Some(Instant::now()),
+                    Err(e) => health.last_error = Some((Instant::now(), e.to_string())),
+                }
+            }
+
             request.sender.send(response).unwrap();
         }
     }
@@ -191,6 +297,8 @@ impl GoogleMapsApi {
     pub fn handle(&self) -> GoogleMapsApiHandle {
         GoogleMapsApiHandle {
             internal_sender: self.internal_sender.clone(),
+            cache: None,
+            health: self.health.clone(),
         }
     }
 }
@@ -198,19 +306,106 @@ impl GoogleMapsApi {
 #[derive(Debug, Clone)]
 pub struct GoogleMapsApiHandle {
     internal_sender: Sender<GoogleMapsRequest>,
+    cache: Option<MapsCache>,
+    health: Arc<RwLock<MapsHealth>>,
 }
 
 impl GoogleMapsApiHandle {
+    /// check if the api is usable, based on the outcome of the most recent request.
+    /// returns how long ago the last successful request was made, if there has been one
+    pub fn health(&self) -> Result<Option<Duration>, String> {
+        if self.internal_sender.is_closed() {
+            return Err(String::from("worker is not running"));
+        }
+
+        let health = self.health.read().unwrap();
+        match (&health.last_success, &health.last_error) {
+            (Some(success), Some((error_at, _))) if success > error_at => {
+                Ok(Some(success.elapsed()))
+            }
+            (_, Some((_, error))) => Err(error.clone()),
+            (success, None) => Ok(success.map(|s| s.elapsed())),
+        }
+    }
+
+    /// when the last successful request to the api was made, if there has been one
+    pub fn last_success(&self) -> Option<Instant> {
+        self.health.read().unwrap().last_success
+    }
+
+    /// cache responses in the provided database, so repeated requests for an address don't hit the api
+    pub fn with_cache(mut self, database: DatabaseHandle) -> Self {
+        self.cache = Some(MapsCache::new(database));
+        self
+    }
+
+    /// bypass the cache entirely, every request will be sent to the api
+    #[allow(dead_code)]
+    pub fn without_cache(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+
+    /// get the distance from an origin to a list of destinations, using a cached response if available
+    pub async fn get_distance(
+        &self,
+        origin: String,
+        destinations: &[Destination],
+        mode: TravelMode,
+    ) -> GoogleMapApiResponse {
+        if let Some(cache) = &self.cache {
+            match cache.get(&origin, destinations, mode).await {
+                Ok(Some(data)) => {
+                    debug!("using cached maps response for {}", origin);
+                    return Ok(data);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("failed to read from maps cache: {}", e),
+            }
+        }
+
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+        let data = loop {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            self.add_to_queue(origin.clone(), destinations, mode, tx)
+                .await;
+
+            match rx.await.map_err(|_| GoogleMapError::NetworkError)? {
+                Err(GoogleMapError::RateLimited) if attempt < MAX_ATTEMPTS => {
+                    warn!(
+                        "google maps rate limited, retrying in {:?} (attempt {}/{})",
+                        backoff, attempt, MAX_ATTEMPTS
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => break result?,
+            }
+        };
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&origin, destinations, mode, &data).await {
+                warn!("failed to write to maps cache: {}", e);
+            }
+        }
+
+        Ok(data)
+    }
+
     pub async fn add_to_queue(
         &self,
         origin: String,
         destinations: &[Destination],
+        mode: TravelMode,
         return_channel: tokio::sync::oneshot::Sender<GoogleMapApiResponse>,
     ) {
         self.internal_sender
             .send(GoogleMapsRequest {
                 origin,
                 destinations: destinations.to_vec(),
+                mode,
                 sender: return_channel,
             })
             .await
@@ -1,8 +1,11 @@
-use std::{convert::Infallible, sync::atomic::Ordering, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
 use warp::Filter;
 
-use crate::state::AppState;
+use crate::state::{env_or, AppState};
+
+/// the port prometheus metrics are served on, unless METRICS_PORT is set
+const DEFAULT_METRICS_PORT: u16 = 9000;
 
 #[derive(Debug)]
 pub struct HealthcheckBuilder {
@@ -15,15 +18,21 @@ impl HealthcheckBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<Healthcheck, Infallible> {
+    pub async fn build(self) -> Result<Healthcheck, String> {
         let state = self.state.expect("state must be set");
-        Ok(Healthcheck { state })
+        let metrics_port = env_or("METRICS_PORT", DEFAULT_METRICS_PORT)?;
+        Ok(Healthcheck {
+            state,
+            metrics_port,
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct Healthcheck {
     state: AppState,
+    /// the port the prometheus `/metrics` endpoint is served on
+    metrics_port: u16,
 }
 
 impl Healthcheck {
@@ -34,7 +43,8 @@ impl Healthcheck {
     pub async fn run(&mut self) {
         // create a simple warp webserver on port 3000
         // that returns a 200 if the state is healthy
-        // and a 500 if the state is unhealthy
+        // and a 500 if the state is unhealthy, alongside
+        // a prometheus metrics endpoint on the metrics port
 
         let start_time = self.state.start_time;
         let num_connected = self.state.num_connected.clone();
@@ -55,8 +65,20 @@ impl Healthcheck {
             warp::reply::with_status("OK", warp::http::StatusCode::OK)
         });
 
-        let server = warp::serve(healthcheck);
+        let metrics = self.state.metrics.clone();
+        let metrics = warp::path!("metrics").and(warp::get()).map(move || {
+            warp::reply::with_header(
+                metrics.render(),
+                "content-type",
+                "text/plain; version=0.0.4",
+            )
+        });
 
-        server.bind(([0, 0, 0, 0], 3000)).await;
+        // metrics are served on their own port so that they need not be exposed alongside the
+        // healthcheck
+        tokio::join!(
+            warp::serve(healthcheck).bind(([0, 0, 0, 0], 3000)),
+            warp::serve(metrics).bind(([0, 0, 0, 0], self.metrics_port)),
+        );
     }
 }
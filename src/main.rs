@@ -4,7 +4,9 @@ mod google_api;
 mod healthcheck;
 
 mod logging;
+mod metrics;
 mod state;
+mod timeout;
 
 use log::{error, info};
 use std::process::exit;
@@ -63,6 +65,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("discord bot shut down");
     });
 
+    info!("spawning database monitor");
+    let database = state.database.clone();
+    let database_handle = tokio::task::spawn(async move {
+        database.monitor().await;
+    });
+
     info!("creating healthcheck server");
     let healthcheck_state = state.clone();
     let healthcheck_handle = tokio::task::spawn(async move {
@@ -86,15 +94,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tokio::pin!(discord_handle);
     tokio::pin!(google_maps_thread_handle);
     tokio::pin!(healthcheck_handle);
+    tokio::pin!(database_handle);
 
     loop {
         tokio::select! {
             biased;
-            _ = tokio::signal::ctrl_c() => {
-                info!("received ctrl-c, shutting down");
-                break;
-            }
-
+            // the discord handler listens for SIGINT and SIGTERM itself, and exits once every
+            // guild has shut down cleanly
             _ = &mut discord_handle => {
                 info!("discord handler shut down");
                 break;
@@ -109,6 +115,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 info!("healthcheck server shut down");
                 break;
             }
+
+            _ = database_handle => {
+                info!("database monitor shut down");
+                break;
+            }
         }
     }
 
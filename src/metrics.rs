@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+/// the upper bounds of the command latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 10] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// usage of a single command since the bot started
+#[derive(Debug, Default, Clone)]
+struct CommandMetrics {
+    invocations: u64,
+    errors: u64,
+    /// the number of invocations which completed within each of the `LATENCY_BUCKETS`
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+}
+
+/// counts how often each command is used, how often it fails, and how long it takes, so that
+/// they can be scraped by prometheus
+#[derive(Debug, Default)]
+pub struct Metrics {
+    commands: Mutex<BTreeMap<&'static str, CommandMetrics>>,
+}
+
+impl Metrics {
+    /// record a single invocation of a command, and whether it succeeded
+    pub fn record_command(&self, command: &'static str, latency: Duration, success: bool) {
+        let mut commands = self.commands.lock().unwrap();
+        let metrics = commands.entry(command).or_default();
+
+        let seconds = latency.as_secs_f64();
+        metrics.invocations += 1;
+        if !success {
+            metrics.errors += 1;
+        }
+        metrics.latency_sum += seconds;
+        for (count, bound) in metrics.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+    }
+
+    /// render every metric in the prometheus text exposition format
+    pub fn render(&self) -> String {
+        let commands = self.commands.lock().unwrap().clone();
+        let mut out = String::new();
+
+        // writing to a string can't fail, so the results are ignored
+        let _ = writeln!(
+            out,
+            "# HELP tombot_command_invocations_total Number of times each command has been used"
+        );
+        let _ = writeln!(out, "# TYPE tombot_command_invocations_total counter");
+        for (name, metrics) in commands.iter() {
+            let _ = writeln!(
+                out,
+                "tombot_command_invocations_total{{command=\"{}\"}} {}",
+                name, metrics.invocations
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP tombot_command_errors_total Number of times each command has failed"
+        );
+        let _ = writeln!(out, "# TYPE tombot_command_errors_total counter");
+        for (name, metrics) in commands.iter() {
+            let _ = writeln!(
+                out,
+                "tombot_command_errors_total{{command=\"{}\"}} {}",
+                name, metrics.errors
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP tombot_command_duration_seconds How long each command took to handle"
+        );
+        let _ = writeln!(out, "# TYPE tombot_command_duration_seconds histogram");
+        for (name, metrics) in commands.iter() {
+            for (count, bound) in metrics.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "tombot_command_duration_seconds_bucket{{command=\"{}\",le=\"{}\"}} {}",
+                    name, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "tombot_command_duration_seconds_bucket{{command=\"{}\",le=\"+Inf\"}} {}",
+                name, metrics.invocations
+            );
+            let _ = writeln!(
+                out,
+                "tombot_command_duration_seconds_sum{{command=\"{}\"}} {}",
+                name, metrics.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "tombot_command_duration_seconds_count{{command=\"{}\"}} {}",
+                name, metrics.invocations
+            );
+        }
+
+        out
+    }
+}
@@ -1,75 +1,479 @@
 use std::{
+    collections::HashMap,
     error::Error,
-    sync::{atomic::AtomicU64, Arc, RwLock},
-    time::Duration,
+    str::FromStr,
+    sync::{atomic::AtomicU64, Arc, Mutex, RwLock},
+    time::{Duration, Instant},
 };
 
 use lazy_static::lazy_static;
-use log::info;
+use log::{error, info, warn};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr,
+    Statement,
+};
 use serde::Deserialize;
 use serenity::prelude::TypeMapKey;
 
-use crate::google_api::maps::GoogleMapsApiHandle;
+use crate::{
+    discord_bot::{
+        common::distance::Coordinates,
+        database::{
+            flatmates::{FlatmateModel, NewFlatmate, SerenityFlatmateDatabase},
+            DatabaseResult,
+        },
+    },
+    google_api::maps::{GoogleMapsApiHandle, TravelMode},
+    metrics::Metrics,
+};
 
 #[derive(Deserialize)]
 pub struct TomlConfig {
+    /// the account bills are paid into by default, overridden by `HEAD_TENANT_ACC_NUMBER` if set
+    #[serde(default)]
     pub head_tennant_acc_number: String,
+    #[serde(default = "default_destinations")]
     pub destinations: Vec<Destination>,
+    #[serde(default = "default_travel_modes")]
+    pub travel_modes: Vec<TravelMode>,
+    /// how long to wait for a listing page to load, in seconds
+    #[serde(default = "default_listing_timeout")]
+    pub listing_timeout: u64,
+    /// how long to wait for each request to google maps, in seconds
+    #[serde(default = "default_maps_timeout")]
+    pub maps_timeout: u64,
+    /// how long a loaded listing is reused for when it is posted again, in seconds, 0 disables
+    /// the cache
+    #[serde(default = "default_listing_cache_ttl")]
+    pub listing_cache_ttl: u64,
+    /// the least time between requests to realestate.co.nz, in milliseconds, so the bot isn't
+    /// blocked for scraping. Requests beyond this rate wait their turn
+    #[serde(default = "default_listing_request_interval")]
+    pub listing_request_interval: u64,
+    /// the number of 👍 votes a listing needs before its thread is starred
+    #[serde(default = "default_listing_vote_threshold")]
+    pub listing_vote_threshold: u64,
+    /// the largest share of the flat's combined weekly income the rent of a listing can be while
+    /// still being considered affordable
+    #[serde(default = "default_affordability_ratio")]
+    pub affordability_ratio: f64,
+    /// the most interactions each guild handles at once, any more wait for a free slot
+    #[serde(default = "default_max_concurrent_interactions")]
+    pub max_concurrent_interactions: usize,
+    /// how long every successful command is kept in the audit log for, in days
+    #[serde(default = "default_audit_log_retention")]
+    pub audit_log_retention: u64,
+    /// the id of the role allowed to use admin only commands, in addition to the guild owner
+    #[serde(default)]
+    pub admin_role: Option<u64>,
+    /// the channel the weekly chore rota is posted in, chores are not announced if unset
+    #[serde(default)]
+    pub chore_channel: Option<u64>,
+    /// the channel the weekly spending summary is posted in, the summary is not posted if unset
+    #[serde(default)]
+    pub summary_channel: Option<u64>,
+    /// the day of the week the spending summary is posted on, e.g. `sunday`
+    #[serde(default = "default_summary_day")]
+    pub summary_day: String,
+    /// the hour of the day, from 0 to 23, after which the spending summary is posted
+    #[serde(default = "default_summary_hour")]
+    pub summary_hour: u32,
+    /// the categories a bill can be filed under with /pay, bills without one are uncategorized
+    #[serde(default = "default_bill_categories")]
+    pub bill_categories: Vec<String>,
+    /// where /feedback is sent, either a discord webhook or any endpoint accepting a json post.
+    /// Overridden by `FEEDBACK_WEBHOOK_URL` if set, /feedback is unavailable if neither is
+    #[serde(default)]
+    pub feedback_webhook: Option<String>,
+    /// the flatmates used to seed the roster the first time the bot starts, after which the
+    /// roster is read from the database
+    #[serde(default)]
     pub flatmates: Vec<Flatmate>,
     pub phrases: Vec<String>,
     pub powered_by: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Flatmate {
     pub discord_id: u64,
     pub name: String,
     pub display_name: String,
+    /// the flatmate's weekly income in cents, used to check whether listings are affordable
+    #[serde(skip)]
+    pub weekly_income: Option<i64>,
+}
+
+impl From<FlatmateModel> for Flatmate {
+    fn from(model: FlatmateModel) -> Self {
+        Self {
+            discord_id: model.discord_id as u64,
+            name: model.name,
+            display_name: model.display_name,
+            weekly_income: model.weekly_income,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Destination {
     pub label: String,
     pub address: String,
+    /// the location of the destination, used to estimate distances when google maps is unavailable
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+impl Destination {
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        Some(Coordinates {
+            latitude: self.latitude?,
+            longitude: self.longitude?,
+        })
+    }
+}
+
+/// the destinations used when none are configured, so that distance embeds always have something to show
+fn default_destinations() -> Vec<Destination> {
+    vec![Destination {
+        label: String::from("Auckland CBD"),
+        address: String::from("Auckland CBD, Auckland"),
+        latitude: Some(-36.8485),
+        longitude: Some(174.7633),
+    }]
+}
+
+/// the travel modes shown in distance embeds when none are configured
+fn default_travel_modes() -> Vec<TravelMode> {
+    vec![TravelMode::Driving]
+}
+
+/// the listing timeout used when none is configured
+fn default_listing_timeout() -> u64 {
+    90
+}
+
+fn default_maps_timeout() -> u64 {
+    20
+}
+
+fn default_listing_cache_ttl() -> u64 {
+    60 * 60
+}
+
+fn default_listing_request_interval() -> u64 {
+    2000
+}
+
+fn default_audit_log_retention() -> u64 {
+    90
+}
+
+fn default_max_concurrent_interactions() -> usize {
+    32
+}
+
+/// the vote threshold used when none is configured
+fn default_listing_vote_threshold() -> u64 {
+    3
+}
+
+/// the affordability ratio used when none is configured, rent of at most 30% of income
+fn default_affordability_ratio() -> f64 {
+    0.3
+}
+
+/// the spending summary is posted on sunday evenings when no other time is configured
+fn default_summary_day() -> String {
+    String::from("sunday")
+}
+
+fn default_summary_hour() -> u32 {
+    18
+}
+
+/// the bill categories offered when none are configured
+fn default_bill_categories() -> Vec<String> {
+    ["groceries", "utilities", "rent", "fun"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// check an account number looks like a new zealand bank account, e.g. `12-3456-7890123-00`
+fn is_account_number(account: &str) -> bool {
+    let parts: Vec<&str> = account.split('-').collect();
+    parts.len() == 4
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
 }
 
 lazy_static! {
     pub static ref CONFIG: TomlConfig = {
-        let config = std::fs::read_to_string("config.toml").expect("Failed to read config.toml");
-        toml::from_str(&config).expect("Failed to parse config.toml")
+        // tests don't have a deployment's config, so they run against the example
+        let path = if cfg!(test) { "config.example.toml" } else { "config.toml" };
+        let config = std::fs::read_to_string(path).expect("Failed to read config.toml");
+        let mut config: TomlConfig = toml::from_str(&config).expect("Failed to parse config.toml");
+
+        if let Ok(account) = std::env::var("HEAD_TENANT_ACC_NUMBER") {
+            if !account.trim().is_empty() {
+                config.head_tennant_acc_number = account.trim().to_string();
+            }
+        }
+        if let Ok(webhook) = std::env::var("FEEDBACK_WEBHOOK_URL") {
+            if !webhook.trim().is_empty() {
+                config.feedback_webhook = Some(webhook.trim().to_string());
+            }
+        }
+        if !is_account_number(&config.head_tennant_acc_number) {
+            panic!(
+                "head tenant account number {:?} is not a valid account number, set it in config.toml or HEAD_TENANT_ACC_NUMBER",
+                config.head_tennant_acc_number
+            );
+        }
+
+        config
     };
 }
 
+/// read an optional environment variable, falling back to a default if it is not set and
+/// failing if it is set to something that can't be parsed
+pub(crate) fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} is set to {:?}, which is not valid", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// settings for the database connection pool, read from the environment
+struct PoolConfig {
+    max_connections: u32,
+    min_connections: u32,
+    connect_timeout: Duration,
+    idle_timeout: Duration,
+    max_lifetime: Duration,
+    /// how long to keep retrying the first connection, zero fails on the first attempt
+    startup_retry: Duration,
+}
+
+impl PoolConfig {
+    fn from_env() -> Result<Self, String> {
+        let config = Self {
+            max_connections: env_or("POSTGRES_MAX_CONNECTIONS", 100)?,
+            min_connections: env_or("POSTGRES_MIN_CONNECTIONS", 5)?,
+            connect_timeout: Duration::from_secs(env_or("POSTGRES_CONNECT_TIMEOUT", 8)?),
+            idle_timeout: Duration::from_secs(env_or("POSTGRES_IDLE_TIMEOUT", 8)?),
+            max_lifetime: Duration::from_secs(env_or("POSTGRES_MAX_LIFETIME", 8)?),
+            startup_retry: Duration::from_secs(env_or("POSTGRES_STARTUP_RETRY", 0)?),
+        };
+
+        if config.min_connections > config.max_connections {
+            return Err(format!(
+                "POSTGRES_MIN_CONNECTIONS ({}) is greater than POSTGRES_MAX_CONNECTIONS ({})",
+                config.min_connections, config.max_connections
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+/// the ways creating the app state can fail, so startup can decide whether it is worth retrying
+#[derive(Debug)]
+pub enum StateError {
+    /// unable to connect to the database
+    Connect(DbErr),
+    /// the database schema could not be migrated to the latest version
+    Migrate(DbErr),
+    /// a setting from the environment was invalid
+    Config(String),
+    /// the database was reachable, but loading the initial data from it failed
+    Database(Box<dyn Error + Send + Sync>),
+}
+
+impl std::fmt::Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect(e) => write!(f, "Failed to connect to database: {}", e),
+            Self::Migrate(e) => write!(f, "Failed to migrate database: {}", e),
+            Self::Config(e) => write!(f, "Invalid config: {}", e),
+            Self::Database(e) => write!(f, "Failed to load from database: {}", e),
+        }
+    }
+}
+
+impl Error for StateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Connect(e) | Self::Migrate(e) => Some(e),
+            Self::Config(_) => None,
+            Self::Database(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+impl From<String> for StateError {
+    fn from(e: String) -> Self {
+        Self::Config(e)
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for StateError {
+    fn from(e: Box<dyn Error + Send + Sync>) -> Self {
+        Self::Database(e)
+    }
+}
+
+/// how often the database connection is checked, so it can be re-established if it was lost
+const DATABASE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// the delay before the first reconnection attempt, doubled for every failed attempt
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+/// the longest delay between reconnection attempts
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// a connection to the database which can be re-established if the server goes away, clones
+/// share the same underlying connection
+#[derive(Clone)]
+pub struct DatabaseHandle {
+    connection: Arc<RwLock<Arc<DatabaseConnection>>>,
+    options: ConnectOptions,
+}
+
+impl DatabaseHandle {
+    async fn connect(options: ConnectOptions) -> Result<Self, DbErr> {
+        let connection = Database::connect(options.clone()).await?;
+        Ok(Self {
+            connection: Arc::new(RwLock::new(Arc::new(connection))),
+            options,
+        })
+    }
+
+    /// connect to the database, retrying with backoff until `window` has passed so the bot can
+    /// start alongside a database which is still coming up
+    async fn connect_with_retry(options: ConnectOptions, window: Duration) -> Result<Self, DbErr> {
+        let deadline = Instant::now() + window;
+        let mut backoff = INITIAL_RECONNECT_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match Self::connect(options.clone()).await {
+                Ok(handle) => return Ok(handle),
+                Err(e) if Instant::now() + backoff < deadline => {
+                    warn!(
+                        "failed to connect to database (attempt {}), retrying in {}s: {}",
+                        attempt,
+                        backoff.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// the current connection, this should be fetched for every query rather than held onto
+    pub fn connection(&self) -> Arc<DatabaseConnection> {
+        self.connection.read().unwrap().clone()
+    }
+
+    /// run a trivial query to check the database is still reachable
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        self.connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                String::from("SELECT 1"),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    /// replace the current connection with a new one, retrying with backoff until it succeeds
+    pub async fn reconnect(&self) {
+        let mut backoff = INITIAL_RECONNECT_BACKOFF;
+        loop {
+            match Database::connect(self.options.clone()).await {
+                Ok(connection) => {
+                    *self.connection.write().unwrap() = Arc::new(connection);
+                    info!("reconnected to database");
+                    return;
+                }
+                Err(e) => {
+                    warn!(
+                        "failed to reconnect to database, retrying in {}s: {}",
+                        backoff.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// periodically check the connection, reconnecting whenever it has been lost
+    pub async fn monitor(&self) {
+        loop {
+            tokio::time::sleep(DATABASE_CHECK_INTERVAL).await;
+            if let Err(e) = self.ping().await {
+                error!("lost connection to database: {}", e);
+                self.reconnect().await;
+            }
+        }
+    }
+}
+
 /// A connection to the database, representing the stored "state" of the app
 pub struct AppState {
     pub google_api: Arc<RwLock<GoogleMapsApiHandle>>,
 
-    pub database: Arc<DatabaseConnection>,
+    pub database: DatabaseHandle,
+
+    /// the current flatmates, loaded from the database
+    flatmates: Arc<RwLock<Vec<Flatmate>>>,
+    /// flatmates who have been removed, kept so that their names can still be shown on old bills
+    former_flatmates: Arc<RwLock<Vec<Flatmate>>>,
+
+    /// when each user's cooldown on a command ends, keyed by user id and command name
+    cooldowns: Arc<Mutex<HashMap<(u64, &'static str), Instant>>>,
 
     pub start_time: std::time::Instant,
     pub num_connected: Arc<AtomicU64>,
+    /// usage and latency of each command, served to prometheus
+    pub metrics: Arc<Metrics>,
 }
 
 impl AppState {
     pub async fn new(
         database_url: String,
         google_api: GoogleMapsApiHandle,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> Result<Self, StateError> {
+        let pool = PoolConfig::from_env()?;
+
         let mut opt = ConnectOptions::new(database_url);
-        opt.max_connections(100)
-            .min_connections(5)
-            .connect_timeout(Duration::from_secs(8))
-            .idle_timeout(Duration::from_secs(8))
-            .max_lifetime(Duration::from_secs(8))
+        opt.max_connections(pool.max_connections)
+            .min_connections(pool.min_connections)
+            .connect_timeout(pool.connect_timeout)
+            .idle_timeout(pool.idle_timeout)
+            .max_lifetime(pool.max_lifetime)
             .sqlx_logging(true)
             .sqlx_logging_level(log::LevelFilter::Info);
 
-        let connection = Database::connect(opt).await?;
+        let database = DatabaseHandle::connect_with_retry(opt, pool.startup_retry)
+            .await
+            .map_err(StateError::Connect)?;
 
         info!("starting database migration...");
-        Migrator::up(&connection, None).await?;
+        Migrator::up(&*database.connection(), None)
+            .await
+            .map_err(StateError::Migrate)?;
         info!("migration complete");
 
         // load CONFIG lazy_static here
@@ -77,19 +481,172 @@ impl AppState {
         let _ = *CONFIG; //IDK if this will load it
         info!("config loaded");
 
-        Ok(Self {
-            google_api: Arc::new(RwLock::new(google_api)),
+        let state = Self {
+            google_api: Arc::new(RwLock::new(google_api.with_cache(database.clone()))),
+
+            database,
 
-            database: Arc::new(connection),
+            flatmates: Arc::new(RwLock::new(Vec::new())),
+            former_flatmates: Arc::new(RwLock::new(Vec::new())),
+
+            cooldowns: Arc::new(Mutex::new(HashMap::new())),
 
             start_time: std::time::Instant::now(),
             num_connected: Arc::new(AtomicU64::new(0)),
-        })
+            metrics: Arc::new(Metrics::default()),
+        };
+
+        info!("loading flatmates...");
+        state.load_flatmates().await?;
+        info!("flatmates loaded");
+
+        Ok(state)
     }
 
     pub fn maps_api(&self) -> GoogleMapsApiHandle {
         self.google_api.read().unwrap().clone()
     }
+
+    /// start a user's cooldown on a command, returning how long is left if they are already on
+    /// cooldown
+    pub fn check_cooldown(
+        &self,
+        user_id: u64,
+        command: &'static str,
+        cooldown: Duration,
+    ) -> Result<(), Duration> {
+        check_cooldown_at(
+            &mut self.cooldowns.lock().unwrap(),
+            user_id,
+            command,
+            cooldown,
+            Instant::now(),
+        )
+    }
+
+    /// the current flatmates, in the order they were added
+    pub fn flatmates(&self) -> Vec<Flatmate> {
+        self.flatmates.read().unwrap().clone()
+    }
+
+    /// the names of the current flatmates in lowercase, as used for the per-flatmate options of
+    /// /pay. These change whenever the roster is reloaded, so must not be cached
+    pub fn flatmate_names(&self) -> Vec<String> {
+        self.flatmates
+            .read()
+            .unwrap()
+            .iter()
+            .map(|f| f.name.to_ascii_lowercase())
+            .collect()
+    }
+
+    /// every flatmate, including those who have been removed, for looking up names on old records
+    pub fn all_flatmates(&self) -> Vec<Flatmate> {
+        let mut flatmates = self.flatmates();
+        flatmates.extend(self.former_flatmates.read().unwrap().iter().cloned());
+        flatmates
+    }
+
+    /// load the flatmates from the database, seeding them from the config the first time the
+    /// bot is started
+    async fn load_flatmates(&self) -> DatabaseResult<()> {
+        if self.get_flatmates().await?.is_empty() {
+            info!(
+                "seeding {} flatmates from config...",
+                CONFIG.flatmates.len()
+            );
+            for flatmate in CONFIG.flatmates.iter() {
+                self.add_flatmate(NewFlatmate {
+                    discord_id: flatmate.discord_id,
+                    name: &flatmate.name,
+                    display_name: &flatmate.display_name,
+                })
+                .await?;
+            }
+        }
+
+        self.reload_flatmates().await
+    }
+
+    /// re-read the flatmates from the database, should be called whenever the roster is changed
+    pub async fn reload_flatmates(&self) -> DatabaseResult<()> {
+        let (current, former): (Vec<FlatmateModel>, Vec<FlatmateModel>) = self
+            .get_flatmates()
+            .await?
+            .into_iter()
+            .partition(|flatmate| flatmate.removed_at.is_none());
+
+        *self.flatmates.write().unwrap() = current.into_iter().map(Flatmate::from).collect();
+        *self.former_flatmates.write().unwrap() = former.into_iter().map(Flatmate::from).collect();
+        Ok(())
+    }
+}
+
+/// start a user's cooldown on a command as of `now`, returning how long is left if they are
+/// already on cooldown
+fn check_cooldown_at(
+    cooldowns: &mut HashMap<(u64, &'static str), Instant>,
+    user_id: u64,
+    command: &'static str,
+    cooldown: Duration,
+    now: Instant,
+) -> Result<(), Duration> {
+    // forget any cooldowns which have ended, so the map doesn't grow forever
+    cooldowns.retain(|_, ends| *ends > now);
+
+    if let Some(ends) = cooldowns.get(&(user_id, command)) {
+        return Err(*ends - now);
+    }
+
+    cooldowns.insert((user_id, command), now + cooldown);
+    Ok(())
+}
+
+#[cfg(test)]
+impl AppState {
+    /// a state backed by an empty in-memory sqlite database, holding the tables used by commands
+    /// and the flatmates from the example config
+    pub async fn for_tests() -> Self {
+        use crate::google_api::maps::GoogleMapsApi;
+        use sea_orm::Schema;
+
+        let mut opt = ConnectOptions::new(String::from("sqlite::memory:"));
+        // each connection to an in-memory database opens a fresh one, so only one is ever made
+        opt.max_connections(1).min_connections(1);
+        let database = DatabaseHandle::connect(opt)
+            .await
+            .expect("failed to open sqlite");
+
+        let connection = database.connection();
+        let backend = connection.get_database_backend();
+        let schema = Schema::new(backend);
+        for table in [
+            schema.create_table_from_entity(entity::bill::Entity),
+            schema.create_table_from_entity(entity::bill_share::Entity),
+            schema.create_table_from_entity(entity::guild_config::Entity),
+        ] {
+            connection
+                .execute(backend.build(&table))
+                .await
+                .expect("failed to create table");
+        }
+
+        let google_api = GoogleMapsApi::builder().key(String::new()).build().handle();
+        Self {
+            google_api: Arc::new(RwLock::new(google_api)),
+
+            database,
+
+            flatmates: Arc::new(RwLock::new(CONFIG.flatmates.clone())),
+            former_flatmates: Arc::new(RwLock::new(Vec::new())),
+
+            cooldowns: Arc::new(Mutex::new(HashMap::new())),
+
+            start_time: std::time::Instant::now(),
+            num_connected: Arc::new(AtomicU64::new(0)),
+            metrics: Arc::new(Metrics::default()),
+        }
+    }
 }
 
 impl std::fmt::Debug for AppState {
@@ -105,8 +662,14 @@ impl Clone for AppState {
 
             database: self.database.clone(),
 
+            flatmates: self.flatmates.clone(),
+            former_flatmates: self.former_flatmates.clone(),
+
+            cooldowns: self.cooldowns.clone(),
+
             start_time: self.start_time,
             num_connected: self.num_connected.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -114,3 +677,66 @@ impl Clone for AppState {
 impl TypeMapKey for AppState {
     type Value = AppState;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn reports_remaining_time_within_cooldown() {
+        let mut cooldowns = HashMap::new();
+        let start = Instant::now();
+
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 1, "pay", COOLDOWN, start),
+            Ok(())
+        );
+        assert_eq!(
+            check_cooldown_at(
+                &mut cooldowns,
+                1,
+                "pay",
+                COOLDOWN,
+                start + Duration::from_secs(10)
+            ),
+            Err(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn succeeds_exactly_at_expiry() {
+        let mut cooldowns = HashMap::new();
+        let start = Instant::now();
+
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 1, "pay", COOLDOWN, start),
+            Ok(())
+        );
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 1, "pay", COOLDOWN, start + COOLDOWN),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn users_and_commands_are_independent() {
+        let mut cooldowns = HashMap::new();
+        let start = Instant::now();
+
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 1, "pay", COOLDOWN, start),
+            Ok(())
+        );
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 2, "pay", COOLDOWN, start),
+            Ok(())
+        );
+        assert_eq!(
+            check_cooldown_at(&mut cooldowns, 1, "feedback", COOLDOWN, start),
+            Ok(())
+        );
+        assert!(check_cooldown_at(&mut cooldowns, 1, "pay", COOLDOWN, start).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::{future::Future, time::Duration};
+
+/// a call to a service outside the bot which didn't finish before its deadline
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout {
+    /// what was being waited on, e.g. `google maps`
+    pub operation: &'static str,
+    pub after: Duration,
+}
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} timed out after {}s",
+            self.operation,
+            self.after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// wait for a call to an external service, giving up once the deadline has passed so a service
+/// which never responds can't hang the task waiting on it. The future is dropped on timeout,
+/// cancelling the request
+pub async fn with_timeout<F: Future>(
+    operation: &'static str,
+    deadline: Duration,
+    future: F,
+) -> Result<F::Output, Timeout> {
+    tokio::time::timeout(deadline, future)
+        .await
+        .map_err(|_| Timeout {
+            operation,
+            after: deadline,
+        })
+}